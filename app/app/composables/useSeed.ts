@@ -0,0 +1,15 @@
+/**
+ * Seeds the random sequence of a module's node. Modules without a stored
+ * seed pick one and store it in the node data, so the patch renders the same
+ * way every time it is loaded.
+ */
+export const useSeed = (seed: number | undefined, setSeed: (seed: number) => void) => {
+  const { node: { id } } = useNode()
+  const { updateNodeData } = useVueFlow()
+
+  const value = seed ?? Math.floor(Math.random() * 2 ** 32)
+  setSeed(value)
+  if (seed === undefined) {
+    updateNodeData(id, { seed: value })
+  }
+}
@@ -136,7 +136,7 @@ export const useDataStore = defineStore('data', () => {
   async function exportPatch() {
     const obj = toObject()
     const data = {
-      version: 1,
+      version: PATCH_VERSION,
       name: currentPatchName.value,
       nodes: obj.nodes,
       edges: obj.edges,
@@ -160,10 +160,11 @@ export const useDataStore = defineStore('data', () => {
     catch {
       throw new Error('Invalid JSON')
     }
-    if (parsed.version !== 1 || !Array.isArray(parsed.nodes) || !Array.isArray(parsed.edges)) {
+    const version = parsed.version
+    if (typeof version !== 'number' || version < 1 || version > PATCH_VERSION || !Array.isArray(parsed.nodes) || !Array.isArray(parsed.edges)) {
       throw new Error('Invalid patch file format')
     }
-    pendingImportData.value = { nodes: parsed.nodes as Node[], edges: parsed.edges as Edge[], viewport: parsed.viewport as ViewportTransform | undefined }
+    pendingImportData.value = { nodes: migratePatchNodes(parsed.nodes as Node[], version), edges: parsed.edges as Edge[], viewport: parsed.viewport as ViewportTransform | undefined }
     currentPatchId.value = 'new'
     currentPatchName.value = (parsed.name as string) ?? 'Imported Patch'
     patchLoadStarted()
@@ -0,0 +1,23 @@
+import type { Node } from '@vue-flow/core'
+import { AudioModuleType } from './module'
+
+export const PATCH_VERSION = 2
+
+/**
+ * Brings the nodes of a patch saved with an older format version up to
+ * `PATCH_VERSION`, so it keeps sounding the same.
+ */
+export function migratePatchNodes(nodes: Node[], version: number): Node[] {
+  if (version < 2) {
+    // the phaser LFO used to advance once per channel and always ran in
+    // stereo, i.e. at twice the stored rate
+    nodes = nodes.map((node) => {
+      if (node.type !== AudioModuleType.Phaser) {
+        return node
+      }
+      const rate = Math.min(2 * (node.data?.rate ?? 0.5), 20)
+      return { ...node, data: { ...node.data, rate } }
+    })
+  }
+  return nodes
+}
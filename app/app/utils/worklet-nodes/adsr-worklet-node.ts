@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class ADSRWorkletNodeBase extends AudioWorkletNode {
   readonly attack: AudioParam
@@ -20,4 +20,4 @@ class ADSRWorkletNodeBase extends AudioWorkletNode {
     this.release = this.parameters.get('release')!
   }
 }
-export const ADSRWorkletNode = Destroyable(Resettable(ADSRWorkletNodeBase))
+export const ADSRWorkletNode = Destroyable(Reconfigurable(Resettable(ADSRWorkletNodeBase)))
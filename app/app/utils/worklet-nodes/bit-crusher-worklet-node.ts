@@ -1,9 +1,22 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
+
+export const enum Oversampling {
+  Off,
+  X2,
+  X4,
+  X8,
+}
+
+type ParamOptions = {
+  bits: number
+  oversampling: Oversampling
+}
 
 class BitCrusherWorkletNodeBase extends AudioWorkletNode {
   readonly bits: AudioParam
+  readonly oversampling: AudioParam
 
-  constructor(context: AudioContext, options?: Partial<{ bits: number }>) {
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'bit-crusher', {
       numberOfInputs: 1,
       numberOfOutputs: 1,
@@ -12,6 +25,7 @@ class BitCrusherWorkletNodeBase extends AudioWorkletNode {
     })
 
     this.bits = this.parameters.get('bits')!
+    this.oversampling = this.parameters.get('oversampling')!
   }
 }
-export const BitCrusherWorkletNode = Destroyable(Resettable(BitCrusherWorkletNodeBase))
+export const BitCrusherWorkletNode = Destroyable(Reconfigurable(Resettable(BitCrusherWorkletNodeBase)))
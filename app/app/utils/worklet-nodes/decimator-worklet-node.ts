@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class DecimatorWorkletNodeBase extends AudioWorkletNode {
   readonly reduction: AudioParam
@@ -16,4 +16,4 @@ class DecimatorWorkletNodeBase extends AudioWorkletNode {
     this.stereoShift = this.parameters.get('stereoShift')!
   }
 }
-export const DecimatorWorkletNode = Destroyable(Resettable(DecimatorWorkletNodeBase))
+export const DecimatorWorkletNode = Destroyable(Reconfigurable(Resettable(DecimatorWorkletNodeBase)))
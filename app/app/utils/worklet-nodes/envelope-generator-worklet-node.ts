@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class EnvelopeGeneratorWorkletNodeBase extends AudioWorkletNode {
   readonly attack: AudioParam
@@ -22,4 +22,4 @@ class EnvelopeGeneratorWorkletNodeBase extends AudioWorkletNode {
     this.applyVelocity = this.parameters.get('applyVelocity')!
   }
 }
-export const EnvelopeGeneratorWorkletNode = Destroyable(Resettable(EnvelopeGeneratorWorkletNodeBase))
+export const EnvelopeGeneratorWorkletNode = Destroyable(Reconfigurable(Resettable(EnvelopeGeneratorWorkletNodeBase)))
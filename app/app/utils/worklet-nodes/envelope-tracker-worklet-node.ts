@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class EnvelopeTrackerWorkletNodeBase extends AudioWorkletNode {
   constructor(context: AudioContext, options: { sensitivity?: number, threshold?: number } = {}) {
@@ -22,4 +22,4 @@ class EnvelopeTrackerWorkletNodeBase extends AudioWorkletNode {
   }
 }
 
-export const EnvelopeTrackerWorkletNode = Destroyable(Resettable(EnvelopeTrackerWorkletNodeBase))
+export const EnvelopeTrackerWorkletNode = Destroyable(Reconfigurable(Resettable(EnvelopeTrackerWorkletNodeBase)))
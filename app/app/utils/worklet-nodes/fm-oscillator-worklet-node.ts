@@ -1,19 +1,24 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 type ParamOptions = {
   frequency: number
   pitchShift: number
   phaseShift: number
+  fmIndex: number
 }
 
+/**
+ * Input 0 is the through-zero FM modulator, scaled by `fmIndex`.
+ */
 class FMOscillatorWorkletNodeBase extends AudioWorkletNode {
   readonly frequency: AudioParam
   readonly pitchShift: AudioParam
   readonly phaseShift: AudioParam
+  readonly fmIndex: AudioParam
 
   constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'fm-oscillator', {
-      numberOfInputs: 0,
+      numberOfInputs: 1,
       numberOfOutputs: 1,
       outputChannelCount: [2],
       parameterData: options,
@@ -22,7 +27,8 @@ class FMOscillatorWorkletNodeBase extends AudioWorkletNode {
     this.frequency = this.parameters.get('frequency')!
     this.pitchShift = this.parameters.get('pitchShift')!
     this.phaseShift = this.parameters.get('phaseShift')!
+    this.fmIndex = this.parameters.get('fmIndex')!
   }
 }
 
-export const FMOscillatorWorkletNode = Destroyable(Resettable(FMOscillatorWorkletNodeBase))
+export const FMOscillatorWorkletNode = Destroyable(Reconfigurable(Resettable(FMOscillatorWorkletNodeBase)))
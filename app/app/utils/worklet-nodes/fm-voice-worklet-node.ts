@@ -1,8 +1,50 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable, Seedable } from './mixins'
+
+export const enum FMVoiceMode {
+  Poly = 0,
+  Mono = 1,
+  Legato = 2,
+}
+
+export const enum FMStealMode {
+  Oldest = 0,
+  Quietest = 1,
+}
+
+export const enum FMEnvelopeMode {
+  Adsr = 0,
+  /** DX7 style four rates and four levels, 0-99 each. */
+  RateLevel = 1,
+}
+
+export const enum FMKeyScalingCurve {
+  NegativeLinear = 0,
+  NegativeExponential = 1,
+  PositiveExponential = 2,
+  PositiveLinear = 3,
+}
+
+export const enum FMLfoWaveform {
+  Triangle = 0,
+  SawDown = 1,
+  SawUp = 2,
+  Square = 3,
+  Sine = 4,
+  SampleAndHold = 5,
+}
+
+export type FMVoicePreset = {
+  name: string
+  params: Partial<Record<keyof ParamOptions, number>>
+}
+
+type SysexReply = { type: 'sysexParsed', id: number, presets?: FMVoicePreset[], error?: string }
 
 type ParamOptions = {
   frequency: number
   algorithm: number
+  feedback: number
+  envelopeMode: FMEnvelopeMode
   op1PitchShift: number
   op2PitchShift: number
   op3PitchShift: number
@@ -15,6 +57,12 @@ type ParamOptions = {
   op4FineTune: number
   op5FineTune: number
   op6FineTune: number
+  op1FixedFrequency: number
+  op2FixedFrequency: number
+  op3FixedFrequency: number
+  op4FixedFrequency: number
+  op5FixedFrequency: number
+  op6FixedFrequency: number
   op1Level: number
   op2Level: number
   op3Level: number
@@ -45,12 +93,128 @@ type ParamOptions = {
   op4Release: number
   op5Release: number
   op6Release: number
+  op1VelocitySensitivity: number
+  op2VelocitySensitivity: number
+  op3VelocitySensitivity: number
+  op4VelocitySensitivity: number
+  op5VelocitySensitivity: number
+  op6VelocitySensitivity: number
+  op1KeyBreakpoint: number
+  op2KeyBreakpoint: number
+  op3KeyBreakpoint: number
+  op4KeyBreakpoint: number
+  op5KeyBreakpoint: number
+  op6KeyBreakpoint: number
+  op1KeyLeftDepth: number
+  op2KeyLeftDepth: number
+  op3KeyLeftDepth: number
+  op4KeyLeftDepth: number
+  op5KeyLeftDepth: number
+  op6KeyLeftDepth: number
+  op1KeyRightDepth: number
+  op2KeyRightDepth: number
+  op3KeyRightDepth: number
+  op4KeyRightDepth: number
+  op5KeyRightDepth: number
+  op6KeyRightDepth: number
+  op1KeyLeftCurve: FMKeyScalingCurve
+  op2KeyLeftCurve: FMKeyScalingCurve
+  op3KeyLeftCurve: FMKeyScalingCurve
+  op4KeyLeftCurve: FMKeyScalingCurve
+  op5KeyLeftCurve: FMKeyScalingCurve
+  op6KeyLeftCurve: FMKeyScalingCurve
+  op1KeyRightCurve: FMKeyScalingCurve
+  op2KeyRightCurve: FMKeyScalingCurve
+  op3KeyRightCurve: FMKeyScalingCurve
+  op4KeyRightCurve: FMKeyScalingCurve
+  op5KeyRightCurve: FMKeyScalingCurve
+  op6KeyRightCurve: FMKeyScalingCurve
+  op1KeyRateScaling: number
+  op2KeyRateScaling: number
+  op3KeyRateScaling: number
+  op4KeyRateScaling: number
+  op5KeyRateScaling: number
+  op6KeyRateScaling: number
+  op1EgRate1: number
+  op1EgRate2: number
+  op1EgRate3: number
+  op1EgRate4: number
+  op2EgRate1: number
+  op2EgRate2: number
+  op2EgRate3: number
+  op2EgRate4: number
+  op3EgRate1: number
+  op3EgRate2: number
+  op3EgRate3: number
+  op3EgRate4: number
+  op4EgRate1: number
+  op4EgRate2: number
+  op4EgRate3: number
+  op4EgRate4: number
+  op5EgRate1: number
+  op5EgRate2: number
+  op5EgRate3: number
+  op5EgRate4: number
+  op6EgRate1: number
+  op6EgRate2: number
+  op6EgRate3: number
+  op6EgRate4: number
+  op1EgLevel1: number
+  op1EgLevel2: number
+  op1EgLevel3: number
+  op1EgLevel4: number
+  op2EgLevel1: number
+  op2EgLevel2: number
+  op2EgLevel3: number
+  op2EgLevel4: number
+  op3EgLevel1: number
+  op3EgLevel2: number
+  op3EgLevel3: number
+  op3EgLevel4: number
+  op4EgLevel1: number
+  op4EgLevel2: number
+  op4EgLevel3: number
+  op4EgLevel4: number
+  op5EgLevel1: number
+  op5EgLevel2: number
+  op5EgLevel3: number
+  op5EgLevel4: number
+  op6EgLevel1: number
+  op6EgLevel2: number
+  op6EgLevel3: number
+  op6EgLevel4: number
+  op1AmpModSensitivity: number
+  op2AmpModSensitivity: number
+  op3AmpModSensitivity: number
+  op4AmpModSensitivity: number
+  op5AmpModSensitivity: number
+  op6AmpModSensitivity: number
+  pitchEgRate1: number
+  pitchEgRate2: number
+  pitchEgRate3: number
+  pitchEgRate4: number
+  pitchEgLevel1: number
+  pitchEgLevel2: number
+  pitchEgLevel3: number
+  pitchEgLevel4: number
+  lfoSpeed: number
+  lfoDelay: number
+  lfoFade: number
+  lfoPitchModDepth: number
+  lfoAmpModDepth: number
+  lfoWaveform: FMLfoWaveform
+  lfoKeySync: number
+  voiceMode: FMVoiceMode
+  stealMode: FMStealMode
 }
 
 class FMVoiceWorkletNodeBase extends AudioWorkletNode {
   // Global parameters
   readonly frequency: AudioParam
   readonly algorithm: AudioParam
+  /** DX7 style, 0-7: each step doubles the feedback of the algorithm's loop. */
+  readonly feedback: AudioParam
+  readonly envelopeMode: AudioParam
 
   // Per-operator pitch shifts
   readonly op1PitchShift: AudioParam
@@ -68,6 +232,14 @@ class FMVoiceWorkletNodeBase extends AudioWorkletNode {
   readonly op5FineTune: AudioParam
   readonly op6FineTune: AudioParam
 
+  // Per-operator fixed frequencies in Hz, ignoring the note; 0 follows it
+  readonly op1FixedFrequency: AudioParam
+  readonly op2FixedFrequency: AudioParam
+  readonly op3FixedFrequency: AudioParam
+  readonly op4FixedFrequency: AudioParam
+  readonly op5FixedFrequency: AudioParam
+  readonly op6FixedFrequency: AudioParam
+
   // Per-operator levels
   readonly op1Level: AudioParam
   readonly op2Level: AudioParam
@@ -105,9 +277,139 @@ class FMVoiceWorkletNodeBase extends AudioWorkletNode {
   readonly op5Release: AudioParam
   readonly op6Release: AudioParam
 
+  // Per-operator velocity sensitivity
+  readonly op1VelocitySensitivity: AudioParam
+  readonly op2VelocitySensitivity: AudioParam
+  readonly op3VelocitySensitivity: AudioParam
+  readonly op4VelocitySensitivity: AudioParam
+  readonly op5VelocitySensitivity: AudioParam
+  readonly op6VelocitySensitivity: AudioParam
+
+  // Per-operator keyboard level and rate scaling
+  readonly op1KeyBreakpoint: AudioParam
+  readonly op2KeyBreakpoint: AudioParam
+  readonly op3KeyBreakpoint: AudioParam
+  readonly op4KeyBreakpoint: AudioParam
+  readonly op5KeyBreakpoint: AudioParam
+  readonly op6KeyBreakpoint: AudioParam
+  readonly op1KeyLeftDepth: AudioParam
+  readonly op2KeyLeftDepth: AudioParam
+  readonly op3KeyLeftDepth: AudioParam
+  readonly op4KeyLeftDepth: AudioParam
+  readonly op5KeyLeftDepth: AudioParam
+  readonly op6KeyLeftDepth: AudioParam
+  readonly op1KeyRightDepth: AudioParam
+  readonly op2KeyRightDepth: AudioParam
+  readonly op3KeyRightDepth: AudioParam
+  readonly op4KeyRightDepth: AudioParam
+  readonly op5KeyRightDepth: AudioParam
+  readonly op6KeyRightDepth: AudioParam
+  readonly op1KeyLeftCurve: AudioParam
+  readonly op2KeyLeftCurve: AudioParam
+  readonly op3KeyLeftCurve: AudioParam
+  readonly op4KeyLeftCurve: AudioParam
+  readonly op5KeyLeftCurve: AudioParam
+  readonly op6KeyLeftCurve: AudioParam
+  readonly op1KeyRightCurve: AudioParam
+  readonly op2KeyRightCurve: AudioParam
+  readonly op3KeyRightCurve: AudioParam
+  readonly op4KeyRightCurve: AudioParam
+  readonly op5KeyRightCurve: AudioParam
+  readonly op6KeyRightCurve: AudioParam
+  readonly op1KeyRateScaling: AudioParam
+  readonly op2KeyRateScaling: AudioParam
+  readonly op3KeyRateScaling: AudioParam
+  readonly op4KeyRateScaling: AudioParam
+  readonly op5KeyRateScaling: AudioParam
+  readonly op6KeyRateScaling: AudioParam
+
+  // Per-operator rate/level envelopes
+  readonly op1EgRate1: AudioParam
+  readonly op1EgRate2: AudioParam
+  readonly op1EgRate3: AudioParam
+  readonly op1EgRate4: AudioParam
+  readonly op2EgRate1: AudioParam
+  readonly op2EgRate2: AudioParam
+  readonly op2EgRate3: AudioParam
+  readonly op2EgRate4: AudioParam
+  readonly op3EgRate1: AudioParam
+  readonly op3EgRate2: AudioParam
+  readonly op3EgRate3: AudioParam
+  readonly op3EgRate4: AudioParam
+  readonly op4EgRate1: AudioParam
+  readonly op4EgRate2: AudioParam
+  readonly op4EgRate3: AudioParam
+  readonly op4EgRate4: AudioParam
+  readonly op5EgRate1: AudioParam
+  readonly op5EgRate2: AudioParam
+  readonly op5EgRate3: AudioParam
+  readonly op5EgRate4: AudioParam
+  readonly op6EgRate1: AudioParam
+  readonly op6EgRate2: AudioParam
+  readonly op6EgRate3: AudioParam
+  readonly op6EgRate4: AudioParam
+  readonly op1EgLevel1: AudioParam
+  readonly op1EgLevel2: AudioParam
+  readonly op1EgLevel3: AudioParam
+  readonly op1EgLevel4: AudioParam
+  readonly op2EgLevel1: AudioParam
+  readonly op2EgLevel2: AudioParam
+  readonly op2EgLevel3: AudioParam
+  readonly op2EgLevel4: AudioParam
+  readonly op3EgLevel1: AudioParam
+  readonly op3EgLevel2: AudioParam
+  readonly op3EgLevel3: AudioParam
+  readonly op3EgLevel4: AudioParam
+  readonly op4EgLevel1: AudioParam
+  readonly op4EgLevel2: AudioParam
+  readonly op4EgLevel3: AudioParam
+  readonly op4EgLevel4: AudioParam
+  readonly op5EgLevel1: AudioParam
+  readonly op5EgLevel2: AudioParam
+  readonly op5EgLevel3: AudioParam
+  readonly op5EgLevel4: AudioParam
+  readonly op6EgLevel1: AudioParam
+  readonly op6EgLevel2: AudioParam
+  readonly op6EgLevel3: AudioParam
+  readonly op6EgLevel4: AudioParam
+
+  // Per-operator sensitivity to the LFO's amplitude modulation, 0-1
+  readonly op1AmpModSensitivity: AudioParam
+  readonly op2AmpModSensitivity: AudioParam
+  readonly op3AmpModSensitivity: AudioParam
+  readonly op4AmpModSensitivity: AudioParam
+  readonly op5AmpModSensitivity: AudioParam
+  readonly op6AmpModSensitivity: AudioParam
+
+  // Pitch envelope: rates and levels 0-99, level 50 is the played pitch
+  readonly pitchEgRate1: AudioParam
+  readonly pitchEgRate2: AudioParam
+  readonly pitchEgRate3: AudioParam
+  readonly pitchEgRate4: AudioParam
+  readonly pitchEgLevel1: AudioParam
+  readonly pitchEgLevel2: AudioParam
+  readonly pitchEgLevel3: AudioParam
+  readonly pitchEgLevel4: AudioParam
+
+  // LFO: speed in Hz, delay and fade-in in seconds, pitch depth in semitones
+  readonly lfoWaveform: AudioParam
+  readonly lfoSpeed: AudioParam
+  readonly lfoDelay: AudioParam
+  readonly lfoFade: AudioParam
+  readonly lfoPitchModDepth: AudioParam
+  readonly lfoAmpModDepth: AudioParam
+  readonly lfoKeySync: AudioParam
+
+  // Note event API
+  readonly voiceMode: AudioParam
+  readonly stealMode: AudioParam
+
+  #sysexRequests = new Map<number, { resolve: (presets: FMVoicePreset[]) => void, reject: (error: Error) => void }>()
+  #nextSysexRequest = 0
+
   constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'fm-voice', {
-      numberOfInputs: 3, // trigger, retrigger
+      numberOfInputs: 4, // frequency, trigger, retrigger, velocity
       numberOfOutputs: 1,
       outputChannelCount: [2],
       parameterData: options,
@@ -116,6 +418,8 @@ class FMVoiceWorkletNodeBase extends AudioWorkletNode {
     // Global parameters
     this.frequency = this.parameters.get('frequency')!
     this.algorithm = this.parameters.get('algorithm')!
+    this.feedback = this.parameters.get('feedback')!
+    this.envelopeMode = this.parameters.get('envelopeMode')!
 
     // Per-operator pitch shifts
     this.op1PitchShift = this.parameters.get('op1PitchShift')!
@@ -133,6 +437,14 @@ class FMVoiceWorkletNodeBase extends AudioWorkletNode {
     this.op5FineTune = this.parameters.get('op5FineTune')!
     this.op6FineTune = this.parameters.get('op6FineTune')!
 
+    // Per-operator fixed frequencies
+    this.op1FixedFrequency = this.parameters.get('op1FixedFrequency')!
+    this.op2FixedFrequency = this.parameters.get('op2FixedFrequency')!
+    this.op3FixedFrequency = this.parameters.get('op3FixedFrequency')!
+    this.op4FixedFrequency = this.parameters.get('op4FixedFrequency')!
+    this.op5FixedFrequency = this.parameters.get('op5FixedFrequency')!
+    this.op6FixedFrequency = this.parameters.get('op6FixedFrequency')!
+
     // Per-operator levels
     this.op1Level = this.parameters.get('op1Level')!
     this.op2Level = this.parameters.get('op2Level')!
@@ -169,7 +481,181 @@ class FMVoiceWorkletNodeBase extends AudioWorkletNode {
     this.op4Release = this.parameters.get('op4Release')!
     this.op5Release = this.parameters.get('op5Release')!
     this.op6Release = this.parameters.get('op6Release')!
+
+    // Per-operator velocity sensitivity
+    this.op1VelocitySensitivity = this.parameters.get('op1VelocitySensitivity')!
+    this.op2VelocitySensitivity = this.parameters.get('op2VelocitySensitivity')!
+    this.op3VelocitySensitivity = this.parameters.get('op3VelocitySensitivity')!
+    this.op4VelocitySensitivity = this.parameters.get('op4VelocitySensitivity')!
+    this.op5VelocitySensitivity = this.parameters.get('op5VelocitySensitivity')!
+    this.op6VelocitySensitivity = this.parameters.get('op6VelocitySensitivity')!
+
+    // Per-operator keyboard level and rate scaling
+    this.op1KeyBreakpoint = this.parameters.get('op1KeyBreakpoint')!
+    this.op2KeyBreakpoint = this.parameters.get('op2KeyBreakpoint')!
+    this.op3KeyBreakpoint = this.parameters.get('op3KeyBreakpoint')!
+    this.op4KeyBreakpoint = this.parameters.get('op4KeyBreakpoint')!
+    this.op5KeyBreakpoint = this.parameters.get('op5KeyBreakpoint')!
+    this.op6KeyBreakpoint = this.parameters.get('op6KeyBreakpoint')!
+    this.op1KeyLeftDepth = this.parameters.get('op1KeyLeftDepth')!
+    this.op2KeyLeftDepth = this.parameters.get('op2KeyLeftDepth')!
+    this.op3KeyLeftDepth = this.parameters.get('op3KeyLeftDepth')!
+    this.op4KeyLeftDepth = this.parameters.get('op4KeyLeftDepth')!
+    this.op5KeyLeftDepth = this.parameters.get('op5KeyLeftDepth')!
+    this.op6KeyLeftDepth = this.parameters.get('op6KeyLeftDepth')!
+    this.op1KeyRightDepth = this.parameters.get('op1KeyRightDepth')!
+    this.op2KeyRightDepth = this.parameters.get('op2KeyRightDepth')!
+    this.op3KeyRightDepth = this.parameters.get('op3KeyRightDepth')!
+    this.op4KeyRightDepth = this.parameters.get('op4KeyRightDepth')!
+    this.op5KeyRightDepth = this.parameters.get('op5KeyRightDepth')!
+    this.op6KeyRightDepth = this.parameters.get('op6KeyRightDepth')!
+    this.op1KeyLeftCurve = this.parameters.get('op1KeyLeftCurve')!
+    this.op2KeyLeftCurve = this.parameters.get('op2KeyLeftCurve')!
+    this.op3KeyLeftCurve = this.parameters.get('op3KeyLeftCurve')!
+    this.op4KeyLeftCurve = this.parameters.get('op4KeyLeftCurve')!
+    this.op5KeyLeftCurve = this.parameters.get('op5KeyLeftCurve')!
+    this.op6KeyLeftCurve = this.parameters.get('op6KeyLeftCurve')!
+    this.op1KeyRightCurve = this.parameters.get('op1KeyRightCurve')!
+    this.op2KeyRightCurve = this.parameters.get('op2KeyRightCurve')!
+    this.op3KeyRightCurve = this.parameters.get('op3KeyRightCurve')!
+    this.op4KeyRightCurve = this.parameters.get('op4KeyRightCurve')!
+    this.op5KeyRightCurve = this.parameters.get('op5KeyRightCurve')!
+    this.op6KeyRightCurve = this.parameters.get('op6KeyRightCurve')!
+    this.op1KeyRateScaling = this.parameters.get('op1KeyRateScaling')!
+    this.op2KeyRateScaling = this.parameters.get('op2KeyRateScaling')!
+    this.op3KeyRateScaling = this.parameters.get('op3KeyRateScaling')!
+    this.op4KeyRateScaling = this.parameters.get('op4KeyRateScaling')!
+    this.op5KeyRateScaling = this.parameters.get('op5KeyRateScaling')!
+    this.op6KeyRateScaling = this.parameters.get('op6KeyRateScaling')!
+
+    // Per-operator rate/level envelopes
+    this.op1EgRate1 = this.parameters.get('op1EgRate1')!
+    this.op1EgRate2 = this.parameters.get('op1EgRate2')!
+    this.op1EgRate3 = this.parameters.get('op1EgRate3')!
+    this.op1EgRate4 = this.parameters.get('op1EgRate4')!
+    this.op2EgRate1 = this.parameters.get('op2EgRate1')!
+    this.op2EgRate2 = this.parameters.get('op2EgRate2')!
+    this.op2EgRate3 = this.parameters.get('op2EgRate3')!
+    this.op2EgRate4 = this.parameters.get('op2EgRate4')!
+    this.op3EgRate1 = this.parameters.get('op3EgRate1')!
+    this.op3EgRate2 = this.parameters.get('op3EgRate2')!
+    this.op3EgRate3 = this.parameters.get('op3EgRate3')!
+    this.op3EgRate4 = this.parameters.get('op3EgRate4')!
+    this.op4EgRate1 = this.parameters.get('op4EgRate1')!
+    this.op4EgRate2 = this.parameters.get('op4EgRate2')!
+    this.op4EgRate3 = this.parameters.get('op4EgRate3')!
+    this.op4EgRate4 = this.parameters.get('op4EgRate4')!
+    this.op5EgRate1 = this.parameters.get('op5EgRate1')!
+    this.op5EgRate2 = this.parameters.get('op5EgRate2')!
+    this.op5EgRate3 = this.parameters.get('op5EgRate3')!
+    this.op5EgRate4 = this.parameters.get('op5EgRate4')!
+    this.op6EgRate1 = this.parameters.get('op6EgRate1')!
+    this.op6EgRate2 = this.parameters.get('op6EgRate2')!
+    this.op6EgRate3 = this.parameters.get('op6EgRate3')!
+    this.op6EgRate4 = this.parameters.get('op6EgRate4')!
+    this.op1EgLevel1 = this.parameters.get('op1EgLevel1')!
+    this.op1EgLevel2 = this.parameters.get('op1EgLevel2')!
+    this.op1EgLevel3 = this.parameters.get('op1EgLevel3')!
+    this.op1EgLevel4 = this.parameters.get('op1EgLevel4')!
+    this.op2EgLevel1 = this.parameters.get('op2EgLevel1')!
+    this.op2EgLevel2 = this.parameters.get('op2EgLevel2')!
+    this.op2EgLevel3 = this.parameters.get('op2EgLevel3')!
+    this.op2EgLevel4 = this.parameters.get('op2EgLevel4')!
+    this.op3EgLevel1 = this.parameters.get('op3EgLevel1')!
+    this.op3EgLevel2 = this.parameters.get('op3EgLevel2')!
+    this.op3EgLevel3 = this.parameters.get('op3EgLevel3')!
+    this.op3EgLevel4 = this.parameters.get('op3EgLevel4')!
+    this.op4EgLevel1 = this.parameters.get('op4EgLevel1')!
+    this.op4EgLevel2 = this.parameters.get('op4EgLevel2')!
+    this.op4EgLevel3 = this.parameters.get('op4EgLevel3')!
+    this.op4EgLevel4 = this.parameters.get('op4EgLevel4')!
+    this.op5EgLevel1 = this.parameters.get('op5EgLevel1')!
+    this.op5EgLevel2 = this.parameters.get('op5EgLevel2')!
+    this.op5EgLevel3 = this.parameters.get('op5EgLevel3')!
+    this.op5EgLevel4 = this.parameters.get('op5EgLevel4')!
+    this.op6EgLevel1 = this.parameters.get('op6EgLevel1')!
+    this.op6EgLevel2 = this.parameters.get('op6EgLevel2')!
+    this.op6EgLevel3 = this.parameters.get('op6EgLevel3')!
+    this.op6EgLevel4 = this.parameters.get('op6EgLevel4')!
+
+    // Per-operator amplitude modulation sensitivity
+    this.op1AmpModSensitivity = this.parameters.get('op1AmpModSensitivity')!
+    this.op2AmpModSensitivity = this.parameters.get('op2AmpModSensitivity')!
+    this.op3AmpModSensitivity = this.parameters.get('op3AmpModSensitivity')!
+    this.op4AmpModSensitivity = this.parameters.get('op4AmpModSensitivity')!
+    this.op5AmpModSensitivity = this.parameters.get('op5AmpModSensitivity')!
+    this.op6AmpModSensitivity = this.parameters.get('op6AmpModSensitivity')!
+
+    // Pitch envelope
+    this.pitchEgRate1 = this.parameters.get('pitchEgRate1')!
+    this.pitchEgRate2 = this.parameters.get('pitchEgRate2')!
+    this.pitchEgRate3 = this.parameters.get('pitchEgRate3')!
+    this.pitchEgRate4 = this.parameters.get('pitchEgRate4')!
+    this.pitchEgLevel1 = this.parameters.get('pitchEgLevel1')!
+    this.pitchEgLevel2 = this.parameters.get('pitchEgLevel2')!
+    this.pitchEgLevel3 = this.parameters.get('pitchEgLevel3')!
+    this.pitchEgLevel4 = this.parameters.get('pitchEgLevel4')!
+
+    // LFO
+    this.lfoWaveform = this.parameters.get('lfoWaveform')!
+    this.lfoSpeed = this.parameters.get('lfoSpeed')!
+    this.lfoDelay = this.parameters.get('lfoDelay')!
+    this.lfoFade = this.parameters.get('lfoFade')!
+    this.lfoPitchModDepth = this.parameters.get('lfoPitchModDepth')!
+    this.lfoAmpModDepth = this.parameters.get('lfoAmpModDepth')!
+    this.lfoKeySync = this.parameters.get('lfoKeySync')!
+
+    this.voiceMode = this.parameters.get('voiceMode')!
+    this.stealMode = this.parameters.get('stealMode')!
+
+    this.port.onmessage = (e: MessageEvent<SysexReply>) => {
+      if (e.data.type !== 'sysexParsed') {
+        return
+      }
+      const request = this.#sysexRequests.get(e.data.id)
+      this.#sysexRequests.delete(e.data.id)
+      if (e.data.presets) {
+        request?.resolve(e.data.presets)
+      }
+      else {
+        request?.reject(new Error(e.data.error))
+      }
+    }
+  }
+
+  /**
+   * Reads the voices of a DX7 single voice or 32 voice bank sysex dump.
+   */
+  parseSysex(data: ArrayBuffer | Uint8Array) {
+    const id = this.#nextSysexRequest++
+    return new Promise<FMVoicePreset[]>((resolve, reject) => {
+      this.#sysexRequests.set(id, { resolve, reject })
+      this.port.postMessage({ type: 'parseSysex', id, data: new Uint8Array(data) })
+    })
+  }
+
+  applyPreset(preset: FMVoicePreset) {
+    for (const [name, value] of Object.entries(preset.params)) {
+      this.parameters.get(name)?.setValueAtTime(value, this.context.currentTime)
+    }
+  }
+
+  /**
+   * Plays a MIDI note on a voice picked by the node itself, as an alternative
+   * to the trigger/frequency inputs. `velocity` is 0-1 and `offset` is in
+   * samples from the start of the next render quantum.
+   */
+  noteOn(note: number, velocity: number, offset = 0) {
+    this.port.postMessage({ type: 'noteOn', note, velocity, offset })
+  }
+
+  noteOff(note: number, offset = 0) {
+    this.port.postMessage({ type: 'noteOff', note, offset })
+  }
+
+  allNotesOff(offset = 0) {
+    this.port.postMessage({ type: 'allNotesOff', offset })
   }
 }
 
-export const FMVoiceWorkletNode = Destroyable(Resettable(FMVoiceWorkletNodeBase))
+export const FMVoiceWorkletNode = Destroyable(Reconfigurable(Seedable(Resettable(FMVoiceWorkletNodeBase))))
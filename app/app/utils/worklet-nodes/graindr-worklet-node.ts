@@ -1,4 +1,5 @@
-import { Destroyable, Resettable } from './mixins'
+import type { Oversampling } from './bit-crusher-worklet-node'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 export const enum Direction {
   Forward,
@@ -23,6 +24,7 @@ type ParamOptions = {
   hicut: number
   playbackDirection: Direction
   toneType: ToneType
+  oversampling: Oversampling
 }
 
 class GraindrWorkletNodeBase extends AudioWorkletNode {
@@ -37,6 +39,7 @@ class GraindrWorkletNodeBase extends AudioWorkletNode {
   readonly hiCut: AudioParam
   readonly playbackDirection: AudioParam
   readonly toneType: AudioParam
+  readonly oversampling: AudioParam
 
   constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'graindr', {
@@ -57,6 +60,7 @@ class GraindrWorkletNodeBase extends AudioWorkletNode {
     this.hiCut = this.parameters.get('hiCut')!
     this.playbackDirection = this.parameters.get('playbackDirection')!
     this.toneType = this.parameters.get('toneType')!
+    this.oversampling = this.parameters.get('oversampling')!
   }
 }
-export const GraindrWorkletNode = Destroyable(Resettable(GraindrWorkletNodeBase))
+export const GraindrWorkletNode = Destroyable(Reconfigurable(Resettable(GraindrWorkletNodeBase)))
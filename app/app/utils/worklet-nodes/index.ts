@@ -6,10 +6,16 @@ export * from './envelope-tracker-worklet-node'
 export * from './fm-oscillator-worklet-node'
 export * from './fm-voice-worklet-node'
 export * from './graindr-worklet-node'
+export * from './karplus-strong-worklet-node'
 export * from './lfo-worklet-node'
 export * from './multi-filter-worklet-node'
 export * from './multiplier-worklet-node'
 export * from './noise-generator-worklet-node'
+export * from './patch-graph-worklet-node'
+export * from './phase-distortion-oscillator-worklet-node'
 export * from './phaser-worklet-node'
 export * from './pitch-tracker-worklet-node'
 export * from './squencer-worklet-node'
+export * from './unison-oscillator-worklet-node'
+export * from './vco-worklet-node'
+export * from './wavetable-oscillator-worklet-node'
@@ -0,0 +1,42 @@
+import { Destroyable, Reconfigurable, Resettable, Seedable } from './mixins'
+
+export const enum KarplusStrongExcitation {
+  Noise = 0,
+  External = 1,
+}
+
+type ParamOptions = {
+  frequency: number
+  excitation: KarplusStrongExcitation
+  damping: number
+  brightness: number
+  stretch: number
+}
+
+/**
+ * Inputs: 0 pitch (1V/oct), 1 trigger (a rising edge plucks the string),
+ * 2 excitation (fed into the string with `KarplusStrongExcitation.External`).
+ */
+class KarplusStrongWorkletNodeBase extends AudioWorkletNode {
+  readonly frequency: AudioParam
+  readonly excitation: AudioParam
+  readonly damping: AudioParam
+  readonly brightness: AudioParam
+  readonly stretch: AudioParam
+
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
+    super(context, 'karplus-strong', {
+      numberOfInputs: 3,
+      numberOfOutputs: 1,
+      outputChannelCount: [1],
+      parameterData: options,
+    })
+
+    this.frequency = this.parameters.get('frequency')!
+    this.excitation = this.parameters.get('excitation')!
+    this.damping = this.parameters.get('damping')!
+    this.brightness = this.parameters.get('brightness')!
+    this.stretch = this.parameters.get('stretch')!
+  }
+}
+export const KarplusStrongWorkletNode = Destroyable(Reconfigurable(Seedable(Resettable(KarplusStrongWorkletNodeBase))))
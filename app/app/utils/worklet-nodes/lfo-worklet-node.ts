@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable, Seedable } from './mixins'
 
 export const enum LFOPolarity {
   Bipolar = 0,
@@ -12,6 +12,27 @@ export const enum LFOWaveform {
   Ramp = 3,
   Square = 4,
   RSH = 5,
+  SmoothRandom = 6,
+  ExpRamp = 7,
+  LogRamp = 8,
+  SkewedTriangle = 9,
+  Custom = 10,
+}
+
+export const enum LFODivision {
+  Sixteenth = 0,
+  Eighth = 1,
+  Quarter = 2,
+  Half = 3,
+  Bar = 4,
+  TwoBars = 5,
+  FourBars = 6,
+}
+
+export const enum LFODivisionType {
+  Straight = 0,
+  Dotted = 1,
+  Triplet = 2,
 }
 
 type ParamOptions = {
@@ -19,19 +40,38 @@ type ParamOptions = {
   phase: number
   polarity: LFOPolarity
   waveform: LFOWaveform
+  tempoSync: number
+  bpm: number
+  division: LFODivision
+  divisionType: LFODivisionType
+  skew: number
+  spreadPhases: number
 }
 
+/**
+ * Input 0 is the clock: a rising edge restarts the cycle.
+ *
+ * Outputs: 0 main, 1 quadrature (90° ahead), 2 inverted, 3 spread (4 channels,
+ * shifted by `1 / spreadPhases` of a cycle each).
+ */
+
 class LFOOwrkletNodeBase extends AudioWorkletNode {
   readonly frequency: AudioParam
   readonly phase: AudioParam
   readonly polarity: AudioParam
   readonly waveform: AudioParam
+  readonly tempoSync: AudioParam
+  readonly bpm: AudioParam
+  readonly division: AudioParam
+  readonly divisionType: AudioParam
+  readonly skew: AudioParam
+  readonly spreadPhases: AudioParam
 
   constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'lfo', {
-      numberOfInputs: 0,
-      numberOfOutputs: 1,
-      outputChannelCount: [1],
+      numberOfInputs: 1,
+      numberOfOutputs: 4,
+      outputChannelCount: [1, 1, 1, 4],
       parameterData: options,
     })
 
@@ -39,6 +79,27 @@ class LFOOwrkletNodeBase extends AudioWorkletNode {
     this.phase = this.parameters.get('phase')!
     this.polarity = this.parameters.get('polarity')!
     this.waveform = this.parameters.get('waveform')!
+    this.tempoSync = this.parameters.get('tempoSync')!
+    this.bpm = this.parameters.get('bpm')!
+    this.division = this.parameters.get('division')!
+    this.divisionType = this.parameters.get('divisionType')!
+    this.skew = this.parameters.get('skew')!
+    this.spreadPhases = this.parameters.get('spreadPhases')!
+  }
+
+  /**
+   * Uses `table` as one cycle of the custom waveform.
+   */
+  loadCustomTable(table: Float32Array) {
+    this.port.postMessage({ type: 'loadTable', table })
+  }
+
+  /**
+   * Builds the custom waveform from `[phase, value]` points with rising
+   * phases in [0, 1], connected by straight lines that wrap around.
+   */
+  loadCustomBreakpoints(points: [number, number][]) {
+    this.port.postMessage({ type: 'loadBreakpoints', breakpoints: new Float32Array(points.flat()) })
   }
 }
-export const LFOWorkletNode = Destroyable(Resettable(LFOOwrkletNodeBase))
+export const LFOWorkletNode = Destroyable(Reconfigurable(Seedable(Resettable(LFOOwrkletNodeBase))))
@@ -9,6 +9,30 @@ export function Resettable<N extends AudioWorkletNodeConstructor>(WorkletNode: N
   }
 }
 
+export function Seedable<N extends AudioWorkletNodeConstructor>(WorkletNode: N) {
+  return class SeedableWorkletNode extends WorkletNode {
+    /**
+     * Restarts the node's random sequence; the same seed renders the same
+     * output.
+     */
+    setSeed(seed: number) {
+      this.port.postMessage({ type: 'setSeed', seed })
+    }
+  }
+}
+
+export function Reconfigurable<N extends AudioWorkletNodeConstructor>(WorkletNode: N) {
+  return class ReconfigurableWorkletNode extends WorkletNode {
+    /**
+     * Adapts the processor to a new sample rate and channel count, keeping
+     * its state where it can.
+     */
+    reconfigure(sampleRate: number, channelCount: number) {
+      this.port.postMessage({ type: 'reconfigure', sampleRate, channelCount })
+    }
+  }
+}
+
 export function Destroyable<N extends AudioWorkletNodeConstructor>(WorkletNode: N) {
   return class DestroyableWorkletNode extends WorkletNode {
     destroy() {
@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class MultiFilterWorklerNodeBase extends AudioWorkletNode {
   readonly cutoff: AudioParam
@@ -16,4 +16,4 @@ class MultiFilterWorklerNodeBase extends AudioWorkletNode {
     this.q = this.parameters.get('q')!
   }
 }
-export const MultiFilterWorkletNode = Destroyable(Resettable(MultiFilterWorklerNodeBase))
+export const MultiFilterWorkletNode = Destroyable(Reconfigurable(Resettable(MultiFilterWorklerNodeBase)))
@@ -1,4 +1,4 @@
-import { Destroyable } from './mixins'
+import { Destroyable, Reconfigurable } from './mixins'
 
 class MultiplierWorkletNodeBase extends AudioWorkletNode {
   constructor(context: AudioContext) {
@@ -9,4 +9,4 @@ class MultiplierWorkletNodeBase extends AudioWorkletNode {
     })
   }
 }
-export const MultiplierWorkletNode = Destroyable(MultiplierWorkletNodeBase)
+export const MultiplierWorkletNode = Destroyable(Reconfigurable(MultiplierWorkletNodeBase))
@@ -1,13 +1,22 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable, Seedable } from './mixins'
 
 export const enum NoiseType {
   White,
   Brownian,
+  Pink,
+  Blue,
+  Violet,
+}
+
+type ParamOptions = {
+  noiseType: NoiseType
+  stereo: number
 }
 
 class NoiseGeneratorWorkletNodeBase extends AudioWorkletNode {
   readonly noiseType: AudioParam
-  constructor(context: AudioContext, options?: Partial<{ noiseType: NoiseType }>) {
+  readonly stereo: AudioParam
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
     super(context, 'noise-generator', {
       numberOfInputs: 0,
       numberOfOutputs: 1,
@@ -16,6 +25,7 @@ class NoiseGeneratorWorkletNodeBase extends AudioWorkletNode {
     })
 
     this.noiseType = this.parameters.get('noiseType')!
+    this.stereo = this.parameters.get('stereo')!
   }
 }
-export const NoiseGeneratorWorkletNode = Destroyable(Resettable(NoiseGeneratorWorkletNodeBase))
+export const NoiseGeneratorWorkletNode = Destroyable(Reconfigurable(Seedable(Resettable(NoiseGeneratorWorkletNodeBase))))
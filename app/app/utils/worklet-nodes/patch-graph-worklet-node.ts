@@ -0,0 +1,69 @@
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
+
+export const enum PatchNodeKind {
+  ADSR = 0,
+  BitCrusher = 1,
+  Decimator = 2,
+  EnvelopeGenerator = 3,
+  FMOscillator = 4,
+  LFO = 5,
+  MultiFilter = 6,
+  Multiplier = 7,
+  Phaser = 8,
+  Sequencer = 9,
+  VCO = 10,
+  UnisonOscillator = 11,
+  PhaseDistortionOscillator = 12,
+  KarplusStrong = 13,
+}
+
+class PatchGraphWorkletNodeBase extends AudioWorkletNode {
+  #nodeCount = 0
+
+  constructor(context: AudioContext) {
+    super(context, 'patch-graph', {
+      numberOfInputs: 1,
+      numberOfOutputs: 1,
+      outputChannelCount: [2],
+    })
+  }
+
+  /** Returns the index of the new node inside the graph. */
+  addNode(kind: PatchNodeKind) {
+    this.port.postMessage({ type: 'addNode', kind })
+    return this.#nodeCount++
+  }
+
+  connectNodes(fromNode: number, fromOutput: number, toNode: number, toInput: number) {
+    this.port.postMessage({ type: 'connectNodes', fromNode, fromOutput, toNode, toInput })
+  }
+
+  disconnectNodes(fromNode: number, fromOutput: number, toNode: number, toInput: number) {
+    this.port.postMessage({ type: 'disconnectNodes', fromNode, fromOutput, toNode, toInput })
+  }
+
+  connectInput(toNode: number, toInput: number) {
+    this.port.postMessage({ type: 'connectInput', toNode, toInput })
+  }
+
+  disconnectInput(toNode: number, toInput: number) {
+    this.port.postMessage({ type: 'disconnectInput', toNode, toInput })
+  }
+
+  setOutput(fromNode: number, fromOutput: number) {
+    this.port.postMessage({ type: 'setOutput', fromNode, fromOutput })
+  }
+
+  setParams(node: number, params: number[]) {
+    this.port.postMessage({ type: 'setParams', node, params })
+  }
+
+  setConstantInput(node: number, input: number, value: number) {
+    this.port.postMessage({ type: 'setConstantInput', node, input, value })
+  }
+
+  setSeed(node: number, seed: number) {
+    this.port.postMessage({ type: 'setSeed', node, seed })
+  }
+}
+export const PatchGraphWorkletNode = Destroyable(Reconfigurable(Resettable(PatchGraphWorkletNodeBase)))
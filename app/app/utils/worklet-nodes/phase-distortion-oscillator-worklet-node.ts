@@ -0,0 +1,38 @@
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
+
+export const enum PhaseDistortionWaveform {
+  Saw = 0,
+  Square = 1,
+  Pulse = 2,
+  Resonant = 3,
+}
+
+type ParamOptions = {
+  frequency: number
+  amount: number
+  waveform: PhaseDistortionWaveform
+}
+
+/**
+ * Inputs: 0 pitch (1V/oct). Connect an envelope to `amount` to sweep the
+ * distortion.
+ */
+class PhaseDistortionOscillatorWorkletNodeBase extends AudioWorkletNode {
+  readonly frequency: AudioParam
+  readonly amount: AudioParam
+  readonly waveform: AudioParam
+
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
+    super(context, 'phase-distortion-oscillator', {
+      numberOfInputs: 1,
+      numberOfOutputs: 1,
+      outputChannelCount: [1],
+      parameterData: options,
+    })
+
+    this.frequency = this.parameters.get('frequency')!
+    this.amount = this.parameters.get('amount')!
+    this.waveform = this.parameters.get('waveform')!
+  }
+}
+export const PhaseDistortionOscillatorWorkletNode = Destroyable(Reconfigurable(Resettable(PhaseDistortionOscillatorWorkletNodeBase)))
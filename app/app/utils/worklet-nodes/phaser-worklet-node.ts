@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class PhaserWorkletNodeBase extends AudioWorkletNode {
   readonly rate: AudioParam
@@ -14,4 +14,4 @@ class PhaserWorkletNodeBase extends AudioWorkletNode {
     this.rate = this.parameters.get('rate')!
   }
 }
-export const PhaserWorkletNode = Destroyable(Resettable(PhaserWorkletNodeBase))
+export const PhaserWorkletNode = Destroyable(Reconfigurable(Resettable(PhaserWorkletNodeBase)))
@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 class PitchTrackerWorkletNodeBase extends AudioWorkletNode {
   constructor(context: AudioContext, options: { harmonicThreshold?: number, windowSizeSamples?: number } = {}) {
@@ -24,4 +24,4 @@ class PitchTrackerWorkletNodeBase extends AudioWorkletNode {
   }
 }
 
-export const PitchTrackerWorkletNode = Destroyable(Resettable(PitchTrackerWorkletNodeBase))
+export const PitchTrackerWorkletNode = Destroyable(Reconfigurable(Resettable(PitchTrackerWorkletNodeBase)))
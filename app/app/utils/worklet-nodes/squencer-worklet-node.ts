@@ -1,4 +1,4 @@
-import { Destroyable, Resettable } from './mixins'
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
 
 const NUM_VALUES = 16
 
@@ -58,4 +58,4 @@ class SequencerWorkletNodeBase extends AudioWorkletNode {
     }
   }
 }
-export const SequencerWorkletNode = Destroyable(Resettable(SequencerWorkletNodeBase))
+export const SequencerWorkletNode = Destroyable(Reconfigurable(Resettable(SequencerWorkletNodeBase)))
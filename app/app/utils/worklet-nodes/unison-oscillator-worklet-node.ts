@@ -0,0 +1,52 @@
+import { Destroyable, Reconfigurable, Resettable, Seedable } from './mixins'
+
+export const enum UnisonWaveform {
+  Saw = 0,
+  Square = 1,
+}
+
+export const enum UnisonPhaseReset {
+  Random = 0,
+  Fixed = 1,
+}
+
+type ParamOptions = {
+  frequency: number
+  voices: number
+  detune: number
+  spread: number
+  centerMix: number
+  waveform: UnisonWaveform
+  phaseReset: UnisonPhaseReset
+}
+
+/**
+ * Inputs: 0 pitch (1V/oct), 1 gate (a rising edge resets the voice phases).
+ */
+class UnisonOscillatorWorkletNodeBase extends AudioWorkletNode {
+  readonly frequency: AudioParam
+  readonly voices: AudioParam
+  readonly detune: AudioParam
+  readonly spread: AudioParam
+  readonly centerMix: AudioParam
+  readonly waveform: AudioParam
+  readonly phaseReset: AudioParam
+
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
+    super(context, 'unison-oscillator', {
+      numberOfInputs: 2,
+      numberOfOutputs: 1,
+      outputChannelCount: [2],
+      parameterData: options,
+    })
+
+    this.frequency = this.parameters.get('frequency')!
+    this.voices = this.parameters.get('voices')!
+    this.detune = this.parameters.get('detune')!
+    this.spread = this.parameters.get('spread')!
+    this.centerMix = this.parameters.get('centerMix')!
+    this.waveform = this.parameters.get('waveform')!
+    this.phaseReset = this.parameters.get('phaseReset')!
+  }
+}
+export const UnisonOscillatorWorkletNode = Destroyable(Reconfigurable(Seedable(Resettable(UnisonOscillatorWorkletNodeBase))))
@@ -0,0 +1,40 @@
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
+
+export const enum VCOWaveform {
+  Sine = 0,
+  Triangle = 1,
+  Saw = 2,
+  Pulse = 3,
+}
+
+type ParamOptions = {
+  frequency: number
+  pulseWidth: number
+  fmDepth: number
+  waveform: VCOWaveform
+}
+
+/**
+ * Inputs: 0 pitch (1V/oct), 1 linear FM, 2 PWM, 3 hard sync.
+ */
+class VCOWorkletNodeBase extends AudioWorkletNode {
+  readonly frequency: AudioParam
+  readonly pulseWidth: AudioParam
+  readonly fmDepth: AudioParam
+  readonly waveform: AudioParam
+
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
+    super(context, 'vco', {
+      numberOfInputs: 4,
+      numberOfOutputs: 1,
+      outputChannelCount: [1],
+      parameterData: options,
+    })
+
+    this.frequency = this.parameters.get('frequency')!
+    this.pulseWidth = this.parameters.get('pulseWidth')!
+    this.fmDepth = this.parameters.get('fmDepth')!
+    this.waveform = this.parameters.get('waveform')!
+  }
+}
+export const VCOWorkletNode = Destroyable(Reconfigurable(Resettable(VCOWorkletNodeBase)))
@@ -0,0 +1,37 @@
+import { Destroyable, Reconfigurable, Resettable } from './mixins'
+
+type ParamOptions = {
+  frequency: number
+  position: number
+}
+
+/**
+ * Input 0 is the 1V/oct pitch input.
+ */
+class WavetableOscillatorWorkletNodeBase extends AudioWorkletNode {
+  readonly frequency: AudioParam
+  readonly position: AudioParam
+
+  constructor(context: AudioContext, options?: Partial<ParamOptions>) {
+    super(context, 'wavetable-oscillator', {
+      numberOfInputs: 1,
+      numberOfOutputs: 1,
+      outputChannelCount: [1],
+      parameterData: options,
+    })
+
+    this.frequency = this.parameters.get('frequency')!
+    this.position = this.parameters.get('position')!
+  }
+
+  /**
+   * Replaces the wavetable with consecutive single-cycle frames of
+   * `frameSize` samples each (at most 256 frames); `position` morphs across
+   * them. The processor builds the new table one frame per render quantum
+   * and keeps playing the old one until it is done.
+   */
+  loadTable(table: Float32Array, frameSize: number) {
+    this.port.postMessage({ type: 'loadTable', table, frameSize })
+  }
+}
+export const WavetableOscillatorWorkletNode = Destroyable(Reconfigurable(Resettable(WavetableOscillatorWorkletNodeBase)))
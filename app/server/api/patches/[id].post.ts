@@ -26,7 +26,7 @@ export default defineEventHandler(async (event) => {
   }
   const now = new Date().toISOString()
   const patch = {
-    version: 1,
+    version: 2,
     id,
     name: body.name ?? existing.name ?? 'New patch',
     createdAt: existing.createdAt ?? now,
@@ -14,7 +14,7 @@ export default defineEventHandler(async (event) => {
   const id = randomUUID()
   const now = new Date().toISOString()
   const patch = {
-    version: 1,
+    version: 2,
     id,
     name: body.name ?? 'New patch',
     createdAt: now,
@@ -8,12 +8,18 @@ import './processors/envelope-tracker';
 import './processors/fm-oscillator';
 import './processors/fm-voice';
 import './processors/graindr';
+import './processors/karplus-strong';
 import './processors/lfo';
 import './processors/multi-filter';
 import './processors/multiplier';
 import './processors/noise-generator';
+import './processors/patch-graph';
+import './processors/phase-distortion-oscillator';
 import './processors/phaser';
 import './processors/pitch-tracker';
 import './processors/sequencer';
+import './processors/unison-oscillator';
+import './processors/vco';
+import './processors/wavetable-oscillator';
 
 console.log('AUDIO PROCESSORS!');
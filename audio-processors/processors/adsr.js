@@ -20,39 +20,17 @@ class ADSRProcessor extends AudioWorkletProcessor {
         this.#adsr.reset();
       } else if (e.data === 'destroy') {
         this.#destroy();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0
-      },
-      {
-        name: 'decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0
-      },
-      {
-        name: 'sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0
-      },
-      {
-        name: 'release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0
-      }
-    ]);
+    return JSON.parse(ADSR.parameter_descriptors());
   }
 
   /**
@@ -60,9 +38,7 @@ class ADSRProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#triggerInputBuffer.recoverMemory(this.#adsr.trigger_input_buffer_ptr());
-      this.#retriggerInputBuffer.recoverMemory(this.#adsr.retrigger_input_buffer_ptr());
-      this.#outputBuffer.recoverMemory(this.#adsr.output_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -80,6 +56,7 @@ class ADSRProcessor extends AudioWorkletProcessor {
     this.#retriggerInputBuffer.setChannelData(inputList[1][0], 0);
 
     this.#adsr.process(
+      RENDER_QUANTUM_FRAMES,
       parameters['attack'][0],
       parameters['decay'][0],
       parameters['sustain'][0],
@@ -91,6 +68,22 @@ class ADSRProcessor extends AudioWorkletProcessor {
     return true;
   }
 
+  #recoverMemory() {
+    this.#triggerInputBuffer.recoverMemory(this.#adsr.trigger_input_buffer_ptr());
+    this.#retriggerInputBuffer.recoverMemory(this.#adsr.retrigger_input_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#adsr.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#adsr.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#adsr.free();
     this.#triggerInputBuffer.free();
@@ -11,6 +11,8 @@ class BitCrusherProcessor extends AudioWorkletProcessor {
     #inputBuffer = new HeapAudioBuffer(this.#bitCrusher.input_buffer_ptr(), CHANNELS);
     #outputBuffer = new HeapAudioBuffer(this.#bitCrusher.output_buffer_ptr(), CHANNELS);
 
+    #channelCount = CHANNELS;
+
     #destroyed = false;
 
     constructor() {
@@ -21,22 +23,17 @@ class BitCrusherProcessor extends AudioWorkletProcessor {
                 this.#bitCrusher.reset();
             } else if (e.data === 'destroy') {
                 this.#destroy();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
         cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'bits',
-                defaultValue: 32.0,
-                minValue: 1.0,
-                maxValue: 32.0,
-                automationRate: 'k-rate'
-            }
-        ]);
+        return JSON.parse(BitCrusher.parameter_descriptors());
     }
 
     /**
@@ -44,8 +41,7 @@ class BitCrusherProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#inputBuffer.recoverMemory(this.#bitCrusher.input_buffer_ptr());
-            this.#outputBuffer.recoverMemory(this.#bitCrusher.output_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -62,19 +58,36 @@ class BitCrusherProcessor extends AudioWorkletProcessor {
         const input = inputList[0];
 
         const inputChannels = input.length - 1;
-        for (let channel = 0; channel < CHANNELS; channel++) {
+        for (let channel = 0; channel < this.#channelCount; channel++) {
             this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
         }
 
-        this.#bitCrusher.process(parameters['bits'][0]);
+        this.#bitCrusher.process(RENDER_QUANTUM_FRAMES, parameters['bits'][0], parameters['oversampling'][0]);
 
         for (let channel = 0; channel < outputList[0].length; channel++) {
-            outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
         }
 
         return true;
     }
 
+    #recoverMemory() {
+        this.#inputBuffer.recoverMemory(this.#bitCrusher.input_buffer_ptr(), this.#channelCount);
+        this.#outputBuffer.recoverMemory(this.#bitCrusher.output_buffer_ptr(), this.#channelCount);
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#bitCrusher.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#bitCrusher.free();
         this.#inputBuffer.free();
@@ -11,6 +11,8 @@ class DecimatorProcessor extends AudioWorkletProcessor {
     #inputBuffer = new HeapAudioBuffer(this.#decimator.input_buffer_ptr(), CHANNELS);
     #outputBuffer = new HeapAudioBuffer(this.#decimator.output_buffer_ptr(), CHANNELS);;
 
+    #channelCount = CHANNELS;
+
     #destroyed = false;
 
     constructor() {
@@ -21,29 +23,17 @@ class DecimatorProcessor extends AudioWorkletProcessor {
                 this.#decimator.reset();
             } else if (e.data === 'destroy') {
                 this.#destroy();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
         cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'reduction',
-                defaultValue: 1,
-                minValue: 0.001,
-                maxValue: 1,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'stereoShift',
-                defaultValue: 0,
-                minValue: 0,
-                maxValue: 1,
-                automationRate: 'k-rate'
-            }
-        ]);
+        return JSON.parse(Decimator.parameter_descriptors());
     }
 
     /**
@@ -51,8 +41,7 @@ class DecimatorProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#inputBuffer.recoverMemory(this.#decimator.input_buffer_ptr());
-            this.#outputBuffer.recoverMemory(this.#decimator.output_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -69,19 +58,36 @@ class DecimatorProcessor extends AudioWorkletProcessor {
         const input = inputList[0];
 
         const inputChannels = input.length - 1;
-        for (let channel = 0; channel < CHANNELS; channel++) {
+        for (let channel = 0; channel < this.#channelCount; channel++) {
             this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
         }
 
-        this.#decimator.process(parameters['reduction'][0], parameters['stereoShift'][0]);
+        this.#decimator.process(RENDER_QUANTUM_FRAMES, parameters['reduction'][0], parameters['stereoShift'][0]);
 
         for (let channel = 0; channel < outputList[0].length; channel++) {
-            outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
         }
 
         return true;
     }
 
+    #recoverMemory() {
+        this.#inputBuffer.recoverMemory(this.#decimator.input_buffer_ptr(), this.#channelCount);
+        this.#outputBuffer.recoverMemory(this.#decimator.output_buffer_ptr(), this.#channelCount);
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#decimator.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#decimator.free();
         this.#inputBuffer.free();
@@ -21,45 +21,17 @@ class EnvelopeGeneratorProcessor extends AudioWorkletProcessor {
         this.#envelopeGenerator.reset();
       } else if (e.data === 'destroy') {
         this.#destroy();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'attack',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 5000.0
-      },
-      {
-        name: 'decay',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 5000.0
-      },
-      {
-        name: 'sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0
-      },
-      {
-        name: 'release',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 5000.0
-      },
-      {
-        name: 'applyVelocity',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 1.0
-      }
-    ]);
+    return JSON.parse(EnvelopeGenerator.parameter_descriptors());
   }
 
   /**
@@ -67,10 +39,7 @@ class EnvelopeGeneratorProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#triggerInputBuffer.recoverMemory(this.#envelopeGenerator.trigger_input_buffer_ptr());
-      this.#retriggerInputBuffer.recoverMemory(this.#envelopeGenerator.retrigger_input_buffer_ptr());
-      this.#velocityInputBuffer.recoverMemory(this.#envelopeGenerator.velocity_input_buffer_ptr());
-      this.#outputBuffer.recoverMemory(this.#envelopeGenerator.output_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -89,6 +58,7 @@ class EnvelopeGeneratorProcessor extends AudioWorkletProcessor {
     this.#velocityInputBuffer.setChannelData(inputList[2][0], 0);
 
     this.#envelopeGenerator.process(
+      RENDER_QUANTUM_FRAMES,
       parameters['attack'][0],
       parameters['decay'][0],
       parameters['sustain'][0],
@@ -101,6 +71,23 @@ class EnvelopeGeneratorProcessor extends AudioWorkletProcessor {
     return true;
   }
 
+  #recoverMemory() {
+    this.#triggerInputBuffer.recoverMemory(this.#envelopeGenerator.trigger_input_buffer_ptr());
+    this.#retriggerInputBuffer.recoverMemory(this.#envelopeGenerator.retrigger_input_buffer_ptr());
+    this.#velocityInputBuffer.recoverMemory(this.#envelopeGenerator.velocity_input_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#envelopeGenerator.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#envelopeGenerator.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#envelopeGenerator.free();
     this.#triggerInputBuffer.free();
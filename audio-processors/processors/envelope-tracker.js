@@ -20,27 +20,17 @@ class EnvelopeTrackerProcessor extends AudioWorkletProcessor {
         this.#destroy();
       } else if (e.data === 'reset') {
         this.#envelopeTracker.reset();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'sensitivity',
-        defaultValue: 1.0,
-        minValue: 0.25,
-        maxValue: 5.0,
-      },
-      {
-        name: 'threshold',
-        defaultValue: -60.0,
-        minValue: -60.0,
-        maxValue: 0.0,
-      },
-    ]);
+    return JSON.parse(EnvelopeTracker.parameter_descriptors());
   }
 
   /**
@@ -48,9 +38,7 @@ class EnvelopeTrackerProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#inputBuffer.recoverMemory(this.#envelopeTracker.input_buffer_ptr());
-      this.#modulationOutputBuffer.recoverMemory(this.#envelopeTracker.modulation_output_buffer_ptr());
-      this.#triggerOutputBuffer.recoverMemory(this.#envelopeTracker.trigger_output_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -67,6 +55,7 @@ class EnvelopeTrackerProcessor extends AudioWorkletProcessor {
     this.#inputBuffer.setChannelData(inputList[0][0], 0);
 
     this.#envelopeTracker.process(
+      RENDER_QUANTUM_FRAMES,
       parameters.sensitivity[0],
       parameters.threshold[0]
     );
@@ -77,6 +66,22 @@ class EnvelopeTrackerProcessor extends AudioWorkletProcessor {
     return true;
   }
 
+  #recoverMemory() {
+    this.#inputBuffer.recoverMemory(this.#envelopeTracker.input_buffer_ptr());
+    this.#modulationOutputBuffer.recoverMemory(this.#envelopeTracker.modulation_output_buffer_ptr());
+    this.#triggerOutputBuffer.recoverMemory(this.#envelopeTracker.trigger_output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#envelopeTracker.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#envelopeTracker.free();
     this.#inputBuffer.free();
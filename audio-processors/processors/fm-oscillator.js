@@ -9,8 +9,12 @@ class FMOscillatorProcessor extends AudioWorkletProcessor {
 
   #frequencyBuffer = new HeapParameterBuffer(this.#fmOscillator.frequency_buffer_ptr());
   #phaseShiftBuffer = new HeapParameterBuffer(this.#fmOscillator.phase_shift_buffer_ptr());
+  #fmIndexBuffer = new HeapParameterBuffer(this.#fmOscillator.fm_index_buffer_ptr());
+  #modulatorBuffer = new HeapAudioBuffer(this.#fmOscillator.modulator_buffer_ptr(), 1);
   #outputBuffer = new HeapAudioBuffer(this.#fmOscillator.output_buffer_ptr(), 2);
 
+  #channelCount = 2;
+
   #destroyed = false;
 
   constructor() {
@@ -21,33 +25,17 @@ class FMOscillatorProcessor extends AudioWorkletProcessor {
         this.#destroy();
       } else if (e.data === 'reset') {
         this.#fmOscillator.reset();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'frequency',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 4186.009,
-      },
-      {
-        name: 'pitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'phaseShift',
-        defaultValue: 0,
-        minValue: 0,
-        maxValue: 1,
-      },
-    ]);
+    return JSON.parse(FMOscillator.parameter_descriptors());
   }
 
   /**
@@ -55,37 +43,66 @@ class FMOscillatorProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#outputBuffer.recoverMemory(this.#fmOscillator.output_buffer_ptr());
-      this.#frequencyBuffer.recoverMemory(this.#fmOscillator.frequency_buffer_ptr());
-      this.#phaseShiftBuffer.recoverMemory(this.#fmOscillator.phase_shift_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
   /**
-   * @param {Float32Array[][]} _inputList 
+   * @param {Float32Array[][]} inputList 
    * @param {Float32Array[][]} outputList 
    * @param {Record<import('./types').ParameterName<typeof FMOscillatorProcessor>, Float32Array>} parameters 
    */
-  process(_inputList, outputList, parameters) {
+  process(inputList, outputList, parameters) {
     if (this.#destroyed) {
       return false;
     }
 
     this.#frequencyBuffer.setData(parameters.frequency);
     this.#phaseShiftBuffer.setData(parameters.phaseShift);
+    this.#fmIndexBuffer.setData(parameters.fmIndex);
+
+    const modulator = inputList[0];
+    if (modulator.length === 0) {
+      this.#modulatorBuffer.getChannelData(0).fill(0);
+    } else {
+      this.#modulatorBuffer.setChannelData(modulator[0], 0);
+    }
 
     this.#fmOscillator.process(
+      RENDER_QUANTUM_FRAMES,
       parameters.pitchShift[0]
     );
 
-    outputList[0][0].set(this.#outputBuffer.getChannelData(0));
-    outputList[0][1].set(this.#outputBuffer.getChannelData(1));
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      const source = Math.min(channel, this.#channelCount - 1);
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
+    }
 
     return true;
   }
 
+  #recoverMemory() {
+    this.#outputBuffer.recoverMemory(this.#fmOscillator.output_buffer_ptr(), this.#channelCount);
+    this.#frequencyBuffer.recoverMemory(this.#fmOscillator.frequency_buffer_ptr());
+    this.#phaseShiftBuffer.recoverMemory(this.#fmOscillator.phase_shift_buffer_ptr());
+    this.#fmIndexBuffer.recoverMemory(this.#fmOscillator.fm_index_buffer_ptr());
+    this.#modulatorBuffer.recoverMemory(this.#fmOscillator.modulator_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#channelCount = channelCount;
+    this.#fmOscillator.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#fmOscillator.free();
+    this.#modulatorBuffer.free();
     this.#outputBuffer.free();
     cachedF32Memory.unregisterListener(this);
     this.#destroyed = true;
@@ -1,59 +1,156 @@
-import { FMSynthesizer } from '../pkg/audio_processors';
+import { FMSynthesizer, parse_dx7_sysex } from '../pkg/audio_processors';
 import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
 import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
 import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
 import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
 
+/**
+ * @typedef {{ type: 'noteOn', note: number, velocity: number, offset?: number }
+ *   | { type: 'noteOff', note: number, offset?: number }
+ *   | { type: 'allNotesOff', offset?: number }
+ *   | { type: 'parseSysex', id: number, data: Uint8Array }
+ *   | { type: 'setSeed', seed: number }} FMVoiceCommand
+ */
+
 class FMVoiceProcessor extends AudioWorkletProcessor {
   #fmSynth = new FMSynthesizer(RENDER_QUANTUM_FRAMES, sampleRate, 2);
 
   // Single frequency input buffer for all 8 voices (8 interleaved channels)
   #frequencyInputBuffer = new HeapAudioBuffer(this.#fmSynth.frequency_input_buffer_ptr(), 8);
   #operatorLevelBuffers = [
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(0)),
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(1)),
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(2)),
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(3)),
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(4)),
-    new HeapParameterBuffer(this.#fmSynth.operator_level_ptr(5)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_level_buffers_ptr(5)),
   ];
   #operatorAttackBuffers = [
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(0)),
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(1)),
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(2)),
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(3)),
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(4)),
-    new HeapParameterBuffer(this.#fmSynth.operator_attack_ptr(5)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_attack_buffers_ptr(5)),
   ];
   #operatorDecayBuffers = [
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(0)),
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(1)),
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(2)),
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(3)),
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(4)),
-    new HeapParameterBuffer(this.#fmSynth.operator_decay_ptr(5)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_decay_buffers_ptr(5)),
   ];
   #operatorSustainBuffers = [
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(0)),
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(1)),
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(2)),
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(3)),
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(4)),
-    new HeapParameterBuffer(this.#fmSynth.operator_sustain_ptr(5)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_sustain_buffers_ptr(5)),
   ];
   #operatorReleaseBuffers = [
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(0)),
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(1)),
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(2)),
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(3)),
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(4)),
-    new HeapParameterBuffer(this.#fmSynth.operator_release_ptr(5)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_release_buffers_ptr(5)),
+  ];
+  #operatorVelocitySensitivityBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(5)),
+  ];
+  #operatorKeyBreakpointBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_breakpoint_buffers_ptr(5)),
+  ];
+  #operatorKeyLeftDepthBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_depth_buffers_ptr(5)),
+  ];
+  #operatorKeyRightDepthBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_depth_buffers_ptr(5)),
   ];
-  // Single trigger and retrigger input buffers for all 8 voices (8 interleaved channels each)
+  #operatorKeyLeftCurveBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_left_curve_buffers_ptr(5)),
+  ];
+  #operatorKeyRightCurveBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_right_curve_buffers_ptr(5)),
+  ];
+  #operatorKeyRateScalingBuffers = [
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(0)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(1)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(2)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(3)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(4)),
+    new HeapParameterBuffer(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(5)),
+  ];
+  // Rate/level envelope stages, indexed by operator * 4 + stage
+  #operatorEgRateBuffers = Array.from(
+    { length: 24 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.operator_eg_rate_buffers_ptr(i)),
+  );
+  #operatorEgLevelBuffers = Array.from(
+    { length: 24 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.operator_eg_level_buffers_ptr(i)),
+  );
+  #operatorAmpModSensitivityBuffers = Array.from(
+    { length: 6 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.operator_amp_mod_sensitivity_buffers_ptr(i)),
+  );
+  #operatorFixedFrequencyBuffers = Array.from(
+    { length: 6 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.operator_fixed_frequency_buffers_ptr(i)),
+  );
+  #pitchEgRateBuffers = Array.from(
+    { length: 4 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.pitch_eg_rate_buffers_ptr(i)),
+  );
+  #pitchEgLevelBuffers = Array.from(
+    { length: 4 },
+    (_, i) => new HeapParameterBuffer(this.#fmSynth.pitch_eg_level_buffers_ptr(i)),
+  );
+  #lfoSpeedBuffer = new HeapParameterBuffer(this.#fmSynth.lfo_speed_buffer_ptr());
+  #lfoDelayBuffer = new HeapParameterBuffer(this.#fmSynth.lfo_delay_buffer_ptr());
+  #lfoFadeBuffer = new HeapParameterBuffer(this.#fmSynth.lfo_fade_buffer_ptr());
+  #lfoPitchModDepthBuffer = new HeapParameterBuffer(this.#fmSynth.lfo_pitch_mod_depth_buffer_ptr());
+  #lfoAmpModDepthBuffer = new HeapParameterBuffer(this.#fmSynth.lfo_amp_mod_depth_buffer_ptr());
+  // Single trigger, retrigger and velocity input buffers for all 8 voices (8 interleaved channels each)
   #triggerInputBuffer = new HeapAudioBuffer(this.#fmSynth.trigger_input_buffer_ptr(), 8);
   #retriggerInputBuffer = new HeapAudioBuffer(this.#fmSynth.retrigger_input_buffer_ptr(), 8);
+  #velocityInputBuffer = new HeapAudioBuffer(this.#fmSynth.velocity_input_buffer_ptr(), 8);
   #outputBuffer = new HeapAudioBuffer(this.#fmSynth.output_buffer_ptr(), 2);
 
+  #channelCount = 2;
+
   #destroyed = false;
 
   constructor() {
@@ -64,279 +161,59 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
         this.#destroy();
       } else if (e.data === 'reset') {
         this.#fmSynth.reset();
+      } else {
+        this.#handleCommand(e.data);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'algorithm',
-        defaultValue: 1,
-        minValue: 1,
-        maxValue: 32,
-      },
-      // Operator 1
-      {
-        name: 'op1Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op1PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op1FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op1Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op1Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op1Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op1Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-      // Operator 2
-      {
-        name: 'op2Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op2PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op2FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op2Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op2Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op2Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op2Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-      // Operator 3
-      {
-        name: 'op3Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op3PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op3FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op3Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op3Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op3Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op3Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-      // Operator 4
-      {
-        name: 'op4Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op4PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op4FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op4Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op4Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op4Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op4Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-      // Operator 5
-      {
-        name: 'op5Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op5PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op5FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op5Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op5Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op5Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op5Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-      // Operator 6
-      {
-        name: 'op6Level',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op6PitchShift',
-        defaultValue: 0,
-        minValue: -24,
-        maxValue: 24,
-      },
-      {
-        name: 'op6FineTune',
-        defaultValue: 0,
-        minValue: -100,
-        maxValue: 100,
-      },
-      {
-        name: 'op6Attack',
-        defaultValue: 0.01,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op6Decay',
-        defaultValue: 0.0,
-        minValue: 0.0,
-        maxValue: 2.0,
-      },
-      {
-        name: 'op6Sustain',
-        defaultValue: 1.0,
-        minValue: 0.0,
-        maxValue: 1.0,
-      },
-      {
-        name: 'op6Release',
-        defaultValue: 0.1,
-        minValue: 0.001,
-        maxValue: 2.0,
-      },
-    ]);
+    return JSON.parse(FMSynthesizer.parameter_descriptors());
+  }
+
+  /**
+   * Note events are played `offset` samples into the next render quantum.
+   * @param {FMVoiceCommand} command
+   */
+  #handleCommand(command) {
+    const offset = command.offset ?? 0;
+    switch (command.type) {
+      case 'noteOn':
+        this.#fmSynth.note_on(command.note, command.velocity, offset);
+        break;
+      case 'noteOff':
+        this.#fmSynth.note_off(command.note, offset);
+        break;
+      case 'allNotesOff':
+        this.#fmSynth.all_notes_off(offset);
+        break;
+      case 'parseSysex':
+        this.#parseSysex(command.id, command.data);
+        break;
+      case 'setSeed':
+        this.#fmSynth.set_seed(command.seed >>> 0);
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
+  }
+
+  /**
+   * Posts the presets found in a DX7 sysex dump back to the node.
+   * @param {number} id
+   * @param {Uint8Array} data
+   */
+  #parseSysex(id, data) {
+    try {
+      const presets = JSON.parse(parse_dx7_sysex(data));
+      this.port.postMessage({ type: 'sysexParsed', id, presets });
+    } catch (error) {
+      this.port.postMessage({ type: 'sysexParsed', id, error: String(error) });
+    }
   }
 
   /**
@@ -344,17 +221,7 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#outputBuffer.recoverMemory(this.#fmSynth.output_buffer_ptr());
-      this.#frequencyInputBuffer.recoverMemory(this.#fmSynth.frequency_input_buffer_ptr());
-      this.#triggerInputBuffer.recoverMemory(this.#fmSynth.trigger_input_buffer_ptr());
-      this.#retriggerInputBuffer.recoverMemory(this.#fmSynth.retrigger_input_buffer_ptr());
-      for (let i = 0; i < 6; i++) {
-        this.#operatorLevelBuffers[i].recoverMemory(this.#fmSynth.operator_level_ptr(i));
-        this.#operatorAttackBuffers[i].recoverMemory(this.#fmSynth.operator_attack_ptr(i));
-        this.#operatorDecayBuffers[i].recoverMemory(this.#fmSynth.operator_decay_ptr(i));
-        this.#operatorSustainBuffers[i].recoverMemory(this.#fmSynth.operator_sustain_ptr(i));
-        this.#operatorReleaseBuffers[i].recoverMemory(this.#fmSynth.operator_release_ptr(i));
-      }
+      this.#recoverMemory();
     }
   }
 
@@ -368,7 +235,8 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
       return false;
     }
 
-    // Set operator parameter buffers (shared across all voices)
+    // Set operator parameter buffers (shared across all voices). Only level
+    // and ADSR are a-rate, the synth reads the rest once per block.
     for (let i = 0; i < 6; i++) {
       const idx = /** @type {1 | 2 | 3 | 4 | 5 | 6} */ (i + 1);
       this.#operatorLevelBuffers[i].setData(parameters[`op${idx}Level`]);
@@ -376,7 +244,33 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
       this.#operatorDecayBuffers[i].setData(parameters[`op${idx}Decay`]);
       this.#operatorSustainBuffers[i].setData(parameters[`op${idx}Sustain`]);
       this.#operatorReleaseBuffers[i].setData(parameters[`op${idx}Release`]);
+      this.#operatorVelocitySensitivityBuffers[i].setData(parameters[`op${idx}VelocitySensitivity`]);
+      this.#operatorKeyBreakpointBuffers[i].setData(parameters[`op${idx}KeyBreakpoint`]);
+      this.#operatorKeyLeftDepthBuffers[i].setData(parameters[`op${idx}KeyLeftDepth`]);
+      this.#operatorKeyRightDepthBuffers[i].setData(parameters[`op${idx}KeyRightDepth`]);
+      this.#operatorKeyLeftCurveBuffers[i].setData(parameters[`op${idx}KeyLeftCurve`]);
+      this.#operatorKeyRightCurveBuffers[i].setData(parameters[`op${idx}KeyRightCurve`]);
+      this.#operatorKeyRateScalingBuffers[i].setData(parameters[`op${idx}KeyRateScaling`]);
+      this.#operatorAmpModSensitivityBuffers[i].setData(parameters[`op${idx}AmpModSensitivity`]);
+      this.#operatorFixedFrequencyBuffers[i].setData(parameters[`op${idx}FixedFrequency`]);
+      for (let stage = 0; stage < 4; stage++) {
+        const stageIdx = /** @type {1 | 2 | 3 | 4} */ (stage + 1);
+        this.#operatorEgRateBuffers[i * 4 + stage].setData(parameters[`op${idx}EgRate${stageIdx}`]);
+        this.#operatorEgLevelBuffers[i * 4 + stage].setData(parameters[`op${idx}EgLevel${stageIdx}`]);
+      }
+    }
+
+    // Pitch envelope and LFO (shared across all voices)
+    for (let stage = 0; stage < 4; stage++) {
+      const stageIdx = /** @type {1 | 2 | 3 | 4} */ (stage + 1);
+      this.#pitchEgRateBuffers[stage].setData(parameters[`pitchEgRate${stageIdx}`]);
+      this.#pitchEgLevelBuffers[stage].setData(parameters[`pitchEgLevel${stageIdx}`]);
     }
+    this.#lfoSpeedBuffer.setData(parameters.lfoSpeed);
+    this.#lfoDelayBuffer.setData(parameters.lfoDelay);
+    this.#lfoFadeBuffer.setData(parameters.lfoFade);
+    this.#lfoPitchModDepthBuffer.setData(parameters.lfoPitchModDepth);
+    this.#lfoAmpModDepthBuffer.setData(parameters.lfoAmpModDepth);
 
     for (let voice = 0; voice < 8; voice++) {
       if (inputList[0]?.[voice]) {
@@ -388,9 +282,13 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
       if (inputList[2]?.[voice]) {
         this.#retriggerInputBuffer.setChannelData(inputList[2][voice], voice);
       }
+      if (inputList[3]?.[voice]) {
+        this.#velocityInputBuffer.setChannelData(inputList[3][voice], voice);
+      }
     }
 
     this.#fmSynth.process(
+      RENDER_QUANTUM_FRAMES,
       parameters.algorithm[0],
       parameters.op1PitchShift[0],
       parameters.op2PitchShift[0],
@@ -404,21 +302,78 @@ class FMVoiceProcessor extends AudioWorkletProcessor {
       parameters.op4FineTune[0],
       parameters.op5FineTune[0],
       parameters.op6FineTune[0],
+      parameters.voiceMode[0],
+      parameters.stealMode[0],
+      parameters.feedback[0],
+      parameters.envelopeMode[0],
+      parameters.lfoWaveform[0],
+      parameters.lfoKeySync[0],
     );
 
-    outputList[0][0].set(this.#outputBuffer.getChannelData(0));
-    outputList[0][1].set(this.#outputBuffer.getChannelData(1));
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      const source = Math.min(channel, this.#channelCount - 1);
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
+    }
     // console.log([...outputList[0][0]], [...outputList[0][1]])
 
     return true;
   }
 
+  #recoverMemory() {
+    this.#outputBuffer.recoverMemory(this.#fmSynth.output_buffer_ptr(), this.#channelCount);
+    this.#frequencyInputBuffer.recoverMemory(this.#fmSynth.frequency_input_buffer_ptr());
+    this.#triggerInputBuffer.recoverMemory(this.#fmSynth.trigger_input_buffer_ptr());
+    this.#retriggerInputBuffer.recoverMemory(this.#fmSynth.retrigger_input_buffer_ptr());
+    this.#velocityInputBuffer.recoverMemory(this.#fmSynth.velocity_input_buffer_ptr());
+    for (let i = 0; i < 6; i++) {
+      this.#operatorLevelBuffers[i].recoverMemory(this.#fmSynth.operator_level_buffers_ptr(i));
+      this.#operatorAttackBuffers[i].recoverMemory(this.#fmSynth.operator_attack_buffers_ptr(i));
+      this.#operatorDecayBuffers[i].recoverMemory(this.#fmSynth.operator_decay_buffers_ptr(i));
+      this.#operatorSustainBuffers[i].recoverMemory(this.#fmSynth.operator_sustain_buffers_ptr(i));
+      this.#operatorReleaseBuffers[i].recoverMemory(this.#fmSynth.operator_release_buffers_ptr(i));
+      this.#operatorVelocitySensitivityBuffers[i].recoverMemory(this.#fmSynth.operator_velocity_sensitivity_buffers_ptr(i));
+      this.#operatorKeyBreakpointBuffers[i].recoverMemory(this.#fmSynth.operator_key_breakpoint_buffers_ptr(i));
+      this.#operatorKeyLeftDepthBuffers[i].recoverMemory(this.#fmSynth.operator_key_left_depth_buffers_ptr(i));
+      this.#operatorKeyRightDepthBuffers[i].recoverMemory(this.#fmSynth.operator_key_right_depth_buffers_ptr(i));
+      this.#operatorKeyLeftCurveBuffers[i].recoverMemory(this.#fmSynth.operator_key_left_curve_buffers_ptr(i));
+      this.#operatorKeyRightCurveBuffers[i].recoverMemory(this.#fmSynth.operator_key_right_curve_buffers_ptr(i));
+      this.#operatorKeyRateScalingBuffers[i].recoverMemory(this.#fmSynth.operator_key_rate_scaling_buffers_ptr(i));
+      this.#operatorAmpModSensitivityBuffers[i].recoverMemory(this.#fmSynth.operator_amp_mod_sensitivity_buffers_ptr(i));
+      this.#operatorFixedFrequencyBuffers[i].recoverMemory(this.#fmSynth.operator_fixed_frequency_buffers_ptr(i));
+    }
+    for (let i = 0; i < 4; i++) {
+      this.#pitchEgRateBuffers[i].recoverMemory(this.#fmSynth.pitch_eg_rate_buffers_ptr(i));
+      this.#pitchEgLevelBuffers[i].recoverMemory(this.#fmSynth.pitch_eg_level_buffers_ptr(i));
+    }
+    this.#lfoSpeedBuffer.recoverMemory(this.#fmSynth.lfo_speed_buffer_ptr());
+    this.#lfoDelayBuffer.recoverMemory(this.#fmSynth.lfo_delay_buffer_ptr());
+    this.#lfoFadeBuffer.recoverMemory(this.#fmSynth.lfo_fade_buffer_ptr());
+    this.#lfoPitchModDepthBuffer.recoverMemory(this.#fmSynth.lfo_pitch_mod_depth_buffer_ptr());
+    this.#lfoAmpModDepthBuffer.recoverMemory(this.#fmSynth.lfo_amp_mod_depth_buffer_ptr());
+    for (let i = 0; i < 24; i++) {
+      this.#operatorEgRateBuffers[i].recoverMemory(this.#fmSynth.operator_eg_rate_buffers_ptr(i));
+      this.#operatorEgLevelBuffers[i].recoverMemory(this.#fmSynth.operator_eg_level_buffers_ptr(i));
+    }
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#channelCount = channelCount;
+    this.#fmSynth.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#fmSynth.free();
     this.#outputBuffer.free();
     this.#frequencyInputBuffer.free();
     this.#triggerInputBuffer.free();
     this.#retriggerInputBuffer.free();
+    this.#velocityInputBuffer.free();
     cachedF32Memory.unregisterListener(this);
     this.#destroyed = true;
   }
@@ -18,6 +18,8 @@ class GraindrProcessor extends AudioWorkletProcessor {
     #shimmerBuffer = new HeapParameterBuffer(this.#graindr.shimmer_buffer_ptr());
     #textureBuffer = new HeapParameterBuffer(this.#graindr.texture_buffer_ptr());
 
+    #channelCount = CHANNELS;
+
     #destroyed = false;
 
     constructor() {
@@ -28,83 +30,17 @@ class GraindrProcessor extends AudioWorkletProcessor {
                 this.#graindr.reset();
             } else if (e.data === 'destroy') {
                 this.#destroy();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
         cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'dryWetMix',
-                defaultValue: 0.0,
-                minValue: 0.0,
-                maxValue: 1.0
-            },
-            {
-                name: 'grainSizeMs',
-                defaultValue: 50.0,
-                minValue: 1.0,
-                maxValue: 1000.0
-            },
-            {
-                name: 'pitchShift',
-                defaultValue: 0,
-                minValue: -12,
-                maxValue: 12
-            },
-            {
-                name: 'fineTune',
-                defaultValue: 0,
-                minValue: -100,
-                maxValue: 100
-            },
-            {
-                name: 'texture',
-                defaultValue: 0.5,
-                minValue: 0.0,
-                maxValue: 1.0
-            },
-            {
-                name: 'stretch',
-                defaultValue: 1,
-                minValue: 1,
-                maxValue: 4
-            },
-            {
-                name: 'shimmer',
-                defaultValue: 0,
-                minValue: 0.0,
-                maxValue: 1.0
-            },
-            {
-                name: 'feedback',
-                defaultValue: 0,
-                minValue: 0.0,
-                maxValue: 1.0
-            },
-            {
-                name: 'hiCut',
-                defaultValue: 22000.0,
-                minValue: 20.0,
-                maxValue: 22000.0
-            },
-            {
-                name: 'playbackDirection',
-                defaultValue: 0,
-                minValue: 0,
-                maxValue: 2,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'toneType',
-                defaultValue: 0,
-                minValue: 0,
-                maxValue: 1,
-                automationRate: 'k-rate'
-            }
-        ]);
+        return JSON.parse(Graindr.parameter_descriptors());
     }
 
     /**
@@ -112,13 +48,7 @@ class GraindrProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#inputBuffer.recoverMemory(this.#graindr.input_buffer_ptr());
-            this.#outputBuffer.recoverMemory(this.#graindr.output_buffer_ptr());
-            this.#feedbackBuffer.recoverMemory(this.#graindr.feedback_buffer_ptr());
-            this.#grainSizeMsBuffer.recoverMemory(this.#graindr.grain_size_ms_buffer_ptr());
-            this.#hiCutFreqBuffer.recoverMemory(this.#graindr.hi_cut_freq_buffer_ptr());
-            this.#shimmerBuffer.recoverMemory(this.#graindr.shimmer_buffer_ptr());
-            this.#textureBuffer.recoverMemory(this.#graindr.texture_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -134,7 +64,7 @@ class GraindrProcessor extends AudioWorkletProcessor {
 
         const input = inputList[0];
         const inputChannels = input.length - 1;
-        for (let channel = 0; channel < CHANNELS; channel++) {
+        for (let channel = 0; channel < this.#channelCount; channel++) {
             this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
         }
 
@@ -145,20 +75,44 @@ class GraindrProcessor extends AudioWorkletProcessor {
         this.#textureBuffer.setData(parameters.texture);
 
         this.#graindr.process(
+            RENDER_QUANTUM_FRAMES,
             parameters.dryWetMix[0],
             parameters.pitchShift[0],
             parameters.fineTune[0],
             parameters.stretch[0],
             parameters.playbackDirection[0],
             parameters.toneType[0],
+            parameters.oversampling[0],
         );
 
-        for (let channel = 0; channel < CHANNELS; channel++) {
-            outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+        for (let channel = 0; channel < outputList[0].length; channel++) {
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
         }
         return true;
     }
 
+    #recoverMemory() {
+        this.#inputBuffer.recoverMemory(this.#graindr.input_buffer_ptr(), this.#channelCount);
+        this.#outputBuffer.recoverMemory(this.#graindr.output_buffer_ptr(), this.#channelCount);
+        this.#feedbackBuffer.recoverMemory(this.#graindr.feedback_buffer_ptr());
+        this.#grainSizeMsBuffer.recoverMemory(this.#graindr.grain_size_ms_buffer_ptr());
+        this.#hiCutFreqBuffer.recoverMemory(this.#graindr.hi_cut_freq_buffer_ptr());
+        this.#shimmerBuffer.recoverMemory(this.#graindr.shimmer_buffer_ptr());
+        this.#textureBuffer.recoverMemory(this.#graindr.texture_buffer_ptr());
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#graindr.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#graindr.free();
         this.#inputBuffer.free();
@@ -84,9 +84,11 @@ export class HeapAudioBuffer {
     /**
      * 
      * @param {number} dataPtr 
+     * @param {number} [channelCount] 
      */
-    recoverMemory(dataPtr) {
+    recoverMemory(dataPtr, channelCount = this.#channelCount) {
         this.free();
+        this.#channelCount = channelCount;
         this.#dataPtr = dataPtr;
         this.#allocateHeap();
     }
@@ -0,0 +1,137 @@
+import { KarplusStrong } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+/**
+ * @typedef {{ type: 'setSeed', seed: number }} KarplusStrongCommand
+ */
+
+class KarplusStrongProcessor extends AudioWorkletProcessor {
+  #string = new KarplusStrong(RENDER_QUANTUM_FRAMES, sampleRate);
+
+  #frequencyBuffer = new HeapParameterBuffer(this.#string.frequency_buffer_ptr());
+
+  #pitchBuffer = new HeapAudioBuffer(this.#string.pitch_buffer_ptr(), 1);
+  #triggerBuffer = new HeapAudioBuffer(this.#string.trigger_buffer_ptr(), 1);
+  #excitationBuffer = new HeapAudioBuffer(this.#string.excitation_buffer_ptr(), 1);
+  #outputBuffer = new HeapAudioBuffer(this.#string.output_buffer_ptr(), 1);
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#string.reset();
+      } else {
+        this.#handleCommand(e.data);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /** @returns {import('./types').ParameterDescriptor[]} */
+  static get parameterDescriptors() {
+    return JSON.parse(KarplusStrong.parameter_descriptors());
+  }
+
+  /**
+   * @param {KarplusStrongCommand} command
+   */
+  #handleCommand(command) {
+    switch (command.type) {
+      case 'setSeed':
+        this.#string.set_seed(command.seed >>> 0);
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
+  }
+
+  /**
+   * @param {Event} e 
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList 
+   * @param {Float32Array[][]} outputList 
+   * @param {Record<import('./types').ParameterName<typeof KarplusStrongProcessor>, Float32Array>} parameters 
+   */
+  process(inputList, outputList, parameters) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    this.#frequencyBuffer.setData(parameters.frequency);
+
+    this.#setInput(this.#pitchBuffer, inputList[0]);
+    this.#setInput(this.#triggerBuffer, inputList[1]);
+    this.#setInput(this.#excitationBuffer, inputList[2]);
+
+    this.#string.process(
+      RENDER_QUANTUM_FRAMES,
+      parameters.excitation[0],
+      parameters.damping[0],
+      parameters.brightness[0],
+      parameters.stretch[0]
+    );
+
+    outputList[0][0].set(this.#outputBuffer.getChannelData(0));
+
+    return true;
+  }
+
+  /**
+   * Unconnected inputs have no channels and read as silence.
+   * @param {HeapAudioBuffer} buffer 
+   * @param {Float32Array[]} input 
+   */
+  #setInput(buffer, input) {
+    if (input.length === 0) {
+      buffer.getChannelData(0).fill(0);
+    } else {
+      buffer.setChannelData(input[0], 0);
+    }
+  }
+
+  #recoverMemory() {
+    this.#frequencyBuffer.recoverMemory(this.#string.frequency_buffer_ptr());
+    this.#pitchBuffer.recoverMemory(this.#string.pitch_buffer_ptr());
+    this.#triggerBuffer.recoverMemory(this.#string.trigger_buffer_ptr());
+    this.#excitationBuffer.recoverMemory(this.#string.excitation_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#string.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#string.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#string.free();
+    this.#pitchBuffer.free();
+    this.#triggerBuffer.free();
+    this.#excitationBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('karplus-strong', KarplusStrongProcessor);
@@ -1,14 +1,26 @@
-import { LFO, LFOPolarity, LFOWaveform } from '../pkg/audio_processors';
+import { LFO } from '../pkg/audio_processors';
 import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
 import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
 import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
 import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
 
+const SPREAD_CHANNELS = 4;
+
+/**
+ * @typedef {{ type: 'setSeed', seed: number }
+ *   | { type: 'loadTable', table: Float32Array }
+ *   | { type: 'loadBreakpoints', breakpoints: Float32Array }} LFOCommand
+ */
+
 class LFOProcessor extends AudioWorkletProcessor {
   #lfo = new LFO(RENDER_QUANTUM_FRAMES, sampleRate);
 
   #phaseShiftBuffer = new HeapParameterBuffer(this.#lfo.phase_shift_buffer_ptr());
+  #clockBuffer = new HeapAudioBuffer(this.#lfo.clock_buffer_ptr(), 1);
   #outputBuffer = new HeapAudioBuffer(this.#lfo.output_buffer_ptr(), 1);
+  #quadratureBuffer = new HeapAudioBuffer(this.#lfo.quadrature_buffer_ptr(), 1);
+  #invertedBuffer = new HeapAudioBuffer(this.#lfo.inverted_buffer_ptr(), 1);
+  #spreadBuffer = new HeapAudioBuffer(this.#lfo.spread_buffer_ptr(), SPREAD_CHANNELS);
 
   #destroyed = false;
 
@@ -20,39 +32,41 @@ class LFOProcessor extends AudioWorkletProcessor {
         this.#destroy();
       } else if (e.data === 'reset') {
         this.#lfo.reset();
+      } else {
+        this.#handleCommand(e.data);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'frequency',
-        defaultValue: 0.1,
-        minValue: 0.1,
-        maxValue: 20,
-      },
-      {
-        name: 'phase',
-        defaultValue: 0,
-        minValue: 0,
-        maxValue: 1,
-      },
-      {
-        name: 'polarity',
-        defaultValue: LFOPolarity.Bipolar,
-        minValue: LFOPolarity.Bipolar,
-        maxValue: LFOPolarity.Unipolar,
-      },
-      {
-        name: 'waveform',
-        defaultValue: LFOWaveform.Sine,
-        minValue: LFOWaveform.Sine,
-        maxValue: LFOWaveform.RSH,
-      },
-    ]);
+    return JSON.parse(LFO.parameter_descriptors());
+  }
+
+  /**
+   * @param {LFOCommand} command
+   */
+  #handleCommand(command) {
+    switch (command.type) {
+      case 'setSeed':
+        this.#lfo.set_seed(command.seed >>> 0);
+        break;
+      case 'loadTable':
+        if (!this.#lfo.load_custom_table(command.table)) {
+          console.warn('lfo: custom waveform table is empty');
+        }
+        break;
+      case 'loadBreakpoints':
+        if (!this.#lfo.load_custom_breakpoints(command.breakpoints)) {
+          console.warn('lfo: custom waveform breakpoints need rising phases in [0, 1]', command.breakpoints);
+        }
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
   }
 
   /**
@@ -60,8 +74,7 @@ class LFOProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#outputBuffer.recoverMemory(this.#lfo.output_buffer_ptr());
-      this.#phaseShiftBuffer.recoverMemory(this.#lfo.phase_shift_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -76,21 +89,61 @@ class LFOProcessor extends AudioWorkletProcessor {
     }
 
     this.#phaseShiftBuffer.setData(parameters.phase);
+    if (inputList[0].length === 0) {
+      this.#clockBuffer.getChannelData(0).fill(0);
+    } else {
+      this.#clockBuffer.setChannelData(inputList[0][0], 0);
+    }
 
     this.#lfo.process(
+      RENDER_QUANTUM_FRAMES,
       parameters.frequency[0],
       parameters.polarity[0],
-      parameters.waveform[0]
+      parameters.waveform[0],
+      parameters.tempoSync[0] !== 0,
+      parameters.bpm[0],
+      parameters.division[0],
+      parameters.divisionType[0],
+      parameters.skew[0],
+      parameters.spreadPhases[0]
     );
 
     outputList[0][0].set(this.#outputBuffer.getChannelData(0));
+    outputList[1][0].set(this.#quadratureBuffer.getChannelData(0));
+    outputList[2][0].set(this.#invertedBuffer.getChannelData(0));
+    for (let channel = 0; channel < SPREAD_CHANNELS; channel++) {
+      outputList[3][channel].set(this.#spreadBuffer.getChannelData(channel));
+    }
 
     return true;
   }
 
+  #recoverMemory() {
+    this.#outputBuffer.recoverMemory(this.#lfo.output_buffer_ptr());
+    this.#quadratureBuffer.recoverMemory(this.#lfo.quadrature_buffer_ptr());
+    this.#invertedBuffer.recoverMemory(this.#lfo.inverted_buffer_ptr());
+    this.#spreadBuffer.recoverMemory(this.#lfo.spread_buffer_ptr());
+    this.#phaseShiftBuffer.recoverMemory(this.#lfo.phase_shift_buffer_ptr());
+    this.#clockBuffer.recoverMemory(this.#lfo.clock_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#lfo.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#lfo.free();
+    this.#clockBuffer.free();
     this.#outputBuffer.free();
+    this.#quadratureBuffer.free();
+    this.#invertedBuffer.free();
+    this.#spreadBuffer.free();
     cachedF32Memory.unregisterListener(this);
     this.#destroyed = true;
   }
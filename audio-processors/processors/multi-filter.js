@@ -19,6 +19,8 @@ class MultiFilterProcessor extends AudioWorkletProcessor {
     #fcBuffer = new HeapParameterBuffer(this.#multiFilter.f_c_buffer_ptr());
     #qBuffer = new HeapParameterBuffer(this.#multiFilter.q_buffer_ptr());
 
+    #channelCount = CHANNELS;
+
     #destroyed = false;
 
     constructor() {
@@ -29,29 +31,17 @@ class MultiFilterProcessor extends AudioWorkletProcessor {
                 this.#multiFilter.reset();
             } else if (e.data === 'destroy') {
                 this.#destroy();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
         cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'cutoff',
-                defaultValue: 1000.0,
-                minValue: 20.0,
-                maxValue: 20480.0,
-                automationRate: 'a-rate'
-            },
-            {
-                name: 'q',
-                defaultValue: 0.707,
-                minValue: 0.707,
-                maxValue: 20.0,
-                automationRate: 'a-rate'
-            },
-        ]);
+        return JSON.parse(MultiFilter.parameter_descriptors());
     }
 
     /**
@@ -59,13 +49,7 @@ class MultiFilterProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#inputBuffer.recoverMemory(this.#multiFilter.input_buffer_ptr());
-            this.#bpfOutBuffer.recoverMemory(this.#multiFilter.bpf_out_buffer_ptr());
-            this.#bsfOutBuffer.recoverMemory(this.#multiFilter.bsf_out_buffer_ptr());
-            this.#hpfOutBuffer.recoverMemory(this.#multiFilter.hpf_out_buffer_ptr());
-            this.#lpfOutBuffer.recoverMemory(this.#multiFilter.lpf_out_buffer_ptr());
-            this.#fcBuffer.recoverMemory(this.#multiFilter.f_c_buffer_ptr());
-            this.#qBuffer.recoverMemory(this.#multiFilter.q_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -82,24 +66,46 @@ class MultiFilterProcessor extends AudioWorkletProcessor {
         const input = inputList[0];
 
         const inputChannels = input.length - 1;
-        for (let channel = 0; channel < CHANNELS; channel++) {
+        for (let channel = 0; channel < this.#channelCount; channel++) {
             this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
         }
         this.#fcBuffer.setData(parameters.cutoff);
         this.#qBuffer.setData(parameters.q);
 
-        this.#multiFilter.process();
+        this.#multiFilter.process(RENDER_QUANTUM_FRAMES);
 
-        for (let channel = 0; channel < CHANNELS; channel++) {
-            outputList[0][channel].set(this.#bpfOutBuffer.getChannelData(channel));
-            outputList[1][channel].set(this.#bsfOutBuffer.getChannelData(channel));
-            outputList[2][channel].set(this.#hpfOutBuffer.getChannelData(channel));
-            outputList[3][channel].set(this.#lpfOutBuffer.getChannelData(channel));
+        for (let channel = 0; channel < outputList[0].length; channel++) {
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#bpfOutBuffer.getChannelData(source));
+            outputList[1][channel].set(this.#bsfOutBuffer.getChannelData(source));
+            outputList[2][channel].set(this.#hpfOutBuffer.getChannelData(source));
+            outputList[3][channel].set(this.#lpfOutBuffer.getChannelData(source));
         }
 
         return true;
     }
 
+    #recoverMemory() {
+        this.#inputBuffer.recoverMemory(this.#multiFilter.input_buffer_ptr(), this.#channelCount);
+        this.#bpfOutBuffer.recoverMemory(this.#multiFilter.bpf_out_buffer_ptr(), this.#channelCount);
+        this.#bsfOutBuffer.recoverMemory(this.#multiFilter.bsf_out_buffer_ptr(), this.#channelCount);
+        this.#hpfOutBuffer.recoverMemory(this.#multiFilter.hpf_out_buffer_ptr(), this.#channelCount);
+        this.#lpfOutBuffer.recoverMemory(this.#multiFilter.lpf_out_buffer_ptr(), this.#channelCount);
+        this.#fcBuffer.recoverMemory(this.#multiFilter.f_c_buffer_ptr());
+        this.#qBuffer.recoverMemory(this.#multiFilter.q_buffer_ptr());
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#multiFilter.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#multiFilter.free();
         this.#inputBuffer.free();
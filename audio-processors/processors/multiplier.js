@@ -13,6 +13,8 @@ class MultiplierProcessor extends AudioWorkletProcessor {
 
     #outputBuffer = new HeapAudioBuffer(this.#multiplier.output_buffer_ptr(), CHANNELS);
 
+    #channelCount = CHANNELS;
+
     #destroyed = false;
 
     constructor() {
@@ -21,6 +23,8 @@ class MultiplierProcessor extends AudioWorkletProcessor {
         this.port.onmessage = ((e) => {
             if (e.data === 'destroy') {
                 this.#destroy();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
@@ -32,9 +36,7 @@ class MultiplierProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#input1Buffer.recoverMemory(this.#multiplier.input_1_buffer_ptr());
-            this.#input2Buffer.recoverMemory(this.#multiplier.input_2_buffer_ptr());
-            this.#outputBuffer.recoverMemory(this.#multiplier.output_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -52,20 +54,38 @@ class MultiplierProcessor extends AudioWorkletProcessor {
         const input2 = inputList[1];
         const input2Channels = input2.length - 1;
 
-        for (let channel = 0; channel < CHANNELS; channel++) {
+        for (let channel = 0; channel < this.#channelCount; channel++) {
             this.#input1Buffer.setChannelData(input1[Math.min(channel, input1Channels)], channel);
             this.#input2Buffer.setChannelData(input2[Math.min(channel, input2Channels)], channel);
         }
 
-        this.#multiplier.process();
+        this.#multiplier.process(RENDER_QUANTUM_FRAMES);
 
-        for (let channel = 0; channel < CHANNELS; channel++) {
-            outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+        for (let channel = 0; channel < outputList[0].length; channel++) {
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
         }
 
         return true;
     }
 
+    #recoverMemory() {
+        this.#input1Buffer.recoverMemory(this.#multiplier.input_1_buffer_ptr(), this.#channelCount);
+        this.#input2Buffer.recoverMemory(this.#multiplier.input_2_buffer_ptr(), this.#channelCount);
+        this.#outputBuffer.recoverMemory(this.#multiplier.output_buffer_ptr(), this.#channelCount);
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#multiplier.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#multiplier.free();
         this.#input1Buffer.free();
@@ -1,11 +1,20 @@
-import { ToggleUtil } from '../pkg/audio_processors';
+import { NoiseGenerator } from '../pkg/audio_processors';
 import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
 
 const CHANNELS = 2;
 
+/**
+ * @typedef {{ type: 'setSeed', seed: number }} NoiseGeneratorCommand
+ */
+
 class NoiseGeneratorProcessor extends AudioWorkletProcessor {
-    #brownianGenerator = new BrownianNoiseGenerator();
-    #toggleUtil = new ToggleUtil(sampleRate);
+    #noiseGenerator = new NoiseGenerator(RENDER_QUANTUM_FRAMES, sampleRate, CHANNELS);
+
+    #outputBuffer = new HeapAudioBuffer(this.#noiseGenerator.output_buffer_ptr(), CHANNELS);
+
+    #channelCount = CHANNELS;
 
     #destroyed = false;
 
@@ -14,23 +23,43 @@ class NoiseGeneratorProcessor extends AudioWorkletProcessor {
 
         this.port.onmessage = ((e) => {
             if (e.data === 'reset') {
-                this.#brownianGenerator.reset();
+                this.#noiseGenerator.reset();
             } else if (e.data === 'destroy') {
                 this.#destroy();
+            } else {
+                this.#handleCommand(e.data);
             }
         });
+
+        cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'noiseType',
-                defaultValue: 0,
-                minValue: 0,
-                maxValue: 1,
-                automationRate: 'k-rate'
-            }
-        ]);
+        return JSON.parse(NoiseGenerator.parameter_descriptors());
+    }
+
+    /**
+     * @param {NoiseGeneratorCommand} command
+     */
+    #handleCommand(command) {
+        switch (command.type) {
+            case 'setSeed':
+                this.#noiseGenerator.set_seed(command.seed >>> 0);
+                break;
+            case 'reconfigure':
+                this.#reconfigure(command.sampleRate, command.channelCount);
+                break;
+        }
+    }
+
+    /**
+     * @param {Event} e 
+     */
+    handleEvent(e) {
+        if (e.type === MEMORY_DETACHED_EVENT) {
+            this.#recoverMemory();
+        }
     }
 
     /**
@@ -43,62 +72,40 @@ class NoiseGeneratorProcessor extends AudioWorkletProcessor {
             return false;
         }
 
-        if (parameters.noiseType[0] === 0) {
-            this.#toggleUtil.to_a();
-        } else {
-            this.#toggleUtil.to_b();
-        }
-
-        this.#brownianGenerator.generateNormalisedSamples();
+        this.#noiseGenerator.process(
+            RENDER_QUANTUM_FRAMES,
+            parameters.noiseType[0],
+            parameters.stereo[0] !== 0
+        );
 
-        const o1 = outputList[0][0];
-        for (let n = 0; n < RENDER_QUANTUM_FRAMES; n++) {
-            o1[n] = this.#toggleUtil.mix(uniformRandomValue(), this.#brownianGenerator.normalisedSamples[n]);
-        }
-
-        for (let channel = 1; channel < CHANNELS; channel++) {
-            outputList[0][channel].set(o1);
+        for (let channel = 0; channel < outputList[0].length; channel++) {
+            const source = Math.min(channel, this.#channelCount - 1);
+            outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
         }
 
         return true;
     }
 
-    #destroy() {
-        this.#toggleUtil.free();
-        this.#destroyed = true;
+    #recoverMemory() {
+        this.#outputBuffer.recoverMemory(this.#noiseGenerator.output_buffer_ptr(), this.#channelCount);
     }
-}
-registerProcessor('noise-generator', NoiseGeneratorProcessor);
-
-function uniformRandomValue() {
-    const rnd = Math.random();
-    return rnd + rnd - 1;
-}
 
-class BrownianNoiseGenerator {
-    normalisedSamples = new Float32Array(RENDER_QUANTUM_FRAMES);
-    #unnormalisedSamples = new Float32Array(RENDER_QUANTUM_FRAMES);
-
-    generateNormalisedSamples() {
-        const init = /** @type {number} */ (this.#unnormalisedSamples.at(-1)) + uniformRandomValue();
-        this.#unnormalisedSamples[0] = init;
-        let min = init;
-        let max = init;
-        for (let i = 1; i < RENDER_QUANTUM_FRAMES; i++) {
-            const next = 0.95 * this.#unnormalisedSamples[i - 1] + uniformRandomValue();
-            this.#unnormalisedSamples[i] = next;
-            min = Math.min(min, next);
-            max = Math.max(max, next);
-        }
-
-        const rangeInv = 1 / (max - min);
-        for (let i = 1; i < RENDER_QUANTUM_FRAMES; i++) {
-            const diff = this.#unnormalisedSamples[i] - min;
-            this.normalisedSamples[i] = 0.8 * ((diff + diff) * rangeInv - 1);
-        }
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#channelCount = channelCount;
+        this.#noiseGenerator.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
     }
 
-    reset() {
-        this.#unnormalisedSamples.with(-1, 0);
+    #destroy() {
+        this.#noiseGenerator.free();
+        this.#outputBuffer.free();
+        cachedF32Memory.unregisterListener(this);
+        this.#destroyed = true;
     }
-}
\ No newline at end of file
+}
+registerProcessor('noise-generator', NoiseGeneratorProcessor);
@@ -0,0 +1,150 @@
+import { PatchGraph } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+const CHANNELS = 2;
+
+/**
+ * @typedef {{ type: 'addNode', kind: number }
+ *   | { type: 'connectNodes' | 'disconnectNodes', fromNode: number, fromOutput: number, toNode: number, toInput: number }
+ *   | { type: 'connectInput' | 'disconnectInput', toNode: number, toInput: number }
+ *   | { type: 'setOutput', fromNode: number, fromOutput: number }
+ *   | { type: 'setParams', node: number, params: number[] }
+ *   | { type: 'setConstantInput', node: number, input: number, value: number }
+ *   | { type: 'setSeed', node: number, seed: number }} PatchGraphCommand
+ */
+
+class PatchGraphProcessor extends AudioWorkletProcessor {
+  #patchGraph = new PatchGraph(RENDER_QUANTUM_FRAMES, sampleRate, CHANNELS);
+
+  #inputBuffer = new HeapAudioBuffer(this.#patchGraph.input_buffer_ptr(), CHANNELS);
+  #outputBuffer = new HeapAudioBuffer(this.#patchGraph.output_buffer_ptr(), CHANNELS);
+
+  #channelCount = CHANNELS;
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#patchGraph.reset();
+      } else {
+        this.#handleCommand(e.data);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /**
+   * @param {PatchGraphCommand} command
+   */
+  #handleCommand(command) {
+    switch (command.type) {
+      case 'addNode':
+        if (this.#patchGraph.add_node(command.kind) === undefined) {
+          console.warn(`patch-graph: unknown node kind ${command.kind}`);
+        }
+        break;
+      case 'connectNodes':
+        if (!this.#patchGraph.connect(command.fromNode, command.fromOutput, command.toNode, command.toInput)) {
+          console.warn('patch-graph: invalid or cyclic connection', command);
+        }
+        break;
+      case 'disconnectNodes':
+        this.#patchGraph.disconnect(command.fromNode, command.fromOutput, command.toNode, command.toInput);
+        break;
+      case 'connectInput':
+        this.#patchGraph.connect_input(command.toNode, command.toInput);
+        break;
+      case 'disconnectInput':
+        this.#patchGraph.disconnect_input(command.toNode, command.toInput);
+        break;
+      case 'setOutput':
+        this.#patchGraph.set_output(command.fromNode, command.fromOutput);
+        break;
+      case 'setParams':
+        if (!this.#patchGraph.set_params(command.node, new Float32Array(command.params))) {
+          console.warn(`patch-graph: unknown node ${command.node}`);
+        }
+        break;
+      case 'setConstantInput':
+        if (!this.#patchGraph.set_constant_input(command.node, command.input, command.value)) {
+          console.warn('patch-graph: invalid node input', command);
+        }
+        break;
+      case 'setSeed':
+        if (!this.#patchGraph.set_seed(command.node, command.seed >>> 0)) {
+          console.warn(`patch-graph: unknown node ${command.node}`);
+        }
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
+  }
+
+  /**
+   * @param {Event} e
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList
+   * @param {Float32Array[][]} outputList
+   */
+  process(inputList, outputList) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    const input = inputList[0];
+    const inputChannels = input.length - 1;
+    for (let channel = 0; channel < this.#channelCount; channel++) {
+      this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
+    }
+
+    this.#patchGraph.process(RENDER_QUANTUM_FRAMES);
+
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      const source = Math.min(channel, this.#channelCount - 1);
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
+    }
+
+    return true;
+  }
+
+  #recoverMemory() {
+    this.#inputBuffer.recoverMemory(this.#patchGraph.input_buffer_ptr(), this.#channelCount);
+    this.#outputBuffer.recoverMemory(this.#patchGraph.output_buffer_ptr(), this.#channelCount);
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#channelCount = channelCount;
+    this.#patchGraph.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#patchGraph.free();
+    this.#inputBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('patch-graph', PatchGraphProcessor);
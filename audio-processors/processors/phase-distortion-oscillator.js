@@ -0,0 +1,100 @@
+import { PhaseDistortionOscillator } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+class PhaseDistortionOscillatorProcessor extends AudioWorkletProcessor {
+  #oscillator = new PhaseDistortionOscillator(RENDER_QUANTUM_FRAMES, sampleRate);
+
+  #frequencyBuffer = new HeapParameterBuffer(this.#oscillator.frequency_buffer_ptr());
+  #amountBuffer = new HeapParameterBuffer(this.#oscillator.amount_buffer_ptr());
+
+  #pitchBuffer = new HeapAudioBuffer(this.#oscillator.pitch_buffer_ptr(), 1);
+  #outputBuffer = new HeapAudioBuffer(this.#oscillator.output_buffer_ptr(), 1);
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#oscillator.reset();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /** @returns {import('./types').ParameterDescriptor[]} */
+  static get parameterDescriptors() {
+    return JSON.parse(PhaseDistortionOscillator.parameter_descriptors());
+  }
+
+  /**
+   * @param {Event} e 
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList 
+   * @param {Float32Array[][]} outputList 
+   * @param {Record<import('./types').ParameterName<typeof PhaseDistortionOscillatorProcessor>, Float32Array>} parameters 
+   */
+  process(inputList, outputList, parameters) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    this.#frequencyBuffer.setData(parameters.frequency);
+    this.#amountBuffer.setData(parameters.amount);
+
+    const pitch = inputList[0];
+    if (pitch.length === 0) {
+      this.#pitchBuffer.getChannelData(0).fill(0);
+    } else {
+      this.#pitchBuffer.setChannelData(pitch[0], 0);
+    }
+
+    this.#oscillator.process(RENDER_QUANTUM_FRAMES, parameters.waveform[0]);
+
+    outputList[0][0].set(this.#outputBuffer.getChannelData(0));
+
+    return true;
+  }
+
+  #recoverMemory() {
+    this.#frequencyBuffer.recoverMemory(this.#oscillator.frequency_buffer_ptr());
+    this.#amountBuffer.recoverMemory(this.#oscillator.amount_buffer_ptr());
+    this.#pitchBuffer.recoverMemory(this.#oscillator.pitch_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#oscillator.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#oscillator.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#oscillator.free();
+    this.#pitchBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('phase-distortion-oscillator', PhaseDistortionOscillatorProcessor);
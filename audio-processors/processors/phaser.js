@@ -14,6 +14,8 @@ class PhaserProcessor extends AudioWorkletProcessor {
 
   #rateBuffer = new HeapParameterBuffer(this.#phaser.rate_buffer_ptr());
 
+  #channelCount = CHANNELS;
+
   #destroyed = false;
 
   constructor() {
@@ -24,22 +26,17 @@ class PhaserProcessor extends AudioWorkletProcessor {
         this.#phaser.reset();
       } else if (e.data === 'destroy') {
         this.#destroy();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'rate',
-        defaultValue: 0.5,
-        minValue: 0.1,
-        maxValue: 20.0,
-        automationRate: 'a-rate'
-      },
-    ]);
+    return JSON.parse(Phaser.parameter_descriptors());
   }
 
   /**
@@ -47,9 +44,7 @@ class PhaserProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#inputBuffer.recoverMemory(this.#phaser.input_buffer_ptr());
-      this.#outputBuffer.recoverMemory(this.#phaser.output_buffer_ptr());
-      this.#rateBuffer.recoverMemory(this.#phaser.rate_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -66,20 +61,38 @@ class PhaserProcessor extends AudioWorkletProcessor {
     const input = inputList[0];
 
     const inputChannels = input.length - 1;
-    for (let channel = 0; channel < CHANNELS; channel++) {
+    for (let channel = 0; channel < this.#channelCount; channel++) {
       this.#inputBuffer.setChannelData(input[Math.min(channel, inputChannels)], channel);
     }
     this.#rateBuffer.setData(parameters.rate);
 
-    this.#phaser.process();
+    this.#phaser.process(RENDER_QUANTUM_FRAMES);
 
-    for (let channel = 0; channel < CHANNELS; channel++) {
-      outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      const source = Math.min(channel, this.#channelCount - 1);
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(source));
     }
 
     return true;
   }
 
+  #recoverMemory() {
+    this.#inputBuffer.recoverMemory(this.#phaser.input_buffer_ptr(), this.#channelCount);
+    this.#outputBuffer.recoverMemory(this.#phaser.output_buffer_ptr(), this.#channelCount);
+    this.#rateBuffer.recoverMemory(this.#phaser.rate_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#channelCount = channelCount;
+    this.#phaser.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#phaser.free();
     this.#inputBuffer.free();
@@ -30,21 +30,17 @@ class PitchTrackerProcessor extends AudioWorkletProcessor {
         this.#destroy();
       } else if (e.data === 'reset') {
         this.#pitchTracker.reset();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
       }
     });
 
     cachedF32Memory.registerListener(this);
   }
 
+  /** @returns {import('./types').ParameterDescriptor[]} */
   static get parameterDescriptors() {
-    return /** @type {const} */ ([
-      {
-        name: 'harmonicThreshold',
-        defaultValue: 0.1,
-        minValue: 0.01,
-        maxValue: 1.0,
-      },
-    ]);
+    return JSON.parse(PitchTracker.parameter_descriptors());
   }
 
   /**
@@ -52,8 +48,7 @@ class PitchTrackerProcessor extends AudioWorkletProcessor {
    */
   handleEvent(e) {
     if (e.type === MEMORY_DETACHED_EVENT) {
-      this.#inputBuffer.recoverMemory(this.#pitchTracker.input_buffer_ptr());
-      this.#outputBuffer.recoverMemory(this.#pitchTracker.output_buffer_ptr());
+      this.#recoverMemory();
     }
   }
 
@@ -70,6 +65,7 @@ class PitchTrackerProcessor extends AudioWorkletProcessor {
     this.#inputBuffer.setChannelData(inputList[0][0], 0);
 
     this.#pitchTracker.process(
+      RENDER_QUANTUM_FRAMES,
       parameters.harmonicThreshold[0]
     );
 
@@ -82,6 +78,21 @@ class PitchTrackerProcessor extends AudioWorkletProcessor {
     return true;
   }
 
+  #recoverMemory() {
+    this.#inputBuffer.recoverMemory(this.#pitchTracker.input_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#pitchTracker.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#pitchTracker.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
   #destroy() {
     this.#pitchTracker.free();
     this.#inputBuffer.free();
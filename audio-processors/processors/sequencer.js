@@ -20,108 +20,17 @@ class SequencerProcessor extends AudioWorkletProcessor {
                 this.#destroy();
             } else if (e.data === 'reset') {
                 this.#sequencer.reset();
+            } else if (e.data?.type === 'reconfigure') {
+                this.#reconfigure(e.data.sampleRate, e.data.channelCount);
             }
         });
 
         cachedF32Memory.registerListener(this);
     }
 
+    /** @returns {import('./types').ParameterDescriptor[]} */
     static get parameterDescriptors() {
-        return /** @type {const} */ ([
-            {
-                name: 'gateThreshold',
-                defaultValue: 0,
-                minValue: -1,
-                maxValue: 1,
-            },
-            {
-                name: 'numSteps',
-                defaultValue: 4,
-                minValue: 1,
-                maxValue: 16,
-            },
-            {
-                name: 'value0',
-                defaultValue: 0,
-                automationRate: 'k-rate',
-            },
-            {
-                name: 'value1',
-                defaultValue: 0,
-                automationRate: 'k-rate',
-            },
-            {
-                name: 'value2',
-                defaultValue: 0,
-                automationRate: 'k-rate',
-            },
-            {
-                name: 'value3',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value4',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value5',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value6',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value7',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value8',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value9',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value10',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value11',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value12',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value13',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value14',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-            {
-                name: 'value15',
-                defaultValue: 0,
-                automationRate: 'k-rate'
-            },
-
-        ]);
+        return JSON.parse(Sequencer.parameter_descriptors());
     }
 
     /**
@@ -129,9 +38,7 @@ class SequencerProcessor extends AudioWorkletProcessor {
      */
     handleEvent(e) {
         if (e.type === MEMORY_DETACHED_EVENT) {
-            this.#gateInBuffer.recoverMemory(this.#sequencer.gate_in_buffer_ptr());
-            this.#outputBuffer.recoverMemory(this.#sequencer.output_buffer_ptr());
-            this.#valuesBuffer.recoverMemory(this.#sequencer.values_buffer_ptr());
+            this.#recoverMemory();
         }
     }
 
@@ -165,7 +72,7 @@ class SequencerProcessor extends AudioWorkletProcessor {
         values[14] = parameters.value14[0]
         values[15] = parameters.value15[0]
 
-        const currentStep = this.#sequencer.process(parameters.gateThreshold[0], parameters.numSteps[0]);
+        const currentStep = this.#sequencer.process(RENDER_QUANTUM_FRAMES, parameters.gateThreshold[0], parameters.numSteps[0]);
 
         outputList[0][0].set(this.#outputBuffer.getChannelData(0));
 
@@ -174,6 +81,22 @@ class SequencerProcessor extends AudioWorkletProcessor {
         return true;
     }
 
+    #recoverMemory() {
+        this.#gateInBuffer.recoverMemory(this.#sequencer.gate_in_buffer_ptr());
+        this.#outputBuffer.recoverMemory(this.#sequencer.output_buffer_ptr());
+        this.#valuesBuffer.recoverMemory(this.#sequencer.values_buffer_ptr());
+    }
+
+    /**
+     * @param {number} sampleRate 
+     * @param {number} channelCount 
+     */
+    #reconfigure(sampleRate, channelCount) {
+        this.#sequencer.reconfigure(sampleRate, channelCount);
+        // the buffers may have been reallocated
+        this.#recoverMemory();
+    }
+
     #destroy() {
         this.#sequencer.free();
         this.#gateInBuffer.free();
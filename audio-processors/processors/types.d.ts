@@ -1 +1,9 @@
-export type ParameterName<T extends { parameterDescriptors: readonly { name: string }[] }> = T['parameterDescriptors'][number]['name'];
\ No newline at end of file
+export type ParameterName<T extends { parameterDescriptors: readonly { name: string }[] }> = T['parameterDescriptors'][number]['name'];
+
+export type ParameterDescriptor = {
+    name: string;
+    defaultValue: number;
+    minValue?: number;
+    maxValue?: number;
+    automationRate?: AutomationRate;
+};
@@ -0,0 +1,139 @@
+import { UnisonOscillator } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+const CHANNELS = 2;
+
+/**
+ * @typedef {{ type: 'setSeed', seed: number }} UnisonOscillatorCommand
+ */
+
+class UnisonOscillatorProcessor extends AudioWorkletProcessor {
+  #oscillator = new UnisonOscillator(RENDER_QUANTUM_FRAMES, sampleRate);
+
+  #frequencyBuffer = new HeapParameterBuffer(this.#oscillator.frequency_buffer_ptr());
+
+  #pitchBuffer = new HeapAudioBuffer(this.#oscillator.pitch_buffer_ptr(), 1);
+  #gateBuffer = new HeapAudioBuffer(this.#oscillator.gate_buffer_ptr(), 1);
+  #outputBuffer = new HeapAudioBuffer(this.#oscillator.output_buffer_ptr(), CHANNELS);
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#oscillator.reset();
+      } else {
+        this.#handleCommand(e.data);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /** @returns {import('./types').ParameterDescriptor[]} */
+  static get parameterDescriptors() {
+    return JSON.parse(UnisonOscillator.parameter_descriptors());
+  }
+
+  /**
+   * @param {UnisonOscillatorCommand} command
+   */
+  #handleCommand(command) {
+    switch (command.type) {
+      case 'setSeed':
+        this.#oscillator.set_seed(command.seed >>> 0);
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
+  }
+
+  /**
+   * @param {Event} e 
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList 
+   * @param {Float32Array[][]} outputList 
+   * @param {Record<import('./types').ParameterName<typeof UnisonOscillatorProcessor>, Float32Array>} parameters 
+   */
+  process(inputList, outputList, parameters) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    this.#frequencyBuffer.setData(parameters.frequency);
+
+    this.#setInput(this.#pitchBuffer, inputList[0]);
+    this.#setInput(this.#gateBuffer, inputList[1]);
+
+    this.#oscillator.process(
+      RENDER_QUANTUM_FRAMES,
+      parameters.voices[0],
+      parameters.detune[0],
+      parameters.spread[0],
+      parameters.centerMix[0],
+      parameters.waveform[0],
+      parameters.phaseReset[0]
+    );
+
+    for (let channel = 0; channel < CHANNELS; channel++) {
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(channel));
+    }
+
+    return true;
+  }
+
+  /**
+   * Unconnected inputs have no channels and read as silence.
+   * @param {HeapAudioBuffer} buffer 
+   * @param {Float32Array[]} input 
+   */
+  #setInput(buffer, input) {
+    if (input.length === 0) {
+      buffer.getChannelData(0).fill(0);
+    } else {
+      buffer.setChannelData(input[0], 0);
+    }
+  }
+
+  #recoverMemory() {
+    this.#frequencyBuffer.recoverMemory(this.#oscillator.frequency_buffer_ptr());
+    this.#pitchBuffer.recoverMemory(this.#oscillator.pitch_buffer_ptr());
+    this.#gateBuffer.recoverMemory(this.#oscillator.gate_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#oscillator.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#oscillator.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#oscillator.free();
+    this.#pitchBuffer.free();
+    this.#gateBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('unison-oscillator', UnisonOscillatorProcessor);
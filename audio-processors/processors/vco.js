@@ -0,0 +1,126 @@
+import { VCO } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+class VCOProcessor extends AudioWorkletProcessor {
+  #vco = new VCO(RENDER_QUANTUM_FRAMES, sampleRate);
+
+  #frequencyBuffer = new HeapParameterBuffer(this.#vco.frequency_buffer_ptr());
+  #pulseWidthBuffer = new HeapParameterBuffer(this.#vco.pulse_width_buffer_ptr());
+
+  #pitchBuffer = new HeapAudioBuffer(this.#vco.pitch_buffer_ptr(), 1);
+  #fmBuffer = new HeapAudioBuffer(this.#vco.fm_buffer_ptr(), 1);
+  #pwmBuffer = new HeapAudioBuffer(this.#vco.pwm_buffer_ptr(), 1);
+  #syncBuffer = new HeapAudioBuffer(this.#vco.sync_buffer_ptr(), 1);
+  #outputBuffer = new HeapAudioBuffer(this.#vco.output_buffer_ptr(), 1);
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#vco.reset();
+      } else if (e.data?.type === 'reconfigure') {
+        this.#reconfigure(e.data.sampleRate, e.data.channelCount);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /** @returns {import('./types').ParameterDescriptor[]} */
+  static get parameterDescriptors() {
+    return JSON.parse(VCO.parameter_descriptors());
+  }
+
+  /**
+   * @param {Event} e 
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList 
+   * @param {Float32Array[][]} outputList 
+   * @param {Record<import('./types').ParameterName<typeof VCOProcessor>, Float32Array>} parameters 
+   */
+  process(inputList, outputList, parameters) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    this.#frequencyBuffer.setData(parameters.frequency);
+    this.#pulseWidthBuffer.setData(parameters.pulseWidth);
+
+    this.#setInput(this.#pitchBuffer, inputList[0]);
+    this.#setInput(this.#fmBuffer, inputList[1]);
+    this.#setInput(this.#pwmBuffer, inputList[2]);
+    this.#setInput(this.#syncBuffer, inputList[3]);
+
+    this.#vco.process(
+      RENDER_QUANTUM_FRAMES,
+      parameters.fmDepth[0],
+      parameters.waveform[0]
+    );
+
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(0));
+    }
+
+    return true;
+  }
+
+  /**
+   * Unconnected inputs have no channels and read as silence.
+   * @param {HeapAudioBuffer} buffer 
+   * @param {Float32Array[]} input 
+   */
+  #setInput(buffer, input) {
+    if (input.length === 0) {
+      buffer.getChannelData(0).fill(0);
+    } else {
+      buffer.setChannelData(input[0], 0);
+    }
+  }
+
+  #recoverMemory() {
+    this.#frequencyBuffer.recoverMemory(this.#vco.frequency_buffer_ptr());
+    this.#pulseWidthBuffer.recoverMemory(this.#vco.pulse_width_buffer_ptr());
+    this.#pitchBuffer.recoverMemory(this.#vco.pitch_buffer_ptr());
+    this.#fmBuffer.recoverMemory(this.#vco.fm_buffer_ptr());
+    this.#pwmBuffer.recoverMemory(this.#vco.pwm_buffer_ptr());
+    this.#syncBuffer.recoverMemory(this.#vco.sync_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#vco.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#vco.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#vco.free();
+    this.#pitchBuffer.free();
+    this.#fmBuffer.free();
+    this.#pwmBuffer.free();
+    this.#syncBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('vco', VCOProcessor);
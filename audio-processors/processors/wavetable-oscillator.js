@@ -0,0 +1,121 @@
+import { WavetableOscillator } from '../pkg/audio_processors';
+import { RENDER_QUANTUM_FRAMES } from './helpers/constants';
+import { HeapAudioBuffer } from './helpers/heap-audio-buffer';
+import { HeapParameterBuffer } from './helpers/heap-parameter-buffer';
+import { MEMORY_DETACHED_EVENT, cachedF32Memory } from './memory';
+
+/**
+ * @typedef {{ type: 'loadTable', table: Float32Array, frameSize: number }} WavetableCommand
+ */
+
+class WavetableOscillatorProcessor extends AudioWorkletProcessor {
+  #oscillator = new WavetableOscillator(RENDER_QUANTUM_FRAMES, sampleRate);
+
+  #frequencyBuffer = new HeapParameterBuffer(this.#oscillator.frequency_buffer_ptr());
+  #positionBuffer = new HeapParameterBuffer(this.#oscillator.position_buffer_ptr());
+  #pitchBuffer = new HeapAudioBuffer(this.#oscillator.pitch_buffer_ptr(), 1);
+  #outputBuffer = new HeapAudioBuffer(this.#oscillator.output_buffer_ptr(), 1);
+
+  #destroyed = false;
+
+  constructor() {
+    super();
+
+    this.port.onmessage = ((e) => {
+      if (e.data === 'destroy') {
+        this.#destroy();
+      } else if (e.data === 'reset') {
+        this.#oscillator.reset();
+      } else {
+        this.#handleCommand(e.data);
+      }
+    });
+
+    cachedF32Memory.registerListener(this);
+  }
+
+  /** @returns {import('./types').ParameterDescriptor[]} */
+  static get parameterDescriptors() {
+    return JSON.parse(WavetableOscillator.parameter_descriptors());
+  }
+
+  /**
+   * @param {Event} e 
+   */
+  handleEvent(e) {
+    if (e.type === MEMORY_DETACHED_EVENT) {
+      this.#recoverMemory();
+    }
+  }
+
+  /**
+   * @param {WavetableCommand} command
+   */
+  #handleCommand(command) {
+    switch (command.type) {
+      case 'loadTable':
+        if (!this.#oscillator.load_table(command.table, command.frameSize)) {
+          console.warn(`wavetable-oscillator: table of ${command.table.length} samples does not split into frames of ${command.frameSize}`);
+        }
+        break;
+      case 'reconfigure':
+        this.#reconfigure(command.sampleRate, command.channelCount);
+        break;
+    }
+  }
+
+  /**
+   * @param {Float32Array[][]} inputList 
+   * @param {Float32Array[][]} outputList 
+   * @param {Record<import('./types').ParameterName<typeof WavetableOscillatorProcessor>, Float32Array>} parameters 
+   */
+  process(inputList, outputList, parameters) {
+    if (this.#destroyed) {
+      return false;
+    }
+
+    this.#frequencyBuffer.setData(parameters.frequency);
+    this.#positionBuffer.setData(parameters.position);
+
+    const pitch = inputList[0];
+    if (pitch.length === 0) {
+      this.#pitchBuffer.getChannelData(0).fill(0);
+    } else {
+      this.#pitchBuffer.setChannelData(pitch[0], 0);
+    }
+
+    this.#oscillator.process(RENDER_QUANTUM_FRAMES);
+
+    for (let channel = 0; channel < outputList[0].length; channel++) {
+      outputList[0][channel].set(this.#outputBuffer.getChannelData(0));
+    }
+
+    return true;
+  }
+
+  #recoverMemory() {
+    this.#frequencyBuffer.recoverMemory(this.#oscillator.frequency_buffer_ptr());
+    this.#positionBuffer.recoverMemory(this.#oscillator.position_buffer_ptr());
+    this.#pitchBuffer.recoverMemory(this.#oscillator.pitch_buffer_ptr());
+    this.#outputBuffer.recoverMemory(this.#oscillator.output_buffer_ptr());
+  }
+
+  /**
+   * @param {number} sampleRate 
+   * @param {number} channelCount 
+   */
+  #reconfigure(sampleRate, channelCount) {
+    this.#oscillator.reconfigure(sampleRate, channelCount);
+    // the buffers may have been reallocated
+    this.#recoverMemory();
+  }
+
+  #destroy() {
+    this.#oscillator.free();
+    this.#pitchBuffer.free();
+    this.#outputBuffer.free();
+    cachedF32Memory.unregisterListener(this);
+    this.#destroyed = true;
+  }
+}
+registerProcessor('wavetable-oscillator', WavetableOscillatorProcessor);
@@ -0,0 +1,241 @@
+//! Compares the processors that compute their coefficients once per block,
+//! four frames at a time, against the per-channel scalar loops they
+//! replaced, which are copied here as the baseline.
+//!
+//! Natively this measures the portable fallback of `F32x4`; the `v128` path
+//! only builds for wasm32 with `--features simd` and `+simd128`.
+//!
+//! `cargo bench -p audio-processors --bench simd`
+
+use audio_processors::{
+    multi_filter::MultiFilter, multiplier::Multiplier, phaser::Phaser, processor::Processor,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const FRAMES: usize = 128;
+const SAMPLE_RATE: f32 = 48000.0;
+const CHANNELS: usize = 2;
+
+fn input() -> Vec<f32> {
+    (0..FRAMES * CHANNELS)
+        .map(|n| (n as f32 * 0.01).sin())
+        .collect()
+}
+
+fn tan(x: f32) -> f32 {
+    let x2 = x * x;
+    let numerator = x * (-135135.0 + x2 * (17325.0 + x2 * (-378.0 + x2)));
+    let denominator = -135135.0 + x2 * (62370.0 + x2 * (-3150.0 + 28.0 * x2));
+    numerator / denominator
+}
+
+fn triangle(arg: f32, increment: f32) -> f32 {
+    let polyblamp = |arg: f32| {
+        if arg < increment {
+            let t = arg / increment - 1.0;
+            -(t * t * t) / 3.0
+        } else if arg > 1.0 - increment {
+            let t = (arg - 1.0) / increment + 1.0;
+            (t * t * t) / 3.0
+        } else {
+            0.0
+        }
+    };
+    let mut y = arg * 4.0;
+    if y >= 3.0 {
+        y -= 4.0;
+    } else if y > 1.0 {
+        y = 2.0 - y;
+    }
+    y + 4.0 * increment * (polyblamp((arg + 0.25).fract()) - polyblamp((arg + 0.75).fract()))
+}
+
+/// The phaser before: every channel ran its own LFO steps and recomputed
+/// the four allpass coefficients on every frame.
+struct ScalarPhaser {
+    phase: f32,
+    f_c: [Vec<f32>; 4],
+    alpha: [Vec<f32>; 4],
+    state: [Vec<f32>; 4],
+}
+
+impl ScalarPhaser {
+    fn new() -> ScalarPhaser {
+        ScalarPhaser {
+            phase: 0.0,
+            f_c: [0; 4].map(|_| vec![1000.0; CHANNELS]),
+            alpha: [0; 4].map(|_| vec![0.0; CHANNELS]),
+            state: [0; 4].map(|_| vec![0.0; CHANNELS]),
+        }
+    }
+
+    fn process(&mut self, input: &[f32], rate: &[f32], output: &mut [f32]) {
+        for channel in 0..CHANNELS {
+            for (n, rate) in rate.iter().enumerate() {
+                let sample_index = channel * FRAMES + n;
+
+                let phase_incr = rate / SAMPLE_RATE;
+                let arg = self.phase;
+                self.phase += phase_incr;
+                self.phase -= self.phase.floor();
+                let bipolar = triangle(arg, phase_incr);
+                let f_c = 160.0 + bipolar.mul_add(0.5, 0.5) * (16000.0 - 160.0);
+
+                for stage in 0..4 {
+                    if self.f_c[stage][channel] != f_c {
+                        self.f_c[stage][channel] = f_c;
+                        let wd = std::f32::consts::TAU * f_c;
+                        let wa = 2.0 * SAMPLE_RATE * tan(wd / SAMPLE_RATE * 0.5);
+                        let g = wa / SAMPLE_RATE * 0.5;
+                        self.alpha[stage][channel] = g / (1.0 + g);
+                    }
+                }
+                let [a1, a2, a3, a4] = [0, 1, 2, 3].map(|stage| self.alpha[stage][channel]);
+                let [s1, s2, s3, s4] = [0, 1, 2, 3].map(|stage| self.state[stage][channel]);
+
+                let gamma1 = a4;
+                let gamma2 = a3 * gamma1;
+                let gamma3 = a2 * gamma2;
+                let gamma4 = a1 * gamma3;
+                let alpha0 = 1.0 / (1.0 + 0.25 * gamma4);
+                let s0 = gamma3 * s1 + gamma2 * s2 + gamma1 * s3 + s4;
+
+                let sample = alpha0 * (input[sample_index] - 0.25 * s0);
+                let mut y = sample;
+                for stage in 0..4 {
+                    let state = &mut self.state[stage][channel];
+                    let x1 = self.alpha[stage][channel] * (y - *state);
+                    let x2 = x1 + *state;
+                    *state = x1 + x2;
+                    y = 2.0 * x2 - y;
+                }
+
+                output[sample_index] = 0.707 * (sample + y);
+            }
+        }
+    }
+}
+
+/// The multi filter before: every channel updated its own coefficients on
+/// every frame, skipping the update when the cutoff and q stayed put.
+struct ScalarMultiFilter {
+    params: Vec<(f32, f32)>,
+    coeffs: Vec<(f32, f32, f32, f32)>,
+    state: Vec<(f32, f32)>,
+}
+
+impl ScalarMultiFilter {
+    fn new() -> ScalarMultiFilter {
+        ScalarMultiFilter {
+            params: vec![(0.0, 0.0); CHANNELS],
+            coeffs: vec![(0.0, 0.0, 0.0, 0.0); CHANNELS],
+            state: vec![(0.0, 0.0); CHANNELS],
+        }
+    }
+
+    fn process(&mut self, input: &[f32], f_c: &[f32], q: &[f32], outputs: &mut [Vec<f32>; 4]) {
+        for channel in 0..CHANNELS {
+            for n in 0..FRAMES {
+                if self.params[channel] != (f_c[n], q[n]) {
+                    self.params[channel] = (f_c[n], q[n]);
+                    let alpha = tan(std::f32::consts::PI * f_c[n] / SAMPLE_RATE);
+                    let sigma = (4.0 * f_c[n] * f_c[n]) / (alpha * SAMPLE_RATE * SAMPLE_RATE);
+                    let r = 1.0 / (2.0 * q[n]);
+                    let rho = 2.0 * r + alpha;
+                    let alpha_0 = 1.0 / (1.0 + 2.0 * r * alpha + alpha * alpha);
+                    self.coeffs[channel] = (alpha, alpha_0, rho, sigma);
+                }
+                let (alpha, alpha_0, rho, sigma) = self.coeffs[channel];
+                let (sn_1, sn_2) = self.state[channel];
+
+                let sample_index = channel * FRAMES + n;
+                let hpf = alpha_0 * (input[sample_index] - rho * sn_1 - sn_2);
+                let bpf = alpha * hpf + sn_1;
+                let lpf = alpha * bpf + sn_2;
+                let bsf = hpf + lpf;
+                let lpf2 = lpf + sigma * sn_1;
+                self.state[channel] = (alpha * hpf + bpf, alpha * bpf + lpf);
+
+                outputs[0][sample_index] = bpf;
+                outputs[1][sample_index] = bsf;
+                outputs[2][sample_index] = hpf;
+                outputs[3][sample_index] = lpf2;
+            }
+        }
+    }
+}
+
+fn bench_phaser(c: &mut Criterion) {
+    let input = input();
+    let rate = vec![2.0; FRAMES];
+
+    let mut group = c.benchmark_group("phaser");
+    group.bench_function("before", |b| {
+        let mut phaser = ScalarPhaser::new();
+        let mut output = vec![0.0; FRAMES * CHANNELS];
+        b.iter(|| phaser.process(black_box(&input), black_box(&rate), &mut output))
+    });
+    group.bench_function("after", |b| {
+        let mut phaser = Phaser::new(FRAMES, SAMPLE_RATE, CHANNELS);
+        phaser.input_buffer(0).copy_from_slice(&input);
+        phaser.input_buffer(1).copy_from_slice(&rate);
+        b.iter(|| Processor::process(black_box(&mut phaser), FRAMES, &[]))
+    });
+    group.finish();
+}
+
+fn bench_multi_filter(c: &mut Criterion) {
+    let input = input();
+    let q = vec![2.0; FRAMES];
+    let constant_f_c = vec![1000.0; FRAMES];
+    let modulated_f_c: Vec<f32> = (0..FRAMES).map(|n| 500.0 + 10.0 * n as f32).collect();
+
+    let mut group = c.benchmark_group("multi_filter");
+    for (name, f_c) in [("constant", &constant_f_c), ("modulated", &modulated_f_c)] {
+        group.bench_function(format!("{}/before", name), |b| {
+            let mut filter = ScalarMultiFilter::new();
+            let mut outputs = [0; 4].map(|_| vec![0.0; FRAMES * CHANNELS]);
+            b.iter(|| {
+                filter.process(
+                    black_box(&input),
+                    black_box(f_c),
+                    black_box(&q),
+                    &mut outputs,
+                )
+            })
+        });
+        group.bench_function(format!("{}/after", name), |b| {
+            let mut filter = MultiFilter::new(FRAMES, SAMPLE_RATE, CHANNELS);
+            filter.input_buffer(0).copy_from_slice(&input);
+            filter.input_buffer(1).copy_from_slice(f_c);
+            filter.input_buffer(2).copy_from_slice(&q);
+            b.iter(|| Processor::process(black_box(&mut filter), FRAMES, &[]))
+        });
+    }
+    group.finish();
+}
+
+fn bench_multiplier(c: &mut Criterion) {
+    let input = input();
+
+    let mut group = c.benchmark_group("multiplier");
+    group.bench_function("before", |b| {
+        let mut output = vec![0.0; FRAMES * CHANNELS];
+        b.iter(|| {
+            let input = black_box(&input);
+            for n in 0..FRAMES * CHANNELS {
+                output[n] = input[n] * input[n];
+            }
+        })
+    });
+    group.bench_function("after", |b| {
+        let mut multiplier = Multiplier::new(FRAMES, CHANNELS);
+        multiplier.input_buffer(0).copy_from_slice(&input);
+        multiplier.input_buffer(1).copy_from_slice(&input);
+        b.iter(|| Processor::process(black_box(&mut multiplier), FRAMES, &[]))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_phaser, bench_multi_filter, bench_multiplier);
+criterion_main!(benches);
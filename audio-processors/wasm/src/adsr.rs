@@ -1,10 +1,14 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
-use crate::dsp::adsr_unit::ADSRUnit;
+use crate::{block_param, dsp::adsr_unit::ADSRUnit, invalid_buffer_index, processor::Processor};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "release", min = 0.001, max = 2.0, default = 0.1)]
 pub struct ADSR {
     adsr: ADSRUnit,
     buffer_frame_length: usize,
@@ -38,11 +42,20 @@ impl ADSR {
         }
     }
 
-    pub fn process(&mut self, attack_sec: f32, decay_sec: f32, sustain_lvl: f32, release_sec: f32) {
+    pub fn process(
+        &mut self,
+        frames: usize,
+        attack_sec: f32,
+        decay_sec: f32,
+        sustain_lvl: f32,
+        release_sec: f32,
+    ) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.adsr
             .set_params(attack_sec, decay_sec, sustain_lvl, release_sec);
 
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
             let flag = self.trigger_input_buffer[n];
             let retrigger = self.prev_retrig == 0.0 && self.retrigger_input_buffer[n] == 1.0;
 
@@ -66,4 +79,56 @@ impl ADSR {
         self.prev_flag = 0.0;
         self.prev_retrig = 0.0;
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.adsr.set_sample_rate(sample_rate);
+    }
+}
+
+impl Processor for ADSR {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["trigger", "retrigger"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.trigger_input_buffer,
+            1 => &mut self.retrigger_input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        ADSR::process(
+            self,
+            frames,
+            block_param!(params, 0, "attack"),
+            block_param!(params, 1, "decay"),
+            block_param!(params, 2, "sustain"),
+            block_param!(params, 3, "release"),
+        );
+    }
+
+    fn reset(&mut self) {
+        ADSR::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        ADSR::reconfigure(self, sample_rate, channel_count);
+    }
 }
@@ -1,15 +1,24 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
-    dsp::{lofi, smoothed_value::SmoothedValue},
-    linear_smoothed_value,
+    block_param,
+    dsp::{
+        lofi,
+        oversampling::{Oversampler, OversamplingFactor},
+        smoothed_value::SmoothedValue,
+    },
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
 };
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "bits", min = 1.0, max = 32.0, default = 32.0, rate = "k-rate")]
+#[param(name = "oversampling", min = 0, max = 3, default = 0, rate = "k-rate")]
 pub struct BitCrusher {
     bit_crusher: lofi::BitCrusher,
+    oversamplers: Vec<Oversampler>,
     buffer_frame_length: usize,
     channel_count: usize,
 
@@ -30,6 +39,7 @@ impl BitCrusher {
 
         BitCrusher {
             bit_crusher: lofi::BitCrusher::new(),
+            oversamplers: (0..channel_count).map(|_| Oversampler::new()).collect(),
             buffer_frame_length,
             channel_count,
 
@@ -40,16 +50,26 @@ impl BitCrusher {
         }
     }
 
-    pub fn process(&mut self, bits: f32) {
+    pub fn process(&mut self, frames: usize, bits: f32, oversampling: u8) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.bits.set_target_value(bits);
 
-        for n in 0..self.buffer_frame_length {
-            self.bit_crusher.set_bits(self.bits.get_next_value());
+        let factor = OversamplingFactor::from(oversampling);
+        self.oversamplers
+            .iter_mut()
+            .for_each(|oversampler| oversampler.set_factor(factor));
+
+        let bit_crusher = &mut self.bit_crusher;
+
+        for n in 0..frames {
+            bit_crusher.set_bits(self.bits.get_next_value());
 
             let mut channel_offset = 0;
-            for _ in 0..self.channel_count {
+            for oversampler in &mut self.oversamplers {
                 let i = n + channel_offset;
-                self.output_buffer[i] = self.bit_crusher.process(self.input_buffer[i]);
+                self.output_buffer[i] =
+                    oversampler.process(self.input_buffer[i], |x| bit_crusher.process(x));
                 channel_offset += self.buffer_frame_length;
             }
         }
@@ -57,5 +77,63 @@ impl BitCrusher {
 
     pub fn reset(&mut self) {
         self.bits.reset();
+        self.oversamplers.iter_mut().for_each(Oversampler::reset);
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.bits.set_sample_rate(sample_rate);
+
+        self.channel_count = channel_count;
+        self.oversamplers
+            .resize_with(channel_count, Oversampler::new);
+        self.input_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+impl Processor for BitCrusher {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        BitCrusher::process(
+            self,
+            frames,
+            block_param!(params, 0, "bits"),
+            block_param!(params, 1, "oversampling") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        BitCrusher::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        BitCrusher::reconfigure(self, sample_rate, channel_count);
     }
 }
@@ -1,13 +1,17 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
+    block_param,
     dsp::{lofi, smoothed_value::SmoothedValue},
-    linear_smoothed_value,
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
 };
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "reduction", min = 0.001, max = 1, default = 1, rate = "k-rate")]
+#[param(name = "stereoShift", min = 0, max = 1, default = 0, rate = "k-rate")]
 pub struct Decimator {
     buffer_frame_length: usize,
     channel_count: usize,
@@ -48,11 +52,13 @@ impl Decimator {
         }
     }
 
-    pub fn process(&mut self, reduction: f32, stereo_shift: f32) {
+    pub fn process(&mut self, frames: usize, reduction: f32, stereo_shift: f32) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.reduction.set_target_value(reduction);
         self.stereo_shift.set_target_value(stereo_shift);
 
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
             let next_reduction = self.reduction.get_next_value();
             let next_stereo_shift = self.stereo_shift.get_next_value();
 
@@ -79,4 +85,62 @@ impl Decimator {
         self.reduction.reset();
         self.stereo_shift.reset();
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.reduction.set_sample_rate(sample_rate);
+        self.stereo_shift.set_sample_rate(sample_rate);
+
+        self.channel_count = channel_count;
+        self.decimators
+            .resize_with(channel_count, lofi::Decimator::new);
+        self.input_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+impl Processor for Decimator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        Decimator::process(
+            self,
+            frames,
+            block_param!(params, 0, "reduction"),
+            block_param!(params, 1, "stereoShift"),
+        );
+    }
+
+    fn reset(&mut self) {
+        Decimator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        Decimator::reconfigure(self, sample_rate, channel_count);
+    }
 }
@@ -75,6 +75,11 @@ impl ADSRUnit {
         }
     }
 
+    /// Current envelope value, without advancing it.
+    pub fn level(&self) -> f32 {
+        self.envelope_val
+    }
+
     pub fn note_off(&mut self) {
         if let ADSRState::Idle = self.state {
             return;
@@ -145,6 +150,11 @@ impl ADSRUnit {
         }
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.calc_rates();
+    }
+
     fn calc_rates(&mut self) {
         self.attack_rate = if self.attack_sec > 0.0 {
             1.0 / (self.attack_sec * self.sample_rate)
@@ -1,46 +1,29 @@
-use std::f32::consts::TAU;
+use std::f32::consts::PI;
 
-use crate::dsp::fast_math;
+use crate::dsp::{
+    fast_math,
+    simd::{self, F32x4, LANES},
+};
 
+/// First-order allpass. The coefficient of every frame comes from
+/// [`AllpassFilter::calc_alphas`], so that one block of cutoffs can drive
+/// the filters of every channel.
+#[derive(Default)]
 pub struct AllpassFilter {
-    sample_rate: f32,
-    sample_rate_inv: f32,
-
-    f_c: f32,
-    alpha: f32,
     state: f32,
 }
 
 impl AllpassFilter {
-    pub fn new(sample_rate: f32) -> Self {
-        Self {
-            sample_rate,
-            sample_rate_inv: 1.0 / sample_rate,
-
-            f_c: 1000.0,
-            alpha: 0.0,
-            state: 0.0,
-        }
+    pub fn new() -> Self {
+        Self { state: 0.0 }
     }
 
-    pub fn get_state(&self) -> (f32, f32) {
-        (self.alpha, self.state)
+    pub fn get_state(&self) -> f32 {
+        self.state
     }
 
-    pub fn set_params(&mut self, f_c: f32) {
-        if f_c == self.f_c {
-            return;
-        }
-        self.f_c = f_c;
-
-        let wd = TAU * self.f_c;
-        let wa = 2.0 * self.sample_rate * fast_math::tan(wd * self.sample_rate_inv * 0.5);
-        let g = wa * self.sample_rate_inv * 0.5;
-        self.alpha = g / (1.0 + g);
-    }
-
-    pub fn process(&mut self, x: f32) -> f32 {
-        let x1 = self.alpha * (x - self.state);
+    pub fn process(&mut self, x: f32, alpha: f32) -> f32 {
+        let x1 = alpha * (x - self.state);
         let x2 = x1 + self.state;
 
         self.state = x1 + x2;
@@ -51,4 +34,15 @@ impl AllpassFilter {
     pub fn reset(&mut self) {
         self.state = 0.0;
     }
+
+    /// Coefficients for the cutoff frequencies `f_c`, four frames at a time.
+    /// `alphas` must hold `f_c.len()` rounded up to whole lane groups.
+    pub fn calc_alphas(f_c: &[f32], sample_rate_inv: f32, alphas: &mut [f32]) {
+        for n in (0..f_c.len()).step_by(LANES) {
+            let f_c = simd::load_frames(f_c, n);
+            // the prewarped g = wa / (2 * fs) reduces to tan(pi * fc / fs)
+            let g = fast_math::tan_lanes(F32x4::splat(PI * sample_rate_inv) * f_c);
+            (g / (F32x4::splat(1.0) + g)).write_to_slice(&mut alphas[n..]);
+        }
+    }
 }
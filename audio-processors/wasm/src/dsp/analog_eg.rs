@@ -1,10 +1,3 @@
-use std::f64::EPSILON;
-
-use js_sys::Array;
-use web_sys::console::log;
-
-use crate::dsp::lofi;
-
 enum EGState {
     Idle,
     Attack,
@@ -182,6 +175,13 @@ impl AnalogEG {
         }
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate_millihz = (sample_rate as f64) / 1000.0;
+        self.calc_attack_coeff(1.0);
+        self.calc_decay_coeff(1.0);
+        self.calc_release_coeff(1.0);
+    }
+
     fn calc_attack_coeff(&mut self, scale: f64) {
         if self.attack_msec == 0.0 {
             return;
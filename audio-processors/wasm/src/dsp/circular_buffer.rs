@@ -46,8 +46,7 @@ impl CircularBuffer {
 
     pub fn read(&self, delay_in_samples: usize) -> f32 {
         // meant for read before write!
-        let mut read_index = self.write_index - delay_in_samples;
-        read_index &= self.wrap_mask;
+        let read_index = self.write_index.wrapping_sub(delay_in_samples) & self.wrap_mask;
         self.buffer[read_index]
     }
 
@@ -92,6 +91,24 @@ impl CircularBuffer {
         reset_vec![self.buffer];
         self.write_index = 0;
     }
+
+    /// Changes the length, keeping the most recent samples at the same
+    /// delays.
+    pub fn resize(&mut self, length: usize) {
+        let buf_len = length.next_power_of_two();
+        if buf_len == self.buffer.len() {
+            return;
+        }
+        let kept = buf_len.min(self.buffer.len());
+        let mut buf: Vec<f32> = vec![0.0; buf_len];
+        for delay in 1..=kept {
+            buf[kept - delay] = self.read(delay);
+        }
+
+        self.buffer = buf.into_boxed_slice();
+        self.wrap_mask = buf_len - 1;
+        self.write_index = kept & self.wrap_mask;
+    }
 }
 
 #[cfg(test)]
@@ -102,4 +119,21 @@ mod tests {
     fn test_cubic_interpolation() {
         assert_eq!(1.0, cubic_interpolation(1.0, 1.0, 1.0, 1.0, 1.0));
     }
+
+    #[test]
+    fn test_resize_keeps_recent_samples() {
+        let mut buffer = CircularBuffer::new(8);
+        for n in 0..12 {
+            buffer.write(n as f32);
+        }
+        buffer.resize(16);
+        assert_eq!(buffer.read(1), 11.0);
+        assert_eq!(buffer.read(8), 4.0);
+        assert_eq!(buffer.read(9), 0.0);
+
+        buffer.resize(4);
+        buffer.write(12.0);
+        assert_eq!(buffer.read(1), 12.0);
+        assert_eq!(buffer.read(4), 9.0);
+    }
 }
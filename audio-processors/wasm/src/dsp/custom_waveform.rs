@@ -0,0 +1,103 @@
+/// Samples per cycle a breakpoint list is rendered to.
+pub const BREAKPOINT_TABLE_SIZE: usize = 1024;
+
+/// Single-cycle waveform read with linear interpolation, loaded either as a
+/// table of samples or as a list of breakpoints. Reads silence until loaded.
+pub struct CustomWaveform {
+    table: Vec<f32>,
+}
+
+impl CustomWaveform {
+    pub fn new() -> CustomWaveform {
+        CustomWaveform { table: vec![0.0] }
+    }
+
+    /// Uses `table` as one cycle. Returns `false` and keeps the current
+    /// waveform if `table` is empty.
+    pub fn load_table(&mut self, table: &[f32]) -> bool {
+        if table.is_empty() {
+            return false;
+        }
+        self.table.clear();
+        self.table.extend_from_slice(table);
+        true
+    }
+
+    /// Connects interleaved `(phase, value)` pairs with straight lines,
+    /// wrapping from the last point back to the first. Returns `false` and
+    /// keeps the current waveform unless there is at least one pair and the
+    /// phases rise within `[0, 1]`.
+    pub fn load_breakpoints(&mut self, breakpoints: &[f32]) -> bool {
+        if breakpoints.is_empty() || !breakpoints.len().is_multiple_of(2) {
+            return false;
+        }
+        let points: Vec<(f32, f32)> = breakpoints
+            .chunks_exact(2)
+            .map(|point| (point[0], point[1]))
+            .collect();
+        let valid_phases = points.iter().all(|(phase, _)| (0.0..=1.0).contains(phase))
+            && points.windows(2).all(|pair| pair[0].0 <= pair[1].0);
+        if !valid_phases {
+            return false;
+        }
+
+        let (first_phase, first_value) = points[0];
+        let (last_phase, last_value) = points[points.len() - 1];
+        // the segment wrapping around from the last point to the first one
+        let wrap_length = 1.0 - last_phase + first_phase;
+
+        self.table.clear();
+        self.table.extend((0..BREAKPOINT_TABLE_SIZE).map(|n| {
+            let phase = n as f32 / BREAKPOINT_TABLE_SIZE as f32;
+            match points.iter().position(|(p, _)| *p > phase) {
+                Some(next) if next > 0 => {
+                    let (x1, y1) = points[next - 1];
+                    let (x2, y2) = points[next];
+                    y1 + (phase - x1) / (x2 - x1) * (y2 - y1)
+                }
+                _ if wrap_length <= 0.0 => last_value,
+                next => {
+                    let distance = if next.is_some() {
+                        phase + 1.0 - last_phase
+                    } else {
+                        phase - last_phase
+                    };
+                    last_value + distance / wrap_length * (first_value - last_value)
+                }
+            }
+        }));
+        true
+    }
+
+    /// Reads phase `arg` in `[0, 1)`.
+    #[inline]
+    pub fn read(&self, arg: f32) -> f32 {
+        let size = self.table.len();
+        let index = arg * size as f32;
+        let index1 = (index as usize) % size;
+        let index2 = (index1 + 1) % size;
+        let frac = index - index.floor();
+        let y1 = self.table[index1];
+        y1 + frac * (self.table[index2] - y1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakpoints_wrap_around() {
+        let mut waveform = CustomWaveform::new();
+        assert!(waveform.load_breakpoints(&[0.25, 1.0, 0.75, -1.0]));
+
+        assert!((waveform.read(0.25) - 1.0).abs() < 1e-6);
+        assert!((waveform.read(0.5)).abs() < 1e-6);
+        assert!((waveform.read(0.75) + 1.0).abs() < 1e-6);
+        // halfway along the segment from (0.75, -1) over the wrap to (1.25, 1)
+        assert!(waveform.read(0.0).abs() < 1e-6);
+
+        assert!(!waveform.load_breakpoints(&[0.5, 0.0, 0.25, 1.0]));
+        assert!((waveform.read(0.25) - 1.0).abs() < 1e-6);
+    }
+}
@@ -1,5 +1,6 @@
 use super::{
     circular_buffer::{CircularBuffer, InterpolationType},
+    oversampling::OversamplingFactor,
     tape_sim::TapeSimFilter,
     vasv_filter::{pre_feedback_loop_hipass, VASVFilter},
 };
@@ -27,6 +28,18 @@ impl Delay {
         self.pre_feedback_hipass.reset();
     }
 
+    /// Keeps the delayed signal, growing or shrinking the buffer to
+    /// `buffer_size_samples`.
+    pub fn set_sample_rate(&mut self, sample_rate: f32, buffer_size_samples: usize) {
+        self.buffer.resize(buffer_size_samples);
+        self.output_tape_sim_filter.set_sample_rate(sample_rate);
+        self.pre_feedback_hipass.set_sample_rate(sample_rate);
+    }
+
+    pub fn set_oversampling(&mut self, factor: OversamplingFactor) {
+        self.output_tape_sim_filter.set_oversampling(factor);
+    }
+
     pub fn process(
         &mut self,
         sample: f32,
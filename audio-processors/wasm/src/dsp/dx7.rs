@@ -0,0 +1,447 @@
+use std::fmt;
+
+use super::{
+    fm_voice::{KeyScaling, KeyScalingCurve, OperatorEnvelope, OperatorParams, VoiceModulation},
+    rate_level_eg,
+    voice_lfo::VoiceLfoWaveform,
+};
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+const YAMAHA_ID: u8 = 0x43;
+// sub-status of a bulk dump, the MIDI channel goes in the low nibble
+const BULK_DUMP: u8 = 0x00;
+// format numbers
+const SINGLE_VOICE: u8 = 0;
+const BANK: u8 = 9;
+// header: F0 43 0n ff bb bb, then the data, checksum and F7
+const HEADER_LENGTH: usize = 6;
+const SINGLE_VOICE_LENGTH: usize = 155;
+const PACKED_VOICE_LENGTH: usize = 128;
+const BANK_VOICES: usize = 32;
+
+// the envelope times of `OperatorParams`
+const MAX_SEGMENT_SECONDS: f32 = 2.0;
+const MIN_RELEASE_SECONDS: f32 = 0.001;
+// MIDI note of breakpoint 0 (A-1)
+const BREAKPOINT_OFFSET: f32 = 21.0;
+// LFO speed 0 runs at about 0.06 Hz, every 10.3 steps doubling that
+const SLOWEST_LFO_HZ: f32 = 0.0628;
+const LFO_SPEED_STEPS_PER_DOUBLING: f32 = 10.3;
+// LFO delay 99 holds the LFO back this long, and fades it in as long again
+const MAX_LFO_DELAY_SECONDS: f32 = 2.75;
+// pitch and amplitude modulation sensitivities, as fractions of full depth
+const PITCH_MOD_SENSITIVITIES: [f32; 8] = [
+    0.0,
+    10.0 / 255.0,
+    20.0 / 255.0,
+    33.0 / 255.0,
+    55.0 / 255.0,
+    92.0 / 255.0,
+    153.0 / 255.0,
+    1.0,
+];
+const AMP_MOD_SENSITIVITIES: [f32; 4] = [0.0, 0.26, 0.43, 1.0];
+// pitch modulation at full depth and sensitivity, in semitones
+const MAX_PITCH_MOD_SEMITONES: f32 = 12.0;
+
+#[derive(Debug, PartialEq)]
+pub enum Dx7Error {
+    /// Not a Yamaha system exclusive bulk dump.
+    NotDx7,
+    /// A Yamaha message in a format other than a single voice or a bank.
+    UnsupportedFormat(u8),
+    Truncated,
+    ChecksumMismatch,
+}
+
+impl fmt::Display for Dx7Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dx7Error::NotDx7 => write!(f, "not a DX7 sysex dump"),
+            Dx7Error::UnsupportedFormat(format) => {
+                write!(f, "unsupported DX7 sysex format {}", format)
+            }
+            Dx7Error::Truncated => write!(f, "truncated DX7 sysex dump"),
+            Dx7Error::ChecksumMismatch => write!(f, "DX7 sysex checksum mismatch"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dx7Operator {
+    pub rates: [u8; 4],
+    pub levels: [u8; 4],
+    pub breakpoint: u8,
+    pub left_depth: u8,
+    pub right_depth: u8,
+    pub left_curve: u8,
+    pub right_curve: u8,
+    pub rate_scaling: u8,
+    pub amp_mod_sensitivity: u8,
+    pub velocity_sensitivity: u8,
+    pub output_level: u8,
+    pub fixed_frequency: bool,
+    pub coarse: u8,
+    pub fine: u8,
+    // 0-14, 7 is in tune
+    pub detune: u8,
+}
+
+impl Dx7Operator {
+    fn from_unpacked(data: &[u8]) -> Dx7Operator {
+        Dx7Operator {
+            rates: [data[0], data[1], data[2], data[3]],
+            levels: [data[4], data[5], data[6], data[7]],
+            breakpoint: data[8],
+            left_depth: data[9],
+            right_depth: data[10],
+            left_curve: data[11],
+            right_curve: data[12],
+            rate_scaling: data[13],
+            amp_mod_sensitivity: data[14],
+            velocity_sensitivity: data[15],
+            output_level: data[16],
+            fixed_frequency: data[17] & 1 == 1,
+            coarse: data[18],
+            fine: data[19],
+            detune: data[20],
+        }
+    }
+
+    fn from_packed(data: &[u8]) -> Dx7Operator {
+        Dx7Operator {
+            rates: [data[0], data[1], data[2], data[3]],
+            levels: [data[4], data[5], data[6], data[7]],
+            breakpoint: data[8],
+            left_depth: data[9],
+            right_depth: data[10],
+            left_curve: data[11] & 0x03,
+            right_curve: (data[11] >> 2) & 0x03,
+            rate_scaling: data[12] & 0x07,
+            detune: (data[12] >> 3) & 0x0F,
+            amp_mod_sensitivity: data[13] & 0x03,
+            velocity_sensitivity: (data[13] >> 2) & 0x07,
+            output_level: data[14],
+            fixed_frequency: data[15] & 1 == 1,
+            coarse: (data[15] >> 1) & 0x1F,
+            fine: data[16],
+        }
+    }
+
+    /// Frequency relative to the played note, 1 for fixed frequency
+    /// operators, see `fixed_hz`.
+    pub fn frequency_ratio(&self) -> f32 {
+        if self.fixed_frequency {
+            return 1.0;
+        }
+        let coarse = if self.coarse == 0 {
+            0.5
+        } else {
+            self.coarse as f32
+        };
+        // one detune step is roughly a cent
+        let detune = ((self.detune as f32 - 7.0) / 1200.0).exp2();
+        coarse * (1.0 + self.fine as f32 / 100.0) * detune
+    }
+
+    /// Frequency of a fixed frequency operator, which does not follow the
+    /// keyboard: 1, 10, 100 or 1000 Hz, raised by up to almost ten times.
+    pub fn fixed_hz(&self) -> Option<f32> {
+        self.fixed_frequency
+            .then(|| 10.0f32.powf((self.coarse & 0x03) as f32 + self.fine as f32 / 100.0))
+    }
+
+    /// Uses the operator's rate/level envelope. The ADSR params approximate
+    /// it for `OperatorEnvelope::Adsr`, peaking at level 1, sustaining at
+    /// level 3 and releasing towards level 4; `level` leaves out the peak,
+    /// which the rate/level envelope brings along.
+    pub fn operator_params(&self) -> OperatorParams {
+        let [r1, r2, r3, r4] = self.rates;
+        let [l1, l2, l3, l4] = self.levels;
+        let peak = level_to_amplitude(l1);
+        let sustain = if peak > 0.0 {
+            level_to_amplitude(l3) / peak
+        } else {
+            0.0
+        };
+
+        OperatorParams {
+            freq_mod: self.frequency_ratio(),
+            fixed_frequency: self.fixed_hz(),
+            level: level_to_amplitude(self.output_level),
+            env_attack: segment_seconds(r1, l4, l1).min(MAX_SEGMENT_SECONDS),
+            env_decay: (segment_seconds(r2, l1, l2) + segment_seconds(r3, l2, l3))
+                .min(MAX_SEGMENT_SECONDS),
+            env_sustain: sustain.min(1.0),
+            env_release: segment_seconds(r4, l3, l4)
+                .clamp(MIN_RELEASE_SECONDS, MAX_SEGMENT_SECONDS),
+            envelope: OperatorEnvelope::RateLevel,
+            eg_rates: self.rates.map(|rate| rate as f32),
+            eg_levels: self.levels.map(|level| level as f32),
+            velocity_sensitivity: self.velocity_sensitivity.min(7) as f32 / 7.0,
+            key_scaling: KeyScaling {
+                breakpoint: self.breakpoint as f32 + BREAKPOINT_OFFSET,
+                left_depth: self.left_depth.min(99) as f32 / 99.0,
+                right_depth: self.right_depth.min(99) as f32 / 99.0,
+                left_curve: KeyScalingCurve::from(self.left_curve),
+                right_curve: KeyScalingCurve::from(self.right_curve),
+                rate_scaling: self.rate_scaling.min(7) as f32 / 7.0,
+            },
+            amp_mod_sensitivity: AMP_MOD_SENSITIVITIES[self.amp_mod_sensitivity.min(3) as usize],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dx7Voice {
+    /// Operator 1 first.
+    pub operators: [Dx7Operator; 6],
+    pub pitch_rates: [u8; 4],
+    pub pitch_levels: [u8; 4],
+    /// 1-32, as numbered on the DX7 and by `Algorithm`.
+    pub algorithm: i32,
+    pub feedback: u8,
+    pub osc_key_sync: bool,
+    pub lfo_speed: u8,
+    pub lfo_delay: u8,
+    pub lfo_pitch_mod_depth: u8,
+    pub lfo_amp_mod_depth: u8,
+    pub lfo_key_sync: bool,
+    pub lfo_waveform: u8,
+    pub pitch_mod_sensitivity: u8,
+    /// In semitones, 0 is C3.
+    pub transpose: i32,
+    pub name: String,
+}
+
+impl Dx7Voice {
+    fn from_unpacked(data: &[u8]) -> Dx7Voice {
+        // the dump starts with operator 6
+        let mut operators: [Dx7Operator; 6] = Default::default();
+        for (index, operator) in operators.iter_mut().enumerate() {
+            let offset = (5 - index) * 21;
+            *operator = Dx7Operator::from_unpacked(&data[offset..offset + 21]);
+        }
+        Dx7Voice {
+            operators,
+            pitch_rates: [data[126], data[127], data[128], data[129]],
+            pitch_levels: [data[130], data[131], data[132], data[133]],
+            algorithm: (data[134] & 0x1F) as i32 + 1,
+            feedback: data[135] & 0x07,
+            osc_key_sync: data[136] & 1 == 1,
+            lfo_speed: data[137],
+            lfo_delay: data[138],
+            lfo_pitch_mod_depth: data[139],
+            lfo_amp_mod_depth: data[140],
+            lfo_key_sync: data[141] & 1 == 1,
+            lfo_waveform: data[142],
+            pitch_mod_sensitivity: data[143],
+            transpose: data[144] as i32 - 24,
+            name: parse_name(&data[145..155]),
+        }
+    }
+
+    fn from_packed(data: &[u8]) -> Dx7Voice {
+        let mut operators: [Dx7Operator; 6] = Default::default();
+        for (index, operator) in operators.iter_mut().enumerate() {
+            let offset = (5 - index) * 17;
+            *operator = Dx7Operator::from_packed(&data[offset..offset + 17]);
+        }
+        Dx7Voice {
+            operators,
+            pitch_rates: [data[102], data[103], data[104], data[105]],
+            pitch_levels: [data[106], data[107], data[108], data[109]],
+            algorithm: (data[110] & 0x1F) as i32 + 1,
+            feedback: data[111] & 0x07,
+            osc_key_sync: (data[111] >> 3) & 1 == 1,
+            lfo_speed: data[112],
+            lfo_delay: data[113],
+            lfo_pitch_mod_depth: data[114],
+            lfo_amp_mod_depth: data[115],
+            lfo_key_sync: data[116] & 1 == 1,
+            lfo_waveform: (data[116] >> 1) & 0x07,
+            pitch_mod_sensitivity: (data[116] >> 4) & 0x07,
+            transpose: data[117] as i32 - 24,
+            name: parse_name(&data[118..128]),
+        }
+    }
+
+    pub fn operator_params(&self) -> [OperatorParams; 6] {
+        let op = |index: usize| self.operators[index].operator_params();
+        [op(0), op(1), op(2), op(3), op(4), op(5)]
+    }
+
+    pub fn modulation(&self) -> VoiceModulation {
+        let depth = |depth: u8| depth.min(99) as f32 / 99.0;
+        let delay = MAX_LFO_DELAY_SECONDS * depth(self.lfo_delay).powi(2);
+        VoiceModulation {
+            pitch_eg_rates: self.pitch_rates.map(|rate| rate as f32),
+            pitch_eg_levels: self.pitch_levels.map(|level| level as f32),
+            lfo_waveform: VoiceLfoWaveform::from(self.lfo_waveform),
+            lfo_frequency: SLOWEST_LFO_HZ
+                * (self.lfo_speed.min(99) as f32 / LFO_SPEED_STEPS_PER_DOUBLING).exp2(),
+            lfo_delay: delay,
+            lfo_fade: delay,
+            lfo_key_sync: self.lfo_key_sync,
+            lfo_pitch_depth: MAX_PITCH_MOD_SEMITONES
+                * depth(self.lfo_pitch_mod_depth)
+                * PITCH_MOD_SENSITIVITIES[self.pitch_mod_sensitivity.min(7) as usize],
+            lfo_amp_depth: depth(self.lfo_amp_mod_depth),
+        }
+    }
+}
+
+/// Parses a single voice dump or a 32 voice bank dump.
+pub fn parse_sysex(data: &[u8]) -> Result<Vec<Dx7Voice>, Dx7Error> {
+    if data.len() < HEADER_LENGTH
+        || data[0] != SYSEX_START
+        || data[1] != YAMAHA_ID
+        || data[2] & 0xF0 != BULK_DUMP
+    {
+        return Err(Dx7Error::NotDx7);
+    }
+    let length = match data[3] {
+        SINGLE_VOICE => SINGLE_VOICE_LENGTH,
+        BANK => PACKED_VOICE_LENGTH * BANK_VOICES,
+        format => return Err(Dx7Error::UnsupportedFormat(format)),
+    };
+
+    // the data, its checksum and the end of the message
+    if data.len() < HEADER_LENGTH + length + 2 || data[HEADER_LENGTH + length + 1] != SYSEX_END {
+        return Err(Dx7Error::Truncated);
+    }
+    let voice_data = &data[HEADER_LENGTH..HEADER_LENGTH + length];
+    if checksum(voice_data) != data[HEADER_LENGTH + length] {
+        return Err(Dx7Error::ChecksumMismatch);
+    }
+    Ok(match data[3] {
+        SINGLE_VOICE => vec![Dx7Voice::from_unpacked(voice_data)],
+        _ => voice_data
+            .chunks_exact(PACKED_VOICE_LENGTH)
+            .map(Dx7Voice::from_packed)
+            .collect(),
+    })
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    let sum = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    sum.wrapping_neg() & 0x7F
+}
+
+fn parse_name(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| match byte {
+            0x20..=0x7E => *byte as char,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// DX7 levels (0-99) are logarithmic, roughly 0.75 dB per step.
+pub fn level_to_amplitude(level: u8) -> f32 {
+    rate_level_eg::level_to_amplitude(level as f32)
+}
+
+/// Time an envelope segment with `rate` (0-99) takes from level `from` to
+/// `to`. The time halves about every six rate steps.
+pub fn segment_seconds(rate: u8, from: u8, to: u8) -> f32 {
+    let distance = (from as f32 - to as f32).abs() / 99.0;
+    rate_level_eg::sweep_seconds(rate as f32) * distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_voice_dump(voice: &[u8]) -> Vec<u8> {
+        let mut dump = vec![SYSEX_START, YAMAHA_ID, 0x00, SINGLE_VOICE, 0x01, 0x1B];
+        dump.extend_from_slice(voice);
+        dump.push(checksum(voice));
+        dump.push(SYSEX_END);
+        dump
+    }
+
+    #[test]
+    fn test_parse_single_voice() {
+        let mut voice = [0u8; SINGLE_VOICE_LENGTH];
+        // operator 1 is the last in the dump: ratio 2 * 1.5, detuned +7
+        voice[105 + 16] = 99;
+        voice[105 + 18] = 2;
+        voice[105 + 19] = 50;
+        voice[105 + 20] = 14;
+        // operator 6 in tune at ratio 0.5
+        voice[20] = 7;
+        voice[134] = 4;
+        voice[135] = 7;
+        voice[144] = 36;
+        voice[145..155].copy_from_slice(b"E.PIANO 1 ");
+
+        let voices = parse_sysex(&single_voice_dump(&voice)).unwrap();
+        assert_eq!(voices.len(), 1);
+        let voice = &voices[0];
+        assert_eq!(voice.name, "E.PIANO 1");
+        assert_eq!(voice.algorithm, 5);
+        assert_eq!(voice.feedback, 7);
+        assert_eq!(voice.transpose, 12);
+        assert_eq!(voice.operators[0].output_level, 99);
+        assert!(
+            (voice.operators[0].frequency_ratio() - 3.0 * (7.0f32 / 1200.0).exp2()).abs() < 1e-4
+        );
+        assert_eq!(voice.operators[5].frequency_ratio(), 0.5);
+
+        let mut corrupt = single_voice_dump(&[0u8; SINGLE_VOICE_LENGTH]);
+        corrupt[10] = 1;
+        assert_eq!(parse_sysex(&corrupt), Err(Dx7Error::ChecksumMismatch));
+        assert_eq!(parse_sysex(&corrupt[..100]), Err(Dx7Error::Truncated));
+    }
+
+    #[test]
+    fn test_parse_bank() {
+        let mut bank = [0u8; PACKED_VOICE_LENGTH * BANK_VOICES];
+        let voice = &mut bank[PACKED_VOICE_LENGTH..2 * PACKED_VOICE_LENGTH];
+        // operator 6 comes first: right curve 3, left curve 1, detune 9,
+        // rate scaling 5, velocity sensitivity 6, fixed frequency coarse 2
+        voice[11] = 0b1101;
+        voice[12] = (9 << 3) | 5;
+        voice[13] = 6 << 2;
+        voice[15] = (2 << 1) | 1;
+        voice[110] = 31;
+        voice[111] = (1 << 3) | 3;
+        voice[118..128].copy_from_slice(b"BRASS   1 ");
+
+        let mut dump = vec![SYSEX_START, YAMAHA_ID, 0x00, BANK, 0x20, 0x00];
+        dump.extend_from_slice(&bank);
+        dump.push(checksum(&bank));
+        dump.push(SYSEX_END);
+
+        let voices = parse_sysex(&dump).unwrap();
+        assert_eq!(voices.len(), BANK_VOICES);
+        let voice = &voices[1];
+        assert_eq!(voice.name, "BRASS   1");
+        assert_eq!(voice.algorithm, 32);
+        assert_eq!(voice.feedback, 3);
+        assert!(voice.osc_key_sync);
+        let operator = &voice.operators[5];
+        assert_eq!((operator.left_curve, operator.right_curve), (1, 3));
+        assert_eq!((operator.detune, operator.rate_scaling), (9, 5));
+        assert_eq!(operator.velocity_sensitivity, 6);
+        assert!(operator.fixed_frequency);
+        assert_eq!(operator.frequency_ratio(), 1.0);
+        assert!((operator.fixed_hz().unwrap() - 100.0).abs() < 1e-3);
+        assert_eq!(voice.operators[0].fixed_hz(), None);
+    }
+
+    #[test]
+    fn test_parse_requires_bulk_dump() {
+        let mut dump = single_voice_dump(&[0u8; SINGLE_VOICE_LENGTH]);
+        // channel 16 is fine
+        dump[2] = 0x0F;
+        assert!(parse_sysex(&dump).is_ok());
+        // a parameter change that happens to carry a 0 after its sub-status
+        dump[2] = 0x10;
+        assert_eq!(parse_sysex(&dump), Err(Dx7Error::NotDx7));
+    }
+}
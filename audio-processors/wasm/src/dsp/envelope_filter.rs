@@ -18,24 +18,30 @@ pub struct EnvelopeFilterResult {
 
 pub struct EnvelopeFilter {
     attack_time: f32,
+    attack_time_ms: f32,
     clamp_to_one: bool,
     exp_factor: f32,
+    exp_factor_base: f32,
     level_calc_type: LevelCalcType,
     prev_envelope: f32,
     prev_state: State,
     release_time: f32,
+    release_time_ms: f32,
 }
 
 impl EnvelopeFilter {
     pub fn new(sample_rate: f32, exp_factor_base: f32) -> EnvelopeFilter {
         EnvelopeFilter {
             attack_time: 0.0,
+            attack_time_ms: 0.0,
             clamp_to_one: false,
             exp_factor: exp_factor_base * 1000.0 / sample_rate,
+            exp_factor_base,
             level_calc_type: LevelCalcType::PEAK,
             prev_envelope: 0.0,
             prev_state: State::REL,
             release_time: 0.0,
+            release_time_ms: 0.0,
         }
     }
 
@@ -85,6 +91,7 @@ impl EnvelopeFilter {
     }
 
     pub fn set_attack(&mut self, attack_time_ms: f32) {
+        self.attack_time_ms = attack_time_ms;
         self.attack_time = self.calc_env_time(attack_time_ms);
     }
 
@@ -93,9 +100,16 @@ impl EnvelopeFilter {
     }
 
     pub fn set_release(&mut self, release_time_ms: f32) {
+        self.release_time_ms = release_time_ms;
         self.release_time = self.calc_env_time(release_time_ms);
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.exp_factor = self.exp_factor_base * 1000.0 / sample_rate;
+        self.attack_time = self.calc_env_time(self.attack_time_ms);
+        self.release_time = self.calc_env_time(self.release_time_ms);
+    }
+
     pub fn set_type(&mut self, level_calc_type: LevelCalcType) {
         self.level_calc_type = level_calc_type;
     }
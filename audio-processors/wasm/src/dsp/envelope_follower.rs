@@ -63,6 +63,10 @@ impl EnvelopeFollower {
         self.threshold_lin = dB_2_gain!(threshold_db);
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.filter.set_sample_rate(sample_rate);
+    }
+
     pub fn set_sensitivity(&mut self, sensitivity: f32) {
         self.sensitivity = sensitivity;
     }
@@ -1,3 +1,5 @@
+use super::simd::F32x4;
+
 /// Pade approximant continued fraction
 pub fn tan(x: f32) -> f32 {
     let x2 = x * x;
@@ -6,6 +8,16 @@ pub fn tan(x: f32) -> f32 {
     numerator / denominator
 }
 
+/// [`tan`] of four values at once.
+#[inline]
+pub fn tan_lanes(x: F32x4) -> F32x4 {
+    let c = F32x4::splat;
+    let x2 = x * x;
+    let numerator = x * (c(-135135.0) + x2 * (c(17325.0) + x2 * (c(-378.0) + x2)));
+    let denominator = c(-135135.0) + x2 * (c(62370.0) + x2 * (c(-3150.0) + c(28.0) * x2));
+    numerator / denominator
+}
+
 /// Pade approximant continued fraction
 pub fn sin(x: f32) -> f32 {
     let x2 = x * x;
@@ -1,9 +1,29 @@
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
-use super::{adsr_unit::ADSRUnit, fast_math, phase_counter::PhaseCounter};
+use super::{
+    adsr_unit::ADSRUnit,
+    fast_math,
+    phase_counter::PhaseCounter,
+    rate_level_eg::RateLevelEG,
+    voice_lfo::{VoiceLfo, VoiceLfoWaveform},
+};
 
 const ONE_THIRD: f32 = 1.0 / 3.0;
 const ONE_SIXTH: f32 = 1.0 / 6.0;
+const MAX_FEEDBACK: f32 = 7.0;
+// feedback level of the fixed modulation index 1 the voice had before the
+// level was adjustable
+const DEFAULT_FEEDBACK: f32 = 5.3485;
+// operator levels follow the DX7's: 8 level steps per doubling, 99 steps
+// across the whole range
+const LEVEL_STEPS_PER_DOUBLING: f32 = 8.0;
+const MAX_LEVEL_STEPS: f32 = 99.0;
+// attenuation at velocity 0 with full sensitivity
+const MAX_VELOCITY_STEPS: f32 = 64.0;
+// pitch envelope level that leaves the pitch alone, and the shift in
+// semitones at either end of the range
+const PITCH_EG_CENTER: f32 = 50.0;
+const PITCH_EG_RANGE: f32 = 48.0;
 
 pub enum Algorithm {
     A1,
@@ -110,10 +130,49 @@ impl Oscillator {
     fn reset(&mut self) {
         self.phase_counter.reset();
     }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+    }
 }
 
 pub struct OperatorParams {
     pub freq_mod: f32,
+    /// Hz: the operator runs at this frequency whatever the note, instead of
+    /// `freq_mod` times the note's. The voice's pitch modulation still applies.
+    pub fixed_frequency: Option<f32>,
+    pub level: f32,
+    pub env_attack: f32,
+    pub env_decay: f32,
+    pub env_sustain: f32,
+    pub env_release: f32,
+    pub envelope: OperatorEnvelope,
+    /// Rates and levels (0 to 99) of `OperatorEnvelope::RateLevel`.
+    pub eg_rates: [f32; 4],
+    pub eg_levels: [f32; 4],
+    /// 0 to 1: how much quieter the operator gets the softer it is played.
+    pub velocity_sensitivity: f32,
+    pub key_scaling: KeyScaling,
+    /// 0 to 1: how much of the LFO's amplitude modulation the operator gets.
+    pub amp_mod_sensitivity: f32,
+}
+
+impl OperatorParams {
+    pub fn levels(&self) -> OperatorLevels {
+        OperatorLevels {
+            level: self.level,
+            env_attack: self.env_attack,
+            env_decay: self.env_decay,
+            env_sustain: self.env_sustain,
+            env_release: self.env_release,
+        }
+    }
+}
+
+/// Level and ADSR of an operator, the part of `OperatorParams` that may
+/// change from one sample to the next.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OperatorLevels {
     pub level: f32,
     pub env_attack: f32,
     pub env_decay: f32,
@@ -121,11 +180,166 @@ pub struct OperatorParams {
     pub env_release: f32,
 }
 
+/// Which envelope shapes an operator.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OperatorEnvelope {
+    /// Linear ADSR, from the `env_*` params.
+    Adsr,
+    /// DX7 style four rates and four levels, from `eg_rates` and `eg_levels`.
+    RateLevel,
+}
+
+impl From<u8> for OperatorEnvelope {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => OperatorEnvelope::RateLevel,
+            _ => OperatorEnvelope::Adsr,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyScalingCurve {
+    NegativeLinear,
+    NegativeExponential,
+    PositiveExponential,
+    PositiveLinear,
+}
+
+impl From<u8> for KeyScalingCurve {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => KeyScalingCurve::NegativeExponential,
+            2 => KeyScalingCurve::PositiveExponential,
+            3 => KeyScalingCurve::PositiveLinear,
+            _ => KeyScalingCurve::NegativeLinear,
+        }
+    }
+}
+
+/// DX7 style keyboard scaling of an operator's level and envelope rates,
+/// applied when a note starts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KeyScaling {
+    /// MIDI note the level scaling is centered on.
+    pub breakpoint: f32,
+    /// 0 to 1, below the breakpoint.
+    pub left_depth: f32,
+    /// 0 to 1, above the breakpoint.
+    pub right_depth: f32,
+    pub left_curve: KeyScalingCurve,
+    pub right_curve: KeyScalingCurve,
+    /// 0 to 1: how much faster the envelope gets the higher the note.
+    pub rate_scaling: f32,
+}
+
+impl Default for KeyScaling {
+    fn default() -> Self {
+        KeyScaling {
+            breakpoint: 60.0,
+            left_depth: 0.0,
+            right_depth: 0.0,
+            left_curve: KeyScalingCurve::NegativeLinear,
+            right_curve: KeyScalingCurve::NegativeLinear,
+            rate_scaling: 0.0,
+        }
+    }
+}
+
+impl KeyScaling {
+    /// Level change of `note` in level steps. The linear curves reach full
+    /// depth three octaves from the breakpoint, the exponential ones start
+    /// slower and keep on growing.
+    fn level_steps(&self, note: f32) -> f32 {
+        let distance = note - self.breakpoint;
+        let (depth, curve) = if distance < 0.0 {
+            (self.left_depth, self.left_curve)
+        } else {
+            (self.right_depth, self.right_curve)
+        };
+        let octaves = distance.abs() / 12.0;
+        let amount = match curve {
+            KeyScalingCurve::NegativeLinear | KeyScalingCurve::PositiveLinear => octaves / 3.0,
+            KeyScalingCurve::NegativeExponential | KeyScalingCurve::PositiveExponential => {
+                (octaves.exp2() - 1.0) / 7.0
+            }
+        };
+        let steps = MAX_LEVEL_STEPS * depth * amount;
+        match curve {
+            KeyScalingCurve::NegativeLinear | KeyScalingCurve::NegativeExponential => -steps,
+            KeyScalingCurve::PositiveExponential | KeyScalingCurve::PositiveLinear => steps,
+        }
+    }
+
+    /// Factor of the envelope times at `note`. As on the DX7, the rates rise
+    /// by up to 27 steps from the bottom to the top of the keyboard, every
+    /// 4 steps halving the times.
+    fn time_factor(&self, note: f32) -> f32 {
+        let key = (note / 3.0 - 7.0).clamp(0.0, 31.0);
+        (-self.rate_scaling * 7.0 / 8.0 * key / 4.0).exp2()
+    }
+}
+
+/// Pitch envelope and LFO, shared by all operators of a voice.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VoiceModulation {
+    /// Rates and levels (0 to 99) of the pitch envelope. Level 50 is the
+    /// played pitch, 0 and 99 are four octaves below and above.
+    pub pitch_eg_rates: [f32; 4],
+    pub pitch_eg_levels: [f32; 4],
+    pub lfo_waveform: VoiceLfoWaveform,
+    /// Hz.
+    pub lfo_frequency: f32,
+    /// Seconds from the start of a note until the LFO fades in.
+    pub lfo_delay: f32,
+    /// Seconds the LFO takes to fade in.
+    pub lfo_fade: f32,
+    /// Restart the LFO cycle with every note.
+    pub lfo_key_sync: bool,
+    /// Semitones.
+    pub lfo_pitch_depth: f32,
+    /// 0 to 1, scaled by the operators' `amp_mod_sensitivity`.
+    pub lfo_amp_depth: f32,
+}
+
+impl Default for VoiceModulation {
+    fn default() -> Self {
+        VoiceModulation {
+            pitch_eg_rates: [99.0; 4],
+            pitch_eg_levels: [PITCH_EG_CENTER; 4],
+            lfo_waveform: VoiceLfoWaveform::Triangle,
+            lfo_frequency: 5.0,
+            lfo_delay: 0.0,
+            lfo_fade: 0.0,
+            lfo_key_sync: true,
+            lfo_pitch_depth: 0.0,
+            lfo_amp_depth: 0.0,
+        }
+    }
+}
+
 struct Operator {
     osc: Oscillator,
     env: ADSRUnit,
+    rate_level_env: RateLevelEG,
+    // only the selected envelope runs, the other one catches up on a switch
+    envelope: OperatorEnvelope,
+    // the key is down
+    held: bool,
     freq_mod: f32,
+    fixed_frequency: Option<f32>,
     level: f32,
+    velocity_sensitivity: f32,
+    key_scaling: KeyScaling,
+    amp_mod_sensitivity: f32,
+    // gain from the LFO, set by the voice every sample
+    amp_mod: f32,
+    // pitch factor from the pitch envelope and LFO, set by the voice every
+    // sample for fixed frequency operators
+    pitch_mod: f32,
+    // of the current note
+    key_gain: f32,
+    time_factor: f32,
 }
 
 impl Operator {
@@ -133,51 +347,147 @@ impl Operator {
         Self {
             osc: Oscillator::new(sample_rate),
             env: ADSRUnit::new(sample_rate),
+            rate_level_env: RateLevelEG::new(sample_rate),
+            envelope: OperatorEnvelope::Adsr,
+            held: false,
             freq_mod: 1.0,
+            fixed_frequency: None,
             level: 1.0,
+            velocity_sensitivity: 0.0,
+            key_scaling: KeyScaling::default(),
+            amp_mod_sensitivity: 0.0,
+            amp_mod: 1.0,
+            pitch_mod: 1.0,
+            key_gain: 1.0,
+            time_factor: 1.0,
         }
     }
 
     fn get_next_sample(&mut self, frequency: f32, modulation: f32) -> f32 {
-        let mut out = self.osc.advance(frequency * self.freq_mod, modulation);
-        out *= self.level * self.env.get_next_sample();
+        let frequency = match self.fixed_frequency {
+            Some(hz) => hz * self.pitch_mod,
+            None => frequency * self.freq_mod,
+        };
+        let mut out = self.osc.advance(frequency, modulation);
+        out *= self.gain()
+            * self.amp_mod
+            * match self.envelope {
+                OperatorEnvelope::Adsr => self.env.get_next_sample(),
+                OperatorEnvelope::RateLevel => self.rate_level_env.get_next_sample(),
+            };
         out
     }
 
     fn reset(&mut self) {
         self.osc.reset();
         self.env.reset();
+        self.rate_level_env.reset();
+        self.held = false;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.osc.set_sample_rate(sample_rate);
+        self.env.set_sample_rate(sample_rate);
+        self.rate_level_env.set_sample_rate(sample_rate);
     }
 
     fn set_params(&mut self, params: &OperatorParams) {
         self.freq_mod = params.freq_mod;
-        self.level = params.level;
+        self.fixed_frequency = params.fixed_frequency;
+        self.velocity_sensitivity = params.velocity_sensitivity;
+        self.key_scaling = params.key_scaling;
+        self.amp_mod_sensitivity = params.amp_mod_sensitivity;
+        self.set_levels(&params.levels());
+        self.rate_level_env
+            .set_params(params.eg_rates, params.eg_levels, self.time_factor);
+        if params.envelope != self.envelope {
+            self.envelope = params.envelope;
+            self.sync_envelope();
+        }
+    }
+
+    fn set_levels(&mut self, levels: &OperatorLevels) {
+        self.level = levels.level;
         self.env.set_params(
-            params.env_attack,
-            params.env_decay,
-            params.env_sustain,
-            params.env_release,
+            levels.env_attack * self.time_factor,
+            levels.env_decay * self.time_factor,
+            levels.env_sustain,
+            levels.env_release * self.time_factor,
         );
     }
 
-    fn note_on(&mut self) {
-        self.env.note_on();
+    /// `note` is a MIDI note number, `velocity` 0 to 1.
+    fn note_on(&mut self, note: f32, velocity: f32) {
+        let velocity_steps =
+            -self.velocity_sensitivity * (1.0 - velocity.clamp(0.0, 1.0)) * MAX_VELOCITY_STEPS;
+        let steps = self.key_scaling.level_steps(note) + velocity_steps;
+        self.key_gain = (steps / LEVEL_STEPS_PER_DOUBLING).exp2();
+        self.time_factor = self.key_scaling.time_factor(note);
+        self.rate_level_env.set_time_factor(self.time_factor);
+        self.held = true;
+        match self.envelope {
+            OperatorEnvelope::Adsr => self.env.note_on(),
+            OperatorEnvelope::RateLevel => self.rate_level_env.note_on(),
+        }
+    }
+
+    fn level(&self) -> f32 {
+        self.gain()
+            * match self.envelope {
+                OperatorEnvelope::Adsr => self.env.level(),
+                OperatorEnvelope::RateLevel => self.rate_level_env.level(),
+            }
+    }
+
+    // scaling up saturates at full level
+    fn gain(&self) -> f32 {
+        (self.level * self.key_gain).min(1.0)
     }
 
     fn note_off(&mut self) {
-        self.env.note_off();
+        self.held = false;
+        match self.envelope {
+            OperatorEnvelope::Adsr => self.env.note_off(),
+            OperatorEnvelope::RateLevel => self.rate_level_env.note_off(),
+        }
+    }
+
+    /// Brings the newly selected envelope in line with the key: restarted
+    /// for a held note, silent otherwise.
+    fn sync_envelope(&mut self) {
+        match self.envelope {
+            OperatorEnvelope::Adsr => {
+                self.env.reset();
+                if self.held {
+                    self.env.note_on();
+                }
+            }
+            OperatorEnvelope::RateLevel => {
+                self.rate_level_env.reset();
+                if self.held {
+                    self.rate_level_env.note_on();
+                }
+            }
+        }
     }
 }
 
 pub struct FMVoice {
     operators: [Operator; 6],
     op_prev_outs: [f32; 6],
+    // output of the feedback operator before its previous one
+    feedback_prev: f32,
+    // modulation index of the feedback loop
+    feedback: f32,
     algorithm: Algorithm,
+    pitch_eg: RateLevelEG,
+    lfo: VoiceLfo,
+    modulation: VoiceModulation,
 }
 
 impl FMVoice {
     pub fn new(sample_rate: f32) -> Self {
-        Self {
+        let mut voice = Self {
             operators: [
                 Operator::new(sample_rate),
                 Operator::new(sample_rate),
@@ -187,23 +497,64 @@ impl FMVoice {
                 Operator::new(sample_rate),
             ],
             op_prev_outs: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            feedback_prev: 0.0,
+            feedback: 0.0,
             algorithm: Algorithm::A1,
-        }
+            pitch_eg: RateLevelEG::new(sample_rate)
+                .without_attack_jump()
+                .resting_at_level_4(),
+            lfo: VoiceLfo::new(sample_rate),
+            modulation: VoiceModulation::default(),
+        };
+        voice.set_modulation(&VoiceModulation::default());
+        voice.set_feedback(DEFAULT_FEEDBACK);
+        voice
     }
 
     pub fn note_off(&mut self) {
         for operator in self.operators.iter_mut() {
             operator.note_off();
         }
+        self.pitch_eg.note_off();
     }
 
-    pub fn note_on(&mut self) {
+    /// Starts the envelopes of `note` (MIDI note number, fractional notes
+    /// are fine) played at `velocity` (0 to 1).
+    pub fn note_on(&mut self, note: f32, velocity: f32) {
         for operator in self.operators.iter_mut() {
-            operator.note_on();
+            operator.note_on(note, velocity);
         }
+        self.pitch_eg.note_on();
+        self.lfo.note_on(self.modulation.lfo_key_sync);
+    }
+
+    /// Loudest operator envelope: 0 once every operator has gone idle.
+    pub fn level(&self) -> f32 {
+        self.operators
+            .iter()
+            .fold(0.0, |level, operator| level.max(operator.level()))
     }
 
     pub fn process(&mut self, frequency: f32) -> f32 {
+        let modulation = self.modulation;
+        let lfo = self.lfo.next(
+            modulation.lfo_waveform,
+            modulation.lfo_frequency,
+            modulation.lfo_delay,
+            modulation.lfo_fade,
+        );
+        let semitones = (self.pitch_eg.get_next_level() - PITCH_EG_CENTER) / PITCH_EG_CENTER
+            * PITCH_EG_RANGE
+            + lfo * modulation.lfo_pitch_depth;
+        let pitch_mod = (semitones / 12.0).exp2();
+        let frequency = frequency * pitch_mod;
+        // amplitude modulation only ever attenuates
+        let amp_lfo = modulation.lfo_amp_depth * (lfo * 0.5 + 0.5);
+        for operator in self.operators.iter_mut() {
+            operator.amp_mod = 1.0 - operator.amp_mod_sensitivity * amp_lfo;
+            operator.pitch_mod = pitch_mod;
+        }
+
         match self.algorithm {
             Algorithm::A1 => self.a_1(frequency),
             Algorithm::A2 => self.a_2(frequency),
@@ -245,21 +596,72 @@ impl FMVoice {
             operator.reset();
         }
         self.op_prev_outs.fill(0.0);
+        self.feedback_prev = 0.0;
+        self.pitch_eg.reset();
+        self.lfo.reset();
+    }
+
+    /// Restarts the LFO's sample-and-hold sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.lfo.set_seed(seed);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        for operator in self.operators.iter_mut() {
+            operator.set_sample_rate(sample_rate);
+        }
+        self.pitch_eg.set_sample_rate(sample_rate);
+        self.lfo.set_sample_rate(sample_rate);
     }
 
     pub fn set_algorithm(&mut self, algorithm: Algorithm) {
         self.algorithm = algorithm;
     }
 
+    /// DX7 style feedback level (0 to 7): every step doubles the modulation
+    /// index of the algorithm's feedback loop, up to π at 7. 0 turns it off.
+    /// Levels in between are fine, see `DEFAULT_FEEDBACK`.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        let feedback = feedback.min(MAX_FEEDBACK);
+        self.feedback = if feedback <= 0.0 {
+            0.0
+        } else {
+            PI * (feedback - MAX_FEEDBACK).exp2()
+        };
+    }
+
+    pub fn set_modulation(&mut self, modulation: &VoiceModulation) {
+        self.pitch_eg
+            .set_params(modulation.pitch_eg_rates, modulation.pitch_eg_levels, 1.0);
+        self.modulation = *modulation;
+    }
+
     pub fn set_parameters(&mut self, op_params: [&OperatorParams; 6]) {
         for (i, params) in op_params.iter().enumerate() {
             self.operators[i].set_params(params);
         }
     }
 
+    /// Updates just the levels and ADSRs of `set_parameters`, cheap enough
+    /// to call every sample.
+    pub fn set_levels(&mut self, levels: &[OperatorLevels; 6]) {
+        for (operator, levels) in self.operators.iter_mut().zip(levels) {
+            operator.set_levels(levels);
+        }
+    }
+
+    /// Runs the operator at `index`, modulated by the average of its last
+    /// two outputs.
+    fn feedback_sample(&mut self, index: usize, frequency: f32) -> f32 {
+        let modulation = self.feedback * (self.op_prev_outs[index] + self.feedback_prev) * 0.5;
+        let out = self.operators[index].get_next_sample(frequency, modulation);
+        self.feedback_prev = self.op_prev_outs[index];
+        self.op_prev_outs[index] = out;
+        out
+    }
+
     fn a_1(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -292,8 +694,7 @@ impl FMVoice {
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out);
         self.op_prev_outs[2] = o3_out;
 
-        let o2_out = self.operators[1].get_next_sample(frequency, self.op_prev_outs[1]);
-        self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
+        let o2_out = self.feedback_sample(1, frequency);
 
         let o1_out = self.operators[0].get_next_sample(frequency, o2_out);
         self.op_prev_outs[0] = o1_out;
@@ -302,8 +703,7 @@ impl FMVoice {
     }
 
     fn a_3(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -324,7 +724,8 @@ impl FMVoice {
     }
 
     fn a_4(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[3]);
+        let o6_out =
+            self.operators[5].get_next_sample(frequency, self.feedback * self.op_prev_outs[3]);
         self.op_prev_outs[5] = o6_out;
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
@@ -346,8 +747,7 @@ impl FMVoice {
     }
 
     fn a_5(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -368,7 +768,8 @@ impl FMVoice {
     }
 
     fn a_6(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[4]);
+        let o6_out =
+            self.operators[5].get_next_sample(frequency, self.feedback * self.op_prev_outs[4]);
         self.op_prev_outs[5] = o6_out;
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
@@ -390,8 +791,7 @@ impl FMVoice {
     }
 
     fn a_7(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -418,8 +818,7 @@ impl FMVoice {
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
 
-        let o4_out = self.operators[3].get_next_sample(frequency, self.op_prev_outs[3]);
-        self.op_prev_outs[3] = (o4_out + self.op_prev_outs[3]) * 0.5;
+        let o4_out = self.feedback_sample(3, frequency);
 
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out + o5_out);
         self.op_prev_outs[2] = o3_out;
@@ -446,8 +845,7 @@ impl FMVoice {
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out + o5_out);
         self.op_prev_outs[2] = o3_out;
 
-        let o2_out = self.operators[1].get_next_sample(frequency, self.op_prev_outs[1]);
-        self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
+        let o2_out = self.feedback_sample(1, frequency);
 
         let o1_out = self.operators[0].get_next_sample(frequency, o2_out);
         self.op_prev_outs[0] = o1_out;
@@ -465,8 +863,7 @@ impl FMVoice {
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out + o6_out);
         self.op_prev_outs[3] = o4_out;
 
-        let o3_out = self.operators[2].get_next_sample(frequency, self.op_prev_outs[2]);
-        self.op_prev_outs[2] = (o3_out + self.op_prev_outs[2]) * 0.5;
+        let o3_out = self.feedback_sample(2, frequency);
 
         let o2_out = self.operators[1].get_next_sample(frequency, o3_out);
         self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
@@ -478,8 +875,7 @@ impl FMVoice {
     }
 
     fn a_11(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, 0.0);
         self.op_prev_outs[4] = o5_out;
@@ -512,8 +908,7 @@ impl FMVoice {
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out + o5_out + o6_out);
         self.op_prev_outs[2] = o3_out;
 
-        let o2_out = self.operators[1].get_next_sample(frequency, self.op_prev_outs[1]);
-        self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
+        let o2_out = self.feedback_sample(1, frequency);
 
         let o1_out = self.operators[0].get_next_sample(frequency, o2_out);
         self.op_prev_outs[0] = o1_out;
@@ -522,8 +917,7 @@ impl FMVoice {
     }
 
     fn a_13(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, 0.0);
         self.op_prev_outs[4] = o5_out;
@@ -544,8 +938,7 @@ impl FMVoice {
     }
 
     fn a_14(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, 0.0);
         self.op_prev_outs[4] = o5_out;
@@ -578,8 +971,7 @@ impl FMVoice {
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out);
         self.op_prev_outs[2] = o3_out;
 
-        let o2_out = self.operators[1].get_next_sample(frequency, self.op_prev_outs[1]);
-        self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
+        let o2_out = self.feedback_sample(1, frequency);
 
         let o1_out = self.operators[0].get_next_sample(frequency, o2_out);
         self.op_prev_outs[0] = o1_out;
@@ -588,8 +980,7 @@ impl FMVoice {
     }
 
     fn a_16(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -622,8 +1013,7 @@ impl FMVoice {
         let o3_out = self.operators[2].get_next_sample(frequency, o4_out);
         self.op_prev_outs[2] = o3_out;
 
-        let o2_out = self.operators[1].get_next_sample(frequency, self.op_prev_outs[1]);
-        self.op_prev_outs[1] = (o2_out + self.op_prev_outs[1]) * 0.5;
+        let o2_out = self.feedback_sample(1, frequency);
 
         let o1_out = self.operators[0].get_next_sample(frequency, o2_out + o3_out + o5_out);
         self.op_prev_outs[0] = o1_out;
@@ -641,8 +1031,7 @@ impl FMVoice {
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out);
         self.op_prev_outs[3] = o4_out;
 
-        let o3_out = self.operators[2].get_next_sample(frequency, self.op_prev_outs[2]);
-        self.op_prev_outs[2] = (o3_out + self.op_prev_outs[2]) * 0.5;
+        let o3_out = self.feedback_sample(2, frequency);
 
         let o2_out = self.operators[1].get_next_sample(frequency, 0.0);
         self.op_prev_outs[1] = o2_out;
@@ -654,8 +1043,7 @@ impl FMVoice {
     }
 
     fn a_19(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -685,8 +1073,7 @@ impl FMVoice {
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out + o6_out);
         self.op_prev_outs[3] = o4_out;
 
-        let o3_out = self.operators[2].get_next_sample(frequency, self.op_prev_outs[2]);
-        self.op_prev_outs[2] = (o3_out + self.op_prev_outs[2]) * 0.5;
+        let o3_out = self.feedback_sample(2, frequency);
 
         let o2_out = self.operators[1].get_next_sample(frequency, o3_out);
         self.op_prev_outs[1] = o2_out;
@@ -707,8 +1094,7 @@ impl FMVoice {
         let o4_out = self.operators[3].get_next_sample(frequency, o6_out);
         self.op_prev_outs[3] = o4_out;
 
-        let o3_out = self.operators[2].get_next_sample(frequency, self.op_prev_outs[2]);
-        self.op_prev_outs[2] = (o3_out + self.op_prev_outs[2]) * 0.5;
+        let o3_out = self.feedback_sample(2, frequency);
 
         let o2_out = self.operators[1].get_next_sample(frequency, o3_out);
         self.op_prev_outs[1] = o2_out;
@@ -720,8 +1106,7 @@ impl FMVoice {
     }
 
     fn a_22(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -742,8 +1127,7 @@ impl FMVoice {
     }
 
     fn a_23(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -764,8 +1148,7 @@ impl FMVoice {
     }
 
     fn a_24(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -786,8 +1169,7 @@ impl FMVoice {
     }
 
     fn a_25(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -808,8 +1190,7 @@ impl FMVoice {
     }
 
     fn a_26(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, 0.0);
         self.op_prev_outs[4] = o5_out;
@@ -839,8 +1220,7 @@ impl FMVoice {
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out + o6_out);
         self.op_prev_outs[3] = o4_out;
 
-        let o3_out = self.operators[2].get_next_sample(frequency, self.op_prev_outs[2]);
-        self.op_prev_outs[2] = (o3_out + self.op_prev_outs[2]) * 0.5;
+        let o3_out = self.feedback_sample(2, frequency);
 
         let o2_out = self.operators[1].get_next_sample(frequency, o3_out);
         self.op_prev_outs[1] = o2_out;
@@ -855,8 +1235,7 @@ impl FMVoice {
         let o6_out = self.operators[5].get_next_sample(frequency, 0.0);
         self.op_prev_outs[5] = o6_out;
 
-        let o5_out = self.operators[4].get_next_sample(frequency, self.op_prev_outs[4]);
-        self.op_prev_outs[4] = (o5_out + self.op_prev_outs[4]) * 0.5;
+        let o5_out = self.feedback_sample(4, frequency);
 
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out);
         self.op_prev_outs[3] = o4_out;
@@ -874,8 +1253,7 @@ impl FMVoice {
     }
 
     fn a_29(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -899,8 +1277,7 @@ impl FMVoice {
         let o6_out = self.operators[5].get_next_sample(frequency, 0.0);
         self.op_prev_outs[5] = o6_out;
 
-        let o5_out = self.operators[4].get_next_sample(frequency, self.op_prev_outs[4]);
-        self.op_prev_outs[4] = (o5_out + self.op_prev_outs[4]) * 0.5;
+        let o5_out = self.feedback_sample(4, frequency);
 
         let o4_out = self.operators[3].get_next_sample(frequency, o5_out);
         self.op_prev_outs[3] = o4_out;
@@ -918,8 +1295,7 @@ impl FMVoice {
     }
 
     fn a_31(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, o6_out);
         self.op_prev_outs[4] = o5_out;
@@ -940,8 +1316,7 @@ impl FMVoice {
     }
 
     fn a_32(&mut self, frequency: f32) -> f32 {
-        let o6_out = self.operators[5].get_next_sample(frequency, self.op_prev_outs[5]);
-        self.op_prev_outs[5] = (o6_out + self.op_prev_outs[5]) * 0.5;
+        let o6_out = self.feedback_sample(5, frequency);
 
         let o5_out = self.operators[4].get_next_sample(frequency, 0.0);
         self.op_prev_outs[4] = o5_out;
@@ -961,3 +1336,186 @@ impl FMVoice {
         (o1_out + o2_out + o3_out + o4_out + o5_out + o6_out) * ONE_SIXTH
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // op6 on its own, one period of its output once it has settled
+    fn feedback_period(feedback: f32) -> Vec<f32> {
+        let mut voice = FMVoice::new(48000.0);
+        voice.set_algorithm(Algorithm::A32);
+        voice.set_feedback(feedback);
+        let params: Vec<OperatorParams> = (0..6)
+            .map(|op| OperatorParams {
+                freq_mod: 1.0,
+                fixed_frequency: None,
+                level: if op == 5 { 1.0 } else { 0.0 },
+                env_attack: 0.0,
+                env_decay: 0.0,
+                env_sustain: 1.0,
+                env_release: 0.1,
+                envelope: OperatorEnvelope::Adsr,
+                eg_rates: [99.0; 4],
+                eg_levels: [99.0, 99.0, 99.0, 0.0],
+                velocity_sensitivity: 0.0,
+                key_scaling: KeyScaling::default(),
+                amp_mod_sensitivity: 0.0,
+            })
+            .collect();
+        voice.set_parameters([
+            &params[0], &params[1], &params[2], &params[3], &params[4], &params[5],
+        ]);
+        voice.note_on(69.0, 1.0);
+        let output: Vec<f32> = (0..1000).map(|_| voice.process(480.0)).collect();
+        output[900..].to_vec()
+    }
+
+    #[test]
+    fn test_feedback_adds_even_harmonics() {
+        // a sine is the negative of itself half a period later, a waveform
+        // with even harmonics is not
+        let half_wave_error = |period: Vec<f32>| {
+            (0..50).fold(0.0f32, |max, n| max.max((period[n] + period[n + 50]).abs()))
+        };
+        assert!(half_wave_error(feedback_period(0.0)) < 1e-3);
+        assert!(half_wave_error(feedback_period(7.0)) > 0.02);
+    }
+
+    #[test]
+    fn test_feedback_levels() {
+        let mut voice = FMVoice::new(48000.0);
+        let mut index = |level: f32| {
+            voice.set_feedback(level);
+            voice.feedback
+        };
+        assert_eq!(index(0.0), 0.0);
+        assert_eq!(index(-1.0), 0.0);
+        assert_eq!(index(7.0), PI);
+        assert_eq!(index(8.0), PI);
+        for level in 1..7 {
+            assert!((index(level as f32) - PI / (1 << (7 - level)) as f32).abs() < 1e-6);
+        }
+        assert!((index(DEFAULT_FEEDBACK) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_envelope_switch_picks_up_held_note() {
+        let mut params = OperatorParams {
+            freq_mod: 1.0,
+            fixed_frequency: None,
+            level: 1.0,
+            env_attack: 0.0,
+            env_decay: 0.0,
+            env_sustain: 1.0,
+            env_release: 0.1,
+            envelope: OperatorEnvelope::Adsr,
+            eg_rates: [99.0; 4],
+            eg_levels: [99.0, 99.0, 99.0, 0.0],
+            velocity_sensitivity: 0.0,
+            key_scaling: KeyScaling::default(),
+            amp_mod_sensitivity: 0.0,
+        };
+        let mut operator = Operator::new(48000.0);
+        operator.set_params(&params);
+        operator.note_on(69.0, 1.0);
+        operator.get_next_sample(440.0, 0.0);
+        // the rate/level envelope did not run along
+        assert_eq!(operator.rate_level_env.level(), 0.0);
+
+        params.envelope = OperatorEnvelope::RateLevel;
+        operator.set_params(&params);
+        for _ in 0..100 {
+            operator.get_next_sample(440.0, 0.0);
+        }
+        assert_eq!(operator.level(), 1.0);
+
+        operator.note_off();
+        params.envelope = OperatorEnvelope::Adsr;
+        operator.set_params(&params);
+        assert_eq!(operator.level(), 0.0);
+    }
+
+    #[test]
+    fn test_key_scaling() {
+        let scaling = KeyScaling {
+            left_depth: 1.0,
+            right_depth: 0.5,
+            right_curve: KeyScalingCurve::PositiveExponential,
+            rate_scaling: 1.0,
+            ..KeyScaling::default()
+        };
+        assert_eq!(scaling.level_steps(60.0), 0.0);
+        // three octaves out: the full range on the left, half of it on the right
+        assert!((scaling.level_steps(24.0) + 99.0).abs() < 1e-3);
+        assert!((scaling.level_steps(96.0) - 49.5).abs() < 1e-3);
+
+        assert_eq!(scaling.time_factor(12.0), 1.0);
+        assert!(scaling.time_factor(96.0) < scaling.time_factor(60.0));
+    }
+
+    #[test]
+    fn test_rate_scaling_applies_at_note_on() {
+        let params = OperatorParams {
+            freq_mod: 1.0,
+            fixed_frequency: None,
+            level: 1.0,
+            env_attack: 0.0,
+            env_decay: 0.0,
+            env_sustain: 1.0,
+            env_release: 0.1,
+            envelope: OperatorEnvelope::RateLevel,
+            eg_rates: [50.0; 4],
+            eg_levels: [99.0, 99.0, 99.0, 0.0],
+            velocity_sensitivity: 0.0,
+            key_scaling: KeyScaling {
+                rate_scaling: 1.0,
+                ..KeyScaling::default()
+            },
+            amp_mod_sensitivity: 0.0,
+        };
+        // the params are only set once, before the notes
+        let attack_level = |note: f32| {
+            let mut operator = Operator::new(48000.0);
+            operator.set_params(&params);
+            operator.note_on(note, 1.0);
+            for _ in 0..1000 {
+                operator.get_next_sample(440.0, 0.0);
+            }
+            operator.level()
+        };
+        assert!(attack_level(96.0) > attack_level(36.0));
+    }
+
+    #[test]
+    fn test_fixed_frequency_ignores_the_note() {
+        let params = OperatorParams {
+            freq_mod: 2.0,
+            fixed_frequency: Some(100.0),
+            level: 1.0,
+            env_attack: 0.0,
+            env_decay: 0.0,
+            env_sustain: 1.0,
+            env_release: 0.1,
+            envelope: OperatorEnvelope::Adsr,
+            eg_rates: [99.0; 4],
+            eg_levels: [99.0, 99.0, 99.0, 0.0],
+            velocity_sensitivity: 0.0,
+            key_scaling: KeyScaling::default(),
+            amp_mod_sensitivity: 0.0,
+        };
+        let render = |frequency: f32| {
+            let mut operator = Operator::new(48000.0);
+            operator.set_params(&params);
+            operator.note_on(69.0, 1.0);
+            (0..480)
+                .map(|_| operator.get_next_sample(frequency, 0.0))
+                .collect::<Vec<f32>>()
+        };
+        let output = render(440.0);
+        assert_eq!(output, render(880.0));
+        // one period of 100 Hz
+        assert!(output[..240].iter().all(|y| *y >= -1e-3));
+        assert!(output[240..].iter().all(|y| *y <= 1e-3));
+    }
+}
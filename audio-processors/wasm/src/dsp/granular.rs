@@ -1,5 +1,6 @@
 use super::{
     circular_buffer::{cubic_interpolation, CircularBuffer, InterpolationType},
+    oversampling::OversamplingFactor,
     tape_sim::TapeSimFilter,
     vasv_filter::{pre_feedback_loop_hipass, VASVFilter},
 };
@@ -31,20 +32,31 @@ pub struct GranularProcessor {
     next_stretch_factor: u8,
 }
 
+// (input, output) buffer lengths in samples
+fn buffer_sizes(
+    sample_rate: f32,
+    max_grain_size_sec: usize,
+    max_pitch_shift_octaves: usize,
+) -> (usize, usize) {
+    let max_output_buffer_size = max_grain_size_sec * sample_rate as usize;
+    let max_input_buffer_size = max_output_buffer_size * max_pitch_shift_octaves;
+    (2 * max_input_buffer_size, 4 * max_output_buffer_size)
+}
+
 impl GranularProcessor {
     pub fn new(
         sample_rate: f32,
         max_grain_size_sec: usize,
         max_pitch_shift_octaves: usize,
     ) -> GranularProcessor {
-        let max_output_buffer_size = max_grain_size_sec * sample_rate as usize;
-        let max_input_buffer_size = max_output_buffer_size * max_pitch_shift_octaves;
+        let (input_buffer_size, output_buffer_size) =
+            buffer_sizes(sample_rate, max_grain_size_sec, max_pitch_shift_octaves);
 
         GranularProcessor {
-            input_buffer: CircularBuffer::new(2 * max_input_buffer_size),
+            input_buffer: CircularBuffer::new(input_buffer_size),
             current_input_size: 0.0,
 
-            output_buffer: CircularBuffer::new(4 * max_output_buffer_size),
+            output_buffer: CircularBuffer::new(output_buffer_size),
             current_output_size: 0.0,
             written_samples_ctr: 0.0,
 
@@ -101,6 +113,28 @@ impl GranularProcessor {
         self.next_stretch_factor = self.stretch_factor;
     }
 
+    /// Keeps the recorded input and grains, resizing the buffers for the new
+    /// rate.
+    pub fn set_sample_rate(
+        &mut self,
+        sample_rate: f32,
+        max_grain_size_sec: usize,
+        max_pitch_shift_octaves: usize,
+    ) {
+        let (input_buffer_size, output_buffer_size) =
+            buffer_sizes(sample_rate, max_grain_size_sec, max_pitch_shift_octaves);
+        self.input_buffer.resize(input_buffer_size);
+        self.output_buffer.resize(output_buffer_size);
+
+        self.output_lowpass.set_sample_rate(sample_rate);
+        self.output_tape_sim_bandpass.set_sample_rate(sample_rate);
+        self.output_pre_feedback_hipass.set_sample_rate(sample_rate);
+    }
+
+    pub fn set_oversampling(&mut self, factor: OversamplingFactor) {
+        self.output_tape_sim_bandpass.set_oversampling(factor);
+    }
+
     pub fn process(
         &mut self,
         x: f32,
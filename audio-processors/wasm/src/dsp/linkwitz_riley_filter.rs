@@ -94,6 +94,12 @@ impl LinkwitzRileyFilter {
         self.h = LinkwitzRileyFilter::calc_h(self.g);
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.g = LinkwitzRileyFilter::calc_g(self.cutoff_freq, sample_rate);
+        self.h = LinkwitzRileyFilter::calc_h(self.g);
+    }
+
     pub fn set_filter_type(&mut self, filter_type: LinkwitzRileyFilterType) {
         self.filter_type = filter_type;
     }
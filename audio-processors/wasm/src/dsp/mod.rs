@@ -2,7 +2,9 @@ pub mod adsr_unit;
 pub mod allpass_filter;
 pub mod analog_eg;
 pub mod circular_buffer;
+pub mod custom_waveform;
 pub mod delay;
+pub mod dx7;
 pub mod envelope_filter;
 pub mod envelope_follower;
 pub mod fast_math;
@@ -12,12 +14,20 @@ pub mod granular;
 pub mod linkwitz_riley_filter;
 pub mod lofi;
 pub mod noise;
+pub mod oversampling;
 pub mod phase_counter;
 pub mod pinking_filter;
 pub mod pitch_tracker;
+pub mod random;
+pub mod rate_level_eg;
+pub mod simd;
 pub mod smoothed_value;
 pub mod tape_sim;
 pub mod vasv_filter;
+pub mod voice_allocator;
+pub mod voice_lfo;
+pub mod waveforms;
+pub mod wavetable;
 
 pub mod utils;
 
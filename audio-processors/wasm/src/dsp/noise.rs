@@ -1,18 +1,26 @@
-use rand::Rng;
+use super::random::Random;
 
 pub struct WhiteNoiseGenerator {
-    rng: rand::rngs::ThreadRng,
+    rng: Random,
 }
 
 impl WhiteNoiseGenerator {
-    pub fn new() -> WhiteNoiseGenerator {
+    pub fn new(seed: u32) -> WhiteNoiseGenerator {
         WhiteNoiseGenerator {
-            rng: rand::thread_rng(),
+            rng: Random::new(seed),
         }
     }
 
     pub fn next(&mut self) -> f32 {
-        uniform_random_value(&mut self.rng)
+        self.rng.next_bipolar()
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng.set_seed(seed);
+    }
+
+    pub fn reset(&mut self) {
+        self.rng.reset();
     }
 }
 
@@ -20,18 +28,18 @@ pub struct BrownianNoiseGenerator {
     buffer_size: usize,
     curr_position: usize,
     normalised_samples: Vec<f32>,
-    rng: rand::rngs::ThreadRng,
+    rng: Random,
     unnormalised_samples: Vec<f32>,
 }
 
 impl BrownianNoiseGenerator {
-    pub fn new(buffer_size: usize) -> BrownianNoiseGenerator {
+    pub fn new(buffer_size: usize, seed: u32) -> BrownianNoiseGenerator {
         BrownianNoiseGenerator {
             buffer_size,
             curr_position: buffer_size,
             normalised_samples: vec![0.0; buffer_size],
             unnormalised_samples: vec![0.0; buffer_size],
-            rng: rand::thread_rng(),
+            rng: Random::new(seed),
         }
     }
 
@@ -49,16 +57,20 @@ impl BrownianNoiseGenerator {
         let last_unnormalised_sample = self.unnormalised_samples.last_mut().unwrap();
         *last_unnormalised_sample = 0.0;
         self.curr_position = self.buffer_size;
+        self.rng.reset();
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng.set_seed(seed);
     }
 
     fn generate_normalised_samples(&mut self) {
-        let init = self.unnormalised_samples.last().unwrap() + uniform_random_value(&mut self.rng);
+        let init = self.unnormalised_samples.last().unwrap() + self.rng.next_bipolar();
         self.unnormalised_samples[0] = init;
         let mut min = init;
         let mut max = init;
         for i in 1..self.buffer_size {
-            let next =
-                0.95 * self.unnormalised_samples[i - 1] + uniform_random_value(&mut self.rng);
+            let next = 0.95 * self.unnormalised_samples[i - 1] + self.rng.next_bipolar();
             self.unnormalised_samples[i] = next;
 
             if next < min {
@@ -78,9 +90,3 @@ impl BrownianNoiseGenerator {
             });
     }
 }
-
-#[inline(always)]
-fn uniform_random_value(rng: &mut rand::rngs::ThreadRng) -> f32 {
-    let rnd = rng.gen::<f32>();
-    rnd + rnd - 1.0
-}
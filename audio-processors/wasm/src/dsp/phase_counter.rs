@@ -11,19 +11,55 @@ impl PhaseCounter {
         PhaseCounter { phase: 0.0 }
     }
 
+    /// Returns the current phase and moves it by `increment`, which may be
+    /// negative for through-zero modulation; the phase wraps into `[0, 1)`
+    /// in both directions.
     pub fn advance(&mut self, increment: f32) -> f32 {
-        assert!(increment >= 0.0);
-
         let last = self.phase;
-        let next = (last + increment).fract();
-        self.phase = next;
+        self.phase = wrap(last + increment);
 
         // let offset = phase_shift_rad * FRAC_1_2PI;
         // last + offset
         last
     }
 
+    /// The phase the next `advance` returns.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = wrap(phase);
+    }
+
     pub fn reset(&mut self) {
         self.phase = 0.0;
     }
 }
+
+#[inline(always)]
+fn wrap(phase: f32) -> f32 {
+    let wrapped = phase - phase.floor();
+    // tiny negative phases round up to exactly 1.0
+    if wrapped >= 1.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wraps_backwards() {
+        let mut phase_counter = PhaseCounter::new();
+        assert_eq!(phase_counter.advance(-0.25), 0.0);
+        assert_eq!(phase_counter.advance(-0.25), 0.75);
+        assert_eq!(phase_counter.advance(-1e-9), 0.5);
+
+        phase_counter.set_phase(-1e-9);
+        assert_eq!(phase_counter.advance(0.0), 0.0);
+    }
+}
@@ -1,12 +1,12 @@
+/// Paul Kellet's pinking filter; the coefficients are fitted for 44.1 kHz
+/// but hold up well at other common rates.
 pub struct PinkingFilter {
-    sample_rate: f32,
     state: (f32, f32, f32, f32, f32, f32, f32),
 }
 
 impl PinkingFilter {
-    pub fn new(sample_rate: f32) -> PinkingFilter {
+    pub fn new() -> PinkingFilter {
         PinkingFilter {
-            sample_rate,
             state: (0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
         }
     }
@@ -16,7 +16,7 @@ impl PinkingFilter {
         self.state = (
             0.99886 * s0 + x * 0.0555179,
             0.99332 * s1 + x * 0.0750759,
-            0.96900 * s2 + x * 0.1538520,
+            0.96900 * s2 + x * 0.153852,
             0.86650 * s3 + x * 0.3104856,
             0.55000 * s4 + x * 0.5329522,
             -0.7616 * s5 - x * 0.0168980,
@@ -31,7 +31,7 @@ impl PinkingFilter {
             + self.state.5
             + self.state.6
             + x * 0.5362)
-            * 0.115830421;
+            * 0.11583042;
 
         self.state.6 = x * 0.115926;
 
@@ -91,6 +91,11 @@ impl PitchTracker {
         self.write_index = 0;
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.pre_filter.set_sample_rate(sample_rate);
+    }
+
     pub fn set_harmonic_threshold(&mut self, harmonic_threshold: f32) {
         self.harmonic_threshold = harmonic_threshold;
     }
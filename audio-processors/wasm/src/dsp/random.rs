@@ -0,0 +1,109 @@
+/// Seed used by every processor until `set_seed` is called, so fresh
+/// processors already produce the same sequence on every run.
+pub const DEFAULT_SEED: u32 = 0x5EED;
+
+/// Small xorshift PRNG. Not suitable for anything but audio, but cheap and
+/// fully reproducible from its seed.
+#[derive(Clone)]
+pub struct Random {
+    seed: u32,
+    state: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Random {
+        Random {
+            seed,
+            state: scramble(seed),
+        }
+    }
+
+    /// Restarts the sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+        self.state = scramble(seed);
+    }
+
+    /// Restarts the sequence from the current seed.
+    pub fn reset(&mut self) {
+        self.state = scramble(self.seed);
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Uniform in `[0, 1)`.
+    #[inline]
+    pub fn next_f32(&mut self) -> f32 {
+        // the upper 24 bits fit the mantissa exactly
+        (self.next_u32() >> 8) as f32 * (1.0 / (1 << 24) as f32)
+    }
+
+    /// Uniform in `[-1, 1)`.
+    #[inline]
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32().mul_add(2.0, -1.0)
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random::new(DEFAULT_SEED)
+    }
+}
+
+/// Derives the seed of one of several generators sharing a `seed`, e.g. one
+/// per channel.
+#[inline]
+pub fn derive_seed(seed: u32, stream: u32) -> u32 {
+    seed.wrapping_add(stream.wrapping_mul(0x9E37_79B9))
+}
+
+// splitmix32 finaliser: spreads nearby seeds over the whole state space and
+// never yields the all-zero state xorshift gets stuck in
+fn scramble(seed: u32) -> u32 {
+    let mut z = seed.wrapping_add(0x9E37_79B9);
+    z = (z ^ (z >> 16)).wrapping_mul(0x85EB_CA6B);
+    z = (z ^ (z >> 13)).wrapping_mul(0xC2B2_AE35);
+    z ^= z >> 16;
+    if z == 0 {
+        0x9E37_79B9
+    } else {
+        z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(7);
+        let first: Vec<u32> = (0..16).map(|_| a.next_u32()).collect();
+        assert_ne!(first, (0..16).map(|_| b.next_u32()).collect::<Vec<_>>());
+
+        b.set_seed(42);
+        assert_eq!(first, (0..16).map(|_| b.next_u32()).collect::<Vec<_>>());
+
+        a.reset();
+        assert_eq!(first, (0..16).map(|_| a.next_u32()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bipolar_range() {
+        let mut random = Random::default();
+        for _ in 0..10000 {
+            let x = random.next_bipolar();
+            assert!((-1.0..1.0).contains(&x));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+// levels and rates run from 0 to 99, as on the DX7
+const MAX_LEVEL: f32 = 99.0;
+const MAX_RATE: f32 = 99.0;
+// the amplitude doubles every 8 level steps
+const LEVEL_STEPS_PER_DOUBLING: f32 = 8.0;
+// a segment at rate 0 takes this long across the whole level range, and
+// every 6.24 rate steps halve that
+const SLOWEST_SWEEP_SECONDS: f32 = 38.0;
+const RATE_STEPS_PER_DOUBLING: f32 = 6.24;
+// rising segments skip the inaudible bottom of the range
+const ATTACK_JUMP_LEVEL: f32 = 40.0;
+
+enum EGState {
+    /// Moving towards level 1, 2 or 3 while the key is held.
+    Segment(usize),
+    Sustain,
+    /// Moving towards level 4.
+    Release,
+    Idle,
+}
+
+/// Four rate/four level envelope in the style of the DX7. The level moves
+/// in logarithmic steps, so decays are exponential in amplitude.
+///
+/// A note starts from wherever the envelope is, going through levels 1, 2
+/// and 3 and holding at 3. Releasing it goes to level 4, where the next
+/// note picks up again.
+pub struct RateLevelEG {
+    sample_rate: f32,
+    state: EGState,
+    // current level, in level steps
+    level: f32,
+    // level steps per sample of the current segment
+    increment: f32,
+    attack_jump: bool,
+    // rests at level 4 rather than at 0 when idle
+    rest_at_level_4: bool,
+
+    // params
+    rates: [f32; 4],
+    levels: [f32; 4],
+    time_factor: f32,
+}
+
+impl RateLevelEG {
+    pub fn new(sample_rate: f32) -> RateLevelEG {
+        RateLevelEG {
+            sample_rate,
+            state: EGState::Idle,
+            level: 0.0,
+            increment: 0.0,
+            attack_jump: true,
+            rest_at_level_4: false,
+
+            rates: [MAX_RATE; 4],
+            levels: [MAX_LEVEL, MAX_LEVEL, MAX_LEVEL, 0.0],
+            time_factor: 1.0,
+        }
+    }
+
+    /// An envelope for something other than amplitude: rising segments do
+    /// not skip the bottom of the range.
+    pub fn without_attack_jump(mut self) -> RateLevelEG {
+        self.attack_jump = false;
+        self
+    }
+
+    /// An envelope that rests at level 4 before the first note, and follows
+    /// level 4 while idle, e.g. for pitch.
+    pub fn resting_at_level_4(mut self) -> RateLevelEG {
+        self.rest_at_level_4 = true;
+        self.reset();
+        self
+    }
+
+    pub fn get_next_sample(&mut self) -> f32 {
+        self.get_next_level();
+        self.level()
+    }
+
+    /// Advances the envelope and returns its level, from 0 to 99.
+    pub fn get_next_level(&mut self) -> f32 {
+        if let Some(target) = self.target() {
+            if self.level < target {
+                self.level = (self.level + self.increment).min(target);
+            } else {
+                self.level = (self.level - self.increment).max(target);
+            }
+            if self.level == target {
+                self.next_state();
+            }
+        }
+        self.level
+    }
+
+    /// Current amplitude, without advancing the envelope.
+    pub fn level(&self) -> f32 {
+        level_to_amplitude(self.level)
+    }
+
+    pub fn note_on(&mut self) {
+        self.start(EGState::Segment(0));
+    }
+
+    pub fn note_off(&mut self) {
+        if !matches!(self.state, EGState::Idle) {
+            self.start(EGState::Release);
+        }
+    }
+
+    /// Back to silence, or to level 4 with `resting_at_level_4`.
+    pub fn reset(&mut self) {
+        self.state = EGState::Idle;
+        self.level = if self.rest_at_level_4 {
+            self.levels[3]
+        } else {
+            0.0
+        };
+    }
+
+    /// `rates` and `levels` run from 0 to 99. `time_factor` scales the
+    /// time of every segment.
+    pub fn set_params(&mut self, rates: [f32; 4], levels: [f32; 4], time_factor: f32) {
+        let rates = rates.map(|rate| rate.clamp(0.0, MAX_RATE));
+        let changed = rates != self.rates || time_factor != self.time_factor;
+        self.rates = rates;
+        self.levels = levels.map(|level| level.clamp(0.0, MAX_LEVEL));
+        self.time_factor = time_factor;
+        if self.rest_at_level_4 && matches!(self.state, EGState::Idle) {
+            self.level = self.levels[3];
+        }
+        if changed {
+            self.calc_increment();
+        }
+    }
+
+    /// Scales the time of every segment, as `set_params` does.
+    pub fn set_time_factor(&mut self, time_factor: f32) {
+        if time_factor != self.time_factor {
+            self.time_factor = time_factor;
+            self.calc_increment();
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.calc_increment();
+    }
+
+    fn start(&mut self, state: EGState) {
+        self.state = state;
+        if let Some(target) = self.target().filter(|_| self.attack_jump) {
+            if target > ATTACK_JUMP_LEVEL {
+                self.level = self.level.max(ATTACK_JUMP_LEVEL);
+            }
+        }
+        self.calc_increment();
+    }
+
+    fn next_state(&mut self) {
+        match self.state {
+            EGState::Segment(index) if index < 2 => self.start(EGState::Segment(index + 1)),
+            EGState::Segment(_) => self.state = EGState::Sustain,
+            EGState::Release => self.state = EGState::Idle,
+            EGState::Sustain | EGState::Idle => (),
+        }
+    }
+
+    fn target(&self) -> Option<f32> {
+        match self.state {
+            EGState::Segment(index) => Some(self.levels[index]),
+            EGState::Release => Some(self.levels[3]),
+            EGState::Sustain | EGState::Idle => None,
+        }
+    }
+
+    fn calc_increment(&mut self) {
+        let rate = match self.state {
+            EGState::Segment(index) => self.rates[index],
+            _ => self.rates[3],
+        };
+        let seconds = sweep_seconds(rate) * self.time_factor;
+        self.increment = MAX_LEVEL / (seconds * self.sample_rate).max(1.0);
+    }
+}
+
+/// Time a segment at `rate` takes across the whole level range.
+pub fn sweep_seconds(rate: f32) -> f32 {
+    SLOWEST_SWEEP_SECONDS * (-rate.clamp(0.0, MAX_RATE) / RATE_STEPS_PER_DOUBLING).exp2()
+}
+
+/// Level 0 is silence, 99 full amplitude.
+pub fn level_to_amplitude(level: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    ((level.min(MAX_LEVEL) - MAX_LEVEL) / LEVEL_STEPS_PER_DOUBLING).exp2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_and_loop_back() {
+        let sample_rate = 1000.0;
+        let mut eg = RateLevelEG::new(sample_rate);
+        let levels = [99.0, 80.0, 59.0, 50.0];
+        // at rate 99 every segment is over within a sample
+        eg.set_params([99.0; 4], levels, 1.0);
+        eg.note_on();
+        let mut peak = 0.0f32;
+        for _ in 0..100 {
+            peak = peak.max(eg.get_next_sample());
+        }
+        assert_eq!(peak, 1.0);
+        // 40 steps below the peak is 5 doublings down
+        assert!((eg.level() - 1.0 / 32.0).abs() < 1e-6);
+
+        eg.note_off();
+        for _ in 0..100 {
+            eg.get_next_sample();
+        }
+        assert!((eg.level() - level_to_amplitude(50.0)).abs() < 1e-6);
+
+        // the next note starts from level 4 rather than from silence
+        eg.set_params([0.0; 4], levels, 1.0);
+        eg.note_on();
+        let level = eg.get_next_sample();
+        assert!(level > level_to_amplitude(50.0));
+        assert!(level < level_to_amplitude(51.0));
+
+        eg.reset();
+        assert_eq!(eg.level(), 0.0);
+    }
+
+    #[test]
+    fn test_resting_level_follows_level_4() {
+        let mut eg = RateLevelEG::new(1000.0)
+            .without_attack_jump()
+            .resting_at_level_4();
+        eg.set_params([99.0; 4], [99.0, 99.0, 99.0, 50.0], 1.0);
+        assert_eq!(eg.get_next_level(), 50.0);
+        eg.set_params([99.0; 4], [99.0, 99.0, 99.0, 60.0], 1.0);
+        assert_eq!(eg.get_next_level(), 60.0);
+
+        // a held note does not jump
+        eg.note_on();
+        eg.get_next_level();
+        eg.set_params([0.0; 4], [99.0, 99.0, 99.0, 20.0], 1.0);
+        assert!(eg.get_next_level() > 60.0);
+    }
+}
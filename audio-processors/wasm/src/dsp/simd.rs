@@ -0,0 +1,238 @@
+//! Four-lane `f32` vector used to process four consecutive frames at once.
+//! With the `simd` feature on a `wasm32` target built with `+simd128` it
+//! maps to `v128`, otherwise to a plain array with the same lane-wise
+//! semantics.
+//!
+//! Only per-frame work shared by all channels is vectorised (`Phaser`,
+//! modulated `MultiFilter` blocks, `Multiplier`); blocks with constant
+//! coefficients keep the scalar per-channel path. `FMSynthesizer` voices
+//! are deliberately not packed into lanes: every voice branches on its own
+//! algorithm, envelope stage and note state and idle voices are skipped, so
+//! four voices per vector would mostly compute silent lanes.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+#[cfg(all(
+    feature = "simd",
+    target_arch = "wasm32",
+    not(target_feature = "simd128")
+))]
+compile_error!("the `simd` feature needs RUSTFLAGS=\"-C target-feature=+simd128\"");
+
+pub const LANES: usize = 4;
+
+#[cfg(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128"))]
+mod lanes {
+    use core::arch::wasm32::*;
+
+    #[derive(Clone, Copy)]
+    pub struct F32x4(v128);
+
+    impl F32x4 {
+        #[inline(always)]
+        pub fn splat(value: f32) -> F32x4 {
+            F32x4(f32x4_splat(value))
+        }
+
+        #[inline(always)]
+        pub fn from_array(values: [f32; 4]) -> F32x4 {
+            F32x4(f32x4(values[0], values[1], values[2], values[3]))
+        }
+
+        #[inline(always)]
+        pub fn from_slice(values: &[f32]) -> F32x4 {
+            let values = &values[..4];
+            // SAFETY: the slice holds 4 floats, wasm loads need no alignment
+            F32x4(unsafe { v128_load(values.as_ptr() as *const v128) })
+        }
+
+        #[inline(always)]
+        pub fn write_to_slice(self, out: &mut [f32]) {
+            let out = &mut out[..4];
+            // SAFETY: the slice holds 4 floats, wasm stores need no alignment
+            unsafe { v128_store(out.as_mut_ptr() as *mut v128, self.0) }
+        }
+
+        #[inline(always)]
+        pub(super) fn add_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4(f32x4_add(self.0, rhs.0))
+        }
+
+        #[inline(always)]
+        pub(super) fn sub_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4(f32x4_sub(self.0, rhs.0))
+        }
+
+        #[inline(always)]
+        pub(super) fn mul_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4(f32x4_mul(self.0, rhs.0))
+        }
+
+        #[inline(always)]
+        pub(super) fn div_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4(f32x4_div(self.0, rhs.0))
+        }
+    }
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "wasm32", target_feature = "simd128")))]
+mod lanes {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct F32x4([f32; 4]);
+
+    impl F32x4 {
+        #[inline(always)]
+        pub fn splat(value: f32) -> F32x4 {
+            // `[value; 4]` turns into a store loop at opt-level "s"
+            F32x4([value, value, value, value])
+        }
+
+        #[inline(always)]
+        pub fn from_array(values: [f32; 4]) -> F32x4 {
+            F32x4(values)
+        }
+
+        #[inline(always)]
+        pub fn from_slice(values: &[f32]) -> F32x4 {
+            F32x4([values[0], values[1], values[2], values[3]])
+        }
+
+        #[inline(always)]
+        pub fn write_to_slice(self, out: &mut [f32]) {
+            out[..4].copy_from_slice(&self.0);
+        }
+
+        #[inline(always)]
+        pub(super) fn add_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4([
+                self.0[0] + rhs.0[0],
+                self.0[1] + rhs.0[1],
+                self.0[2] + rhs.0[2],
+                self.0[3] + rhs.0[3],
+            ])
+        }
+
+        #[inline(always)]
+        pub(super) fn sub_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4([
+                self.0[0] - rhs.0[0],
+                self.0[1] - rhs.0[1],
+                self.0[2] - rhs.0[2],
+                self.0[3] - rhs.0[3],
+            ])
+        }
+
+        #[inline(always)]
+        pub(super) fn mul_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4([
+                self.0[0] * rhs.0[0],
+                self.0[1] * rhs.0[1],
+                self.0[2] * rhs.0[2],
+                self.0[3] * rhs.0[3],
+            ])
+        }
+
+        #[inline(always)]
+        pub(super) fn div_lanes(self, rhs: F32x4) -> F32x4 {
+            F32x4([
+                self.0[0] / rhs.0[0],
+                self.0[1] / rhs.0[1],
+                self.0[2] / rhs.0[2],
+                self.0[3] / rhs.0[3],
+            ])
+        }
+    }
+}
+
+pub use lanes::F32x4;
+
+impl Default for F32x4 {
+    fn default() -> F32x4 {
+        F32x4::splat(0.0)
+    }
+}
+
+impl Add for F32x4 {
+    type Output = F32x4;
+
+    #[inline(always)]
+    fn add(self, rhs: F32x4) -> F32x4 {
+        self.add_lanes(rhs)
+    }
+}
+
+impl Sub for F32x4 {
+    type Output = F32x4;
+
+    #[inline(always)]
+    fn sub(self, rhs: F32x4) -> F32x4 {
+        self.sub_lanes(rhs)
+    }
+}
+
+impl Mul for F32x4 {
+    type Output = F32x4;
+
+    #[inline(always)]
+    fn mul(self, rhs: F32x4) -> F32x4 {
+        self.mul_lanes(rhs)
+    }
+}
+
+impl Div for F32x4 {
+    type Output = F32x4;
+
+    #[inline(always)]
+    fn div(self, rhs: F32x4) -> F32x4 {
+        self.div_lanes(rhs)
+    }
+}
+
+/// Length of a per-frame scratch buffer that four-frame groups can be
+/// written to without a scalar tail.
+#[inline]
+pub fn padded_len(frames: usize) -> usize {
+    frames.div_ceil(LANES) * LANES
+}
+
+/// Loads frames `n..n + 4` of `values`; lanes past its end repeat the last
+/// frame.
+#[inline(always)]
+pub fn load_frames(values: &[f32], n: usize) -> F32x4 {
+    if n + LANES <= values.len() {
+        return F32x4::from_slice(&values[n..]);
+    }
+    let mut lanes = [values[values.len() - 1]; LANES];
+    for (lane, value) in values[n..].iter().enumerate() {
+        lanes[lane] = *value;
+    }
+    F32x4::from_array(lanes)
+}
+
+/// `out[n] = a[n] * b[n]`, four samples at a time.
+pub fn multiply(out: &mut [f32], a: &[f32], b: &[f32]) {
+    let len = out.len().min(a.len()).min(b.len());
+    let vector_len = len - len % LANES;
+
+    for n in (0..vector_len).step_by(LANES) {
+        (F32x4::from_slice(&a[n..]) * F32x4::from_slice(&b[n..])).write_to_slice(&mut out[n..]);
+    }
+    for n in vector_len..len {
+        out[n] = a[n] * b[n];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_frames_repeats_last_frame() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut out = [0.0; 8];
+        assert_eq!(padded_len(values.len()), out.len());
+        load_frames(&values, 0).write_to_slice(&mut out);
+        load_frames(&values, 4).write_to_slice(&mut out[4..]);
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 6.0, 6.0]);
+    }
+}
@@ -6,6 +6,7 @@ pub enum SmoothingType {
 pub struct SmoothedValue {
     countdown: i32,
     current_value: f32,
+    ramp_length_seconds: f32,
     smoothing_type: SmoothingType,
     steps_to_target: i32,
     step_width: f32,
@@ -24,6 +25,7 @@ impl SmoothedValue {
         SmoothedValue {
             countdown: 0,
             current_value: initial_value,
+            ramp_length_seconds,
             smoothing_type,
             steps_to_target: (ramp_length_seconds * sample_rate).trunc() as i32,
             step_width: 0.0,
@@ -59,6 +61,13 @@ impl SmoothedValue {
         self.set_current_and_target_value(self.target_value);
     }
 
+    /// Recomputes the ramp length in samples; a running ramp jumps to its
+    /// target.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.steps_to_target = (self.ramp_length_seconds * sample_rate).trunc() as i32;
+        self.reset();
+    }
+
     pub fn set_current_and_target_value(&mut self, new_value: f32) {
         self.target_value = new_value;
         self.current_value = new_value;
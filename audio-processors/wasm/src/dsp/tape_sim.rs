@@ -1,7 +1,11 @@
-use super::vasv_filter::VASVFilter;
+use super::{
+    oversampling::{Oversampler, OversamplingFactor},
+    vasv_filter::VASVFilter,
+};
 
 pub struct TapeSimFilter {
     bpf: VASVFilter,
+    clipper_oversampler: Oversampler,
 }
 
 const TAPE_BANDPASS_OUTPUT_GAIN: f32 = 3.98; // approx. 12dB
@@ -10,20 +14,33 @@ impl TapeSimFilter {
     pub fn new(sample_rate: f32) -> TapeSimFilter {
         let mut bpf = VASVFilter::new(sample_rate);
         bpf.set_params(725.0, 0.33, 1.0, 0.0, 0.0, 0.0);
-        TapeSimFilter { bpf }
+        TapeSimFilter {
+            bpf,
+            clipper_oversampler: Oversampler::new(),
+        }
     }
 
     pub fn process(&mut self, x: f32) -> f32 {
-        let mut y = tape_sim_soft_clipper(x);
+        let mut y = self.clipper_oversampler.process(x, tape_sim_soft_clipper);
         y = TAPE_BANDPASS_OUTPUT_GAIN * self.bpf.process(y);
         y
     }
 
     pub fn reset(&mut self) {
         self.bpf.reset();
+        self.clipper_oversampler.reset();
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.bpf.set_sample_rate(sample_rate);
     }
-}
 
+    /// Oversampling factor of the soft clipper, the bandpass runs at the
+    /// host rate.
+    pub fn set_oversampling(&mut self, factor: OversamplingFactor) {
+        self.clipper_oversampler.set_factor(factor);
+    }
+}
 
 #[inline(always)]
 fn tape_sim_soft_clipper(x: f32) -> f32 {
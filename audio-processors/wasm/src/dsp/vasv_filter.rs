@@ -2,7 +2,10 @@ use std::f32::consts::PI;
 
 use crate::{dB_2_gain, gain_2_dB};
 
-use super::fast_math;
+use super::{
+    fast_math,
+    simd::{self, F32x4, LANES},
+};
 
 fn peak_gain_for_q(q: f32) -> f32 {
     if q <= 0.707 {
@@ -22,6 +25,28 @@ struct FilterCoeffs {
     sigma: f32,
 }
 
+/// Coefficients of every frame of a block, for
+/// [`VASVFilter::process_multi_out_with`].
+pub struct FrameCoeffs {
+    alpha: Vec<f32>,
+    alpha_0: Vec<f32>,
+    rho: Vec<f32>,
+    sigma: Vec<f32>,
+}
+
+impl FrameCoeffs {
+    pub fn new(buffer_frame_length: usize) -> FrameCoeffs {
+        // padded to whole lane groups
+        let len = simd::padded_len(buffer_frame_length);
+        FrameCoeffs {
+            alpha: vec![0.0; len],
+            alpha_0: vec![0.0; len],
+            rho: vec![0.0; len],
+            sigma: vec![0.0; len],
+        }
+    }
+}
+
 struct FilterParams {
     f_c: f32,
     q: f32,
@@ -114,6 +139,63 @@ impl VASVFilter {
         (bpf, bsf, hpf, lpf2)
     }
 
+    /// Same as [`VASVFilter::process_multi_out`] with the coefficients of
+    /// frame `n` from [`VASVFilter::calc_frame_coeffs`] instead of the ones
+    /// set by `set_params`.
+    #[inline]
+    pub fn process_multi_out_with(
+        &mut self,
+        x: f32,
+        coeffs: &FrameCoeffs,
+        n: usize,
+    ) -> (f32, f32, f32, f32) {
+        let alpha = coeffs.alpha[n];
+        let (sn_1, sn_2) = self.state;
+
+        let hpf = coeffs.alpha_0[n] * (x - coeffs.rho[n] * sn_1 - sn_2);
+        let bpf = alpha * hpf + sn_1;
+        let lpf = alpha * bpf + sn_2;
+        let bsf = hpf + lpf;
+        let lpf2 = lpf + coeffs.sigma[n] * sn_1;
+
+        self.state = (alpha * hpf + bpf, alpha * bpf + lpf);
+
+        (bpf, bsf, hpf, lpf2)
+    }
+
+    /// Coefficients for every frame of `f_c` and `q`, four frames at a time,
+    /// so that one block of parameters can drive the filters of every
+    /// channel.
+    pub fn calc_frame_coeffs(
+        f_c: &[f32],
+        q: &[f32],
+        sample_rate_inv: f32,
+        coeffs: &mut FrameCoeffs,
+    ) {
+        let c = F32x4::splat;
+        for n in (0..f_c.len()).step_by(LANES) {
+            let f_c = simd::load_frames(f_c, n);
+            let q = simd::load_frames(q, n);
+
+            let alpha = fast_math::tan_lanes(c(PI * sample_rate_inv) * f_c);
+            let sigma = c(4.0 * sample_rate_inv * sample_rate_inv) * f_c * f_c / alpha;
+            let r = c(1.0) / (c(2.0) * q);
+            let rho = c(2.0) * r + alpha;
+            let alpha_0 = c(1.0) / (c(1.0) + c(2.0) * r * alpha + alpha * alpha);
+
+            alpha.write_to_slice(&mut coeffs.alpha[n..]);
+            alpha_0.write_to_slice(&mut coeffs.alpha_0[n..]);
+            rho.write_to_slice(&mut coeffs.rho[n..]);
+            sigma.write_to_slice(&mut coeffs.sigma[n..]);
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.sample_rate_inv = 1.0 / sample_rate;
+        self.calc_coeffs(true, true);
+    }
+
     fn calc_coeffs(&mut self, f_c_changed: bool, q_changed: bool) {
         if !f_c_changed && !q_changed {
             return;
@@ -149,3 +231,37 @@ pub fn pre_feedback_loop_hipass(sample_rate: f32) -> VASVFilter {
     hipass.set_params(100.0, 0.707, 0.0, 0.0, 1.0, 0.0);
     hipass
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_coeffs_match_set_params() {
+        let f_c: Vec<f32> = (0..7).map(|n| 200.0 + 1500.0 * n as f32).collect();
+        let q = [0.707, 1.0, 2.0, 4.0, 8.0, 12.0, 20.0];
+        let mut coeffs = FrameCoeffs::new(f_c.len());
+        VASVFilter::calc_frame_coeffs(&f_c, &q, 1.0 / 48000.0, &mut coeffs);
+
+        let mut expected = VASVFilter::new(48000.0);
+        let mut filter = VASVFilter::new(48000.0);
+        for n in 0..f_c.len() {
+            let x = if n == 0 { 1.0 } else { 0.0 };
+            expected.set_params(f_c[n], q[n], 0.0, 0.0, 0.0, 0.0);
+            let (bpf, bsf, hpf, lpf) = expected.process_multi_out(x);
+            let out = filter.process_multi_out_with(x, &coeffs, n);
+            for (y, y_expected) in [out.0, out.1, out.2, out.3]
+                .iter()
+                .zip([bpf, bsf, hpf, lpf])
+            {
+                assert!(
+                    (y - y_expected).abs() < 1e-5,
+                    "{}: {} vs {}",
+                    n,
+                    y,
+                    y_expected
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoiceMode {
+    Poly,
+    /// One voice, retriggered by every note.
+    Mono,
+    /// One voice, only triggered by a note played while no other is held.
+    Legato,
+}
+
+impl From<u8> for VoiceMode {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => VoiceMode::Mono,
+            2 => VoiceMode::Legato,
+            _ => VoiceMode::Poly,
+        }
+    }
+}
+
+/// Which voice a note takes over once all of them are busy. Voices whose
+/// note has been released are always taken before held ones.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StealMode {
+    Oldest,
+    Quietest,
+}
+
+impl From<u8> for StealMode {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => StealMode::Quietest,
+            _ => StealMode::Oldest,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoiceAction {
+    /// Start `note` on `voice`. `steal` is set if the voice is still
+    /// sounding and should be faded out first.
+    NoteOn {
+        voice: usize,
+        note: u8,
+        velocity: f32,
+        steal: bool,
+    },
+    /// Move `voice` to `note` without retriggering it.
+    ChangeNote {
+        voice: usize,
+        note: u8,
+    },
+    NoteOff {
+        voice: usize,
+    },
+}
+
+#[derive(Copy, Clone, Default)]
+struct Slot {
+    note: u8,
+    held: bool,
+    // driven from outside the allocator, never handed out
+    reserved: bool,
+    // order of the note ons, to find the oldest voice
+    started: u64,
+}
+
+/// Assigns note events to a fixed number of voices.
+pub struct VoiceAllocator {
+    mode: VoiceMode,
+    steal_mode: StealMode,
+    slots: Vec<Slot>,
+    note_count: u64,
+    // held notes with their velocities, latest last (mono and legato)
+    held_notes: Vec<(u8, f32)>,
+    // the voice playing the held notes (mono and legato)
+    mono_voice: usize,
+}
+
+impl VoiceAllocator {
+    pub fn new(voices: usize) -> VoiceAllocator {
+        VoiceAllocator {
+            mode: VoiceMode::Poly,
+            steal_mode: StealMode::Oldest,
+            slots: vec![Slot::default(); voices],
+            note_count: 0,
+            held_notes: Vec::with_capacity(128),
+            mono_voice: 0,
+        }
+    }
+
+    pub fn mode(&self) -> VoiceMode {
+        self.mode
+    }
+
+    /// Switching the voice mode forgets every held note; the caller has to
+    /// release the voices.
+    pub fn set_mode(&mut self, mode: VoiceMode, steal_mode: StealMode) {
+        if mode != self.mode {
+            self.reset();
+        }
+        self.mode = mode;
+        self.steal_mode = steal_mode;
+    }
+
+    /// Keeps `voice` from being allocated while it is played by other means,
+    /// e.g. the trigger lanes of `FMSynthesizer`. A note it was playing is
+    /// forgotten, so its note off has no effect.
+    pub fn set_reserved(&mut self, voice: usize, reserved: bool) {
+        let slot = &mut self.slots[voice];
+        slot.reserved = reserved;
+        if reserved {
+            slot.held = false;
+            if self.mode != VoiceMode::Poly && voice == self.mono_voice {
+                self.held_notes.clear();
+            }
+        }
+    }
+
+    /// `levels` are the current output levels of the voices; a voice that
+    /// is neither held nor sounding is free. Returns `None` if every voice
+    /// is reserved.
+    pub fn note_on(&mut self, note: u8, velocity: f32, levels: &[f32]) -> Option<VoiceAction> {
+        self.note_count += 1;
+        if self.mode != VoiceMode::Poly {
+            if self.held_notes.is_empty() {
+                self.mono_voice = self.slots.iter().position(|slot| !slot.reserved)?;
+            }
+            let retrigger = self.mode == VoiceMode::Mono || self.held_notes.is_empty();
+            self.held_notes.retain(|(n, _)| *n != note);
+            self.held_notes.push((note, velocity));
+            return Some(self.mono_action(self.mono_voice, note, velocity, retrigger));
+        }
+
+        let voice = self
+            .slots
+            .iter()
+            .position(|slot| slot.held && slot.note == note)
+            .or_else(|| self.free_voice(levels))
+            .or_else(|| self.steal_voice(false, levels))
+            .or_else(|| self.steal_voice(true, levels))?;
+        let slot = &mut self.slots[voice];
+        let steal = levels[voice] > 0.0 && !(slot.held && slot.note == note);
+        *slot = Slot {
+            note,
+            held: true,
+            reserved: false,
+            started: self.note_count,
+        };
+        Some(VoiceAction::NoteOn {
+            voice,
+            note,
+            velocity,
+            steal,
+        })
+    }
+
+    pub fn note_off(&mut self, note: u8) -> Option<VoiceAction> {
+        if self.mode != VoiceMode::Poly {
+            let playing = self.held_notes.last().map(|(n, _)| *n);
+            self.held_notes.retain(|(n, _)| *n != note);
+            if playing != Some(note) {
+                return None;
+            }
+            // fall back to the latest note still held
+            return Some(match self.held_notes.last() {
+                Some(&(note, velocity)) => self.mono_action(
+                    self.mono_voice,
+                    note,
+                    velocity,
+                    self.mode == VoiceMode::Mono,
+                ),
+                None => VoiceAction::NoteOff {
+                    voice: self.mono_voice,
+                },
+            });
+        }
+
+        let voice = self
+            .slots
+            .iter()
+            .position(|slot| slot.held && slot.note == note)?;
+        self.slots[voice].held = false;
+        Some(VoiceAction::NoteOff { voice })
+    }
+
+    /// Forgets every note; reserved voices stay reserved.
+    pub fn reset(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = Slot {
+                reserved: slot.reserved,
+                ..Slot::default()
+            };
+        }
+        self.held_notes.clear();
+    }
+
+    fn mono_action(
+        &mut self,
+        voice: usize,
+        note: u8,
+        velocity: f32,
+        retrigger: bool,
+    ) -> VoiceAction {
+        self.slots[voice] = Slot {
+            note,
+            held: true,
+            reserved: false,
+            started: self.note_count,
+        };
+        if retrigger {
+            VoiceAction::NoteOn {
+                voice,
+                note,
+                velocity,
+                // the envelopes simply restart from where they are
+                steal: false,
+            }
+        } else {
+            VoiceAction::ChangeNote { voice, note }
+        }
+    }
+
+    fn free_voice(&self, levels: &[f32]) -> Option<usize> {
+        self.candidates(false)
+            .filter(|voice| levels[*voice] <= 0.0)
+            .min_by_key(|voice| self.slots[*voice].started)
+    }
+
+    fn steal_voice(&self, held: bool, levels: &[f32]) -> Option<usize> {
+        let candidates = self.candidates(held);
+        match self.steal_mode {
+            StealMode::Oldest => candidates.min_by_key(|voice| self.slots[*voice].started),
+            StealMode::Quietest => candidates.min_by(|a, b| levels[*a].total_cmp(&levels[*b])),
+        }
+    }
+
+    fn candidates(&self, held: bool) -> impl Iterator<Item = usize> + '_ {
+        (0..self.slots.len()).filter(move |voice| {
+            let slot = &self.slots[*voice];
+            slot.held == held && !slot.reserved
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn voice(action: Option<VoiceAction>) -> usize {
+        match action {
+            Some(VoiceAction::NoteOn { voice, .. }) => voice,
+            _ => panic!("expected a note on, got {:?}", action),
+        }
+    }
+
+    #[test]
+    fn test_poly_stealing() {
+        let mut allocator = VoiceAllocator::new(3);
+        let mut levels = [0.0; 3];
+        for note in 60..63 {
+            let v = voice(allocator.note_on(note, 1.0, &levels));
+            levels[v] = 1.0;
+        }
+        assert_eq!(
+            allocator.note_off(61),
+            Some(VoiceAction::NoteOff { voice: 1 })
+        );
+
+        // the released voice goes first, then the oldest held one
+        assert_eq!(voice(allocator.note_on(70, 1.0, &levels)), 1);
+        assert_eq!(
+            allocator.note_on(71, 1.0, &levels),
+            Some(VoiceAction::NoteOn {
+                voice: 0,
+                note: 71,
+                velocity: 1.0,
+                steal: true
+            })
+        );
+
+        allocator.set_mode(VoiceMode::Poly, StealMode::Quietest);
+        levels = [1.0, 0.8, 0.2];
+        assert_eq!(voice(allocator.note_on(72, 1.0, &levels)), 2);
+    }
+
+    #[test]
+    fn test_legato_falls_back_to_held_note() {
+        let mut allocator = VoiceAllocator::new(8);
+        allocator.set_mode(VoiceMode::Legato, StealMode::Oldest);
+        let levels = [0.0; 8];
+
+        assert_eq!(voice(allocator.note_on(60, 0.5, &levels)), 0);
+        assert_eq!(
+            allocator.note_on(64, 1.0, &levels),
+            Some(VoiceAction::ChangeNote { voice: 0, note: 64 })
+        );
+        assert_eq!(allocator.note_off(60), None);
+        assert_eq!(
+            allocator.note_on(67, 1.0, &levels),
+            Some(VoiceAction::ChangeNote { voice: 0, note: 67 })
+        );
+        assert_eq!(
+            allocator.note_off(67),
+            Some(VoiceAction::ChangeNote { voice: 0, note: 64 })
+        );
+        assert_eq!(
+            allocator.note_off(64),
+            Some(VoiceAction::NoteOff { voice: 0 })
+        );
+    }
+
+    #[test]
+    fn test_reserved_voices_are_skipped() {
+        let mut allocator = VoiceAllocator::new(3);
+        let levels = [0.0; 3];
+        allocator.set_reserved(0, true);
+        assert_eq!(voice(allocator.note_on(60, 1.0, &levels)), 1);
+        // taken over while held: the note off finds nothing
+        allocator.set_reserved(1, true);
+        assert_eq!(allocator.note_off(60), None);
+        assert_eq!(voice(allocator.note_on(61, 1.0, &levels)), 2);
+        // stealing sticks to the voices left
+        assert_eq!(voice(allocator.note_on(62, 1.0, &levels)), 2);
+        allocator.set_reserved(2, true);
+        assert_eq!(allocator.note_on(63, 1.0, &levels), None);
+
+        allocator.set_mode(VoiceMode::Mono, StealMode::Oldest);
+        assert_eq!(allocator.note_on(64, 1.0, &levels), None);
+        allocator.set_reserved(1, false);
+        assert_eq!(voice(allocator.note_on(64, 1.0, &levels)), 1);
+    }
+}
@@ -0,0 +1,137 @@
+use super::{
+    phase_counter::PhaseCounter,
+    random::{Random, DEFAULT_SEED},
+    waveforms,
+};
+
+/// The waveforms of the DX7's LFO, in its order.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoiceLfoWaveform {
+    Triangle,
+    SawDown,
+    SawUp,
+    Square,
+    Sine,
+    SampleAndHold,
+}
+
+impl From<u8> for VoiceLfoWaveform {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => VoiceLfoWaveform::SawDown,
+            2 => VoiceLfoWaveform::SawUp,
+            3 => VoiceLfoWaveform::Square,
+            4 => VoiceLfoWaveform::Sine,
+            5 => VoiceLfoWaveform::SampleAndHold,
+            _ => VoiceLfoWaveform::Triangle,
+        }
+    }
+}
+
+/// LFO of a single voice. Every note restarts its delay: the LFO stays
+/// silent for `delay` seconds, then fades in over `fade` seconds.
+pub struct VoiceLfo {
+    phase_counter: PhaseCounter,
+    sample_rate_inv: f32,
+    random: Random,
+    held_value: f32,
+    last_arg: f32,
+    // seconds since the note started
+    elapsed: f32,
+}
+
+impl VoiceLfo {
+    pub fn new(sample_rate: f32) -> VoiceLfo {
+        VoiceLfo {
+            phase_counter: PhaseCounter::new(),
+            sample_rate_inv: 1.0 / sample_rate,
+            random: Random::new(DEFAULT_SEED),
+            held_value: 0.0,
+            last_arg: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Bipolar output, scaled by the fade-in.
+    pub fn next(
+        &mut self,
+        waveform: VoiceLfoWaveform,
+        frequency: f32,
+        delay: f32,
+        fade: f32,
+    ) -> f32 {
+        let arg = self.phase_counter.advance(frequency * self.sample_rate_inv);
+        // a new value at the start of every cycle
+        if arg < self.last_arg {
+            self.held_value = self.random.next_bipolar();
+        }
+        self.last_arg = arg;
+
+        let value = match waveform {
+            // LFO rates are far below the point where aliasing matters
+            VoiceLfoWaveform::Triangle => waveforms::triangle(arg, 0.0),
+            VoiceLfoWaveform::SawDown => waveforms::ramp(arg, 0.0),
+            VoiceLfoWaveform::SawUp => waveforms::saw(arg, 0.0),
+            VoiceLfoWaveform::Square => waveforms::square(arg, 0.0),
+            VoiceLfoWaveform::Sine => waveforms::sine(arg),
+            VoiceLfoWaveform::SampleAndHold => self.held_value,
+        };
+
+        let faded_in = if self.elapsed < delay {
+            0.0
+        } else if self.elapsed < delay + fade {
+            (self.elapsed - delay) / fade
+        } else {
+            1.0
+        };
+        self.elapsed += self.sample_rate_inv;
+        faded_in * value
+    }
+
+    /// Restarts the delay, and the cycle with `key_sync`.
+    pub fn note_on(&mut self, key_sync: bool) {
+        self.elapsed = 0.0;
+        if key_sync {
+            self.phase_counter.reset();
+            self.last_arg = 0.0;
+        }
+    }
+
+    /// Restarts the sample-and-hold sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.random.set_seed(seed);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.phase_counter.reset();
+        self.random.reset();
+        self.held_value = 0.0;
+        self.last_arg = 0.0;
+        self.elapsed = 0.0;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_and_fade_in() {
+        let mut lfo = VoiceLfo::new(1000.0);
+        lfo.note_on(true);
+        // a quarter cycle per sample: 0, 1, 0, -1 at full depth
+        let output: Vec<f32> = (0..12)
+            .map(|_| lfo.next(VoiceLfoWaveform::Triangle, 250.0, 0.004, 0.004))
+            .collect();
+        assert!(output[..4].iter().all(|y| *y == 0.0));
+        assert!((output[5] - 0.25).abs() < 1e-6);
+        assert!((output[7] + 0.75).abs() < 1e-6);
+        assert!((output[9] - 1.0).abs() < 1e-6);
+        assert!((output[11] + 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,119 @@
+//! Naive and anti-aliased waveform kernels over a normalized phase `arg` in
+//! `[0, 1)`. `increment` is the phase advance per sample and sets the width of
+//! the polyBLEP/polyBLAMP corrections around each discontinuity.
+
+use std::f32::consts::TAU as TWO_PI;
+
+use super::fast_math;
+
+const ONE_THIRD: f32 = 1.0 / 3.0;
+
+#[inline(always)]
+pub fn sine(arg: f32) -> f32 {
+    let arg1 = (arg + 0.25).fract();
+    if arg1 < 0.5 {
+        return fast_math::sin(TWO_PI * (arg1 - 0.25));
+    }
+    0.0 - fast_math::sin(TWO_PI * (arg1 - 0.75))
+}
+
+#[inline(always)]
+pub fn triangle(arg: f32, increment: f32) -> f32 {
+    let arg1 = (arg + 0.25).fract();
+    let arg2 = (arg + 0.75).fract();
+    let mut y = arg * 4.0;
+    if y >= 3.0 {
+        y -= 4.0;
+    } else if y > 1.0 {
+        y = 2.0 - y;
+    }
+
+    y += 4.0 * increment * (polyblamp(arg1, increment) - polyblamp(arg2, increment));
+
+    // 1.0 - 2.0 * (2.0 * arg - 1.0).abs()
+    // let abs = (arg + arg - 1.0).abs();
+    // 1.0 - (abs + abs)
+    y
+}
+
+#[inline(always)]
+pub fn ramp(arg: f32, increment: f32) -> f32 {
+    1.0 - (arg + arg) + polyblep(arg, increment)
+}
+
+#[inline(always)]
+pub fn saw(arg: f32, increment: f32) -> f32 {
+    let arg1 = (arg + 0.5).fract();
+    (arg1 + arg1) - 1.0 - polyblep(arg1, increment)
+}
+
+#[inline(always)]
+pub fn square(arg: f32, increment: f32) -> f32 {
+    pulse(arg, increment, 0.5)
+}
+
+/// Rises from -1 to 1 over the first `skew` of the cycle and falls back over
+/// the rest; `skew` is kept away from 0 and 1.
+#[inline(always)]
+pub fn skewed_triangle(arg: f32, skew: f32) -> f32 {
+    let skew = skew.clamp(0.001, 0.999);
+    if arg < skew {
+        (arg + arg) / skew - 1.0
+    } else {
+        1.0 - 2.0 * (arg - skew) / (1.0 - skew)
+    }
+}
+
+/// Rising ramp from -1 to 1 that starts slowly and speeds up.
+#[inline(always)]
+pub fn exp_ramp(arg: f32) -> f32 {
+    const CURVE: f32 = 5.0;
+    const NORM: f32 = 2.0 / (32.0 - 1.0); // 2 / (2^CURVE - 1)
+    ((CURVE * arg).exp2() - 1.0).mul_add(NORM, -1.0)
+}
+
+/// Rising ramp from -1 to 1 that starts fast and slows down; `exp_ramp`
+/// mirrored.
+#[inline(always)]
+pub fn log_ramp(arg: f32) -> f32 {
+    -exp_ramp(1.0 - arg)
+}
+
+/// High for the first `width` of the cycle, low for the rest.
+#[inline(always)]
+pub fn pulse(arg: f32, increment: f32, width: f32) -> f32 {
+    let arg1 = (arg + 1.0 - width).fract();
+    let mut y: f32 = if arg < width { 1.0 } else { -1.0 };
+    y += polyblep(arg, increment) - polyblep(arg1, increment);
+    y
+}
+
+#[inline(always)]
+pub fn polyblep(arg: f32, increment: f32) -> f32 {
+    if arg < increment {
+        let t = arg / increment - 1.0;
+        return -(t * t);
+    }
+
+    if arg > 1.0 - increment {
+        let t = (arg - 1.0) / increment + 1.0;
+        return t * t;
+    }
+
+    0.0
+}
+
+#[inline(always)]
+pub fn polyblamp(arg: f32, increment: f32) -> f32 {
+    if arg < increment {
+        let t = arg / increment - 1.0;
+        return -ONE_THIRD * (t * t * t);
+    }
+
+    if arg > 1.0 - increment {
+        let t = (arg - 1.0) / increment + 1.0;
+        return ONE_THIRD * (t * t * t);
+    }
+
+    0.0
+}
@@ -0,0 +1,245 @@
+use rustfft::num_complex::Complex32;
+
+use super::fft_helper::FftHelper;
+
+/// Samples per frame of every mip level.
+pub const TABLE_SIZE: usize = 2048;
+pub const MAX_FRAMES: usize = 256;
+
+/// One level per octave, from `TABLE_SIZE / 2` harmonics down to a single one.
+const MIP_LEVELS: usize = 11;
+
+/// Single-cycle frames stored as octave-spaced, band-limited mip-maps.
+///
+/// A new table is built in the background, one frame per `build_step`, and
+/// replaces the playing one once complete. Its mip-maps are allocated by
+/// `load`, sized to its frame count, so the audio thread only fills them.
+pub struct Wavetable {
+    fft_helper: FftHelper,
+    frame_count: usize,
+    // [level][frame * TABLE_SIZE + n]
+    mip_maps: Vec<Vec<f32>>,
+    // the table being built, swapped with `mip_maps` once complete; holds
+    // the replaced table until the next `load`
+    next_mip_maps: Vec<Vec<f32>>,
+    pending: Option<PendingTable>,
+    spectrum: Vec<Complex32>,
+}
+
+/// A table waiting for its mip-maps to be built.
+struct PendingTable {
+    table: Vec<f32>,
+    frame_size: usize,
+    frame_fft: FftHelper,
+    frame_spectrum: Vec<Complex32>,
+    next_frame: usize,
+}
+
+impl Wavetable {
+    /// Starts out with a single sine frame.
+    pub fn new() -> Wavetable {
+        let mut wavetable = Wavetable {
+            fft_helper: FftHelper::new(TABLE_SIZE),
+            frame_count: 0,
+            mip_maps: Vec::new(),
+            next_mip_maps: Vec::new(),
+            pending: None,
+            spectrum: vec![Complex32::default(); TABLE_SIZE],
+        };
+        let sine: Vec<f32> = (0..TABLE_SIZE)
+            .map(|n| (std::f32::consts::TAU * n as f32 / TABLE_SIZE as f32).sin())
+            .collect();
+        wavetable.load(sine, TABLE_SIZE);
+        wavetable.finish_loading();
+        wavetable
+    }
+
+    /// Frames of the playing table.
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Queues `table`, cut into consecutive frames of `frame_size` samples,
+    /// to replace the frames; frames of any size are resampled to
+    /// `TABLE_SIZE` in the frequency domain. The DC offset is removed.
+    /// A table still being built is dropped. Returns `false` and keeps the
+    /// current frames if `table` does not hold between 1 and `MAX_FRAMES`
+    /// whole frames.
+    ///
+    /// Allocates the mip-maps of the new table, so call it from the message
+    /// handler rather than while rendering.
+    pub fn load(&mut self, table: Vec<f32>, frame_size: usize) -> bool {
+        if frame_size < 2 || table.is_empty() || !table.len().is_multiple_of(frame_size) {
+            return false;
+        }
+        let frame_count = table.len() / frame_size;
+        if frame_count > MAX_FRAMES {
+            return false;
+        }
+
+        self.next_mip_maps = vec![vec![0.0; frame_count * TABLE_SIZE]; MIP_LEVELS];
+
+        self.pending = Some(PendingTable {
+            table,
+            frame_size,
+            frame_fft: FftHelper::new(frame_size),
+            frame_spectrum: vec![Complex32::default(); frame_size],
+            next_frame: 0,
+        });
+        true
+    }
+
+    /// Builds the mip-maps of the next frame of the queued table, and swaps
+    /// the table in after its last frame.
+    pub fn build_step(&mut self) {
+        let pending = match &mut self.pending {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let frame_size = pending.frame_size;
+        let max_harmonic = (frame_size.min(TABLE_SIZE) / 2) - 1;
+        let frame = pending.next_frame;
+        let samples = &pending.table[frame * frame_size..(frame + 1) * frame_size];
+        FftHelper::real_to_complex(samples, &mut pending.frame_spectrum);
+        pending.frame_fft.fft(&mut pending.frame_spectrum, true);
+
+        let offset = frame * TABLE_SIZE;
+        for (level, mip_map) in self.next_mip_maps.iter_mut().enumerate() {
+            let harmonics = ((TABLE_SIZE / 2) >> level).min(max_harmonic);
+
+            self.spectrum.fill(Complex32::default());
+            for k in 1..=harmonics {
+                self.spectrum[k] = pending.frame_spectrum[k];
+                self.spectrum[TABLE_SIZE - k] = pending.frame_spectrum[frame_size - k];
+            }
+            self.fft_helper.inv_fft(&mut self.spectrum);
+            FftHelper::complex_to_real(&self.spectrum, &mut mip_map[offset..offset + TABLE_SIZE]);
+        }
+
+        pending.next_frame += 1;
+        if pending.next_frame * frame_size == pending.table.len() {
+            self.frame_count = pending.next_frame;
+            std::mem::swap(&mut self.mip_maps, &mut self.next_mip_maps);
+            self.pending = None;
+        }
+    }
+
+    /// Builds the rest of the queued table at once.
+    pub fn finish_loading(&mut self) {
+        while self.is_loading() {
+            self.build_step();
+        }
+    }
+
+    /// Reads phase `arg` in `[0, 1)` at morph `position` in `[0, 1]` across
+    /// the frames, from the mip level whose harmonics stay below Nyquist for
+    /// the given phase `increment`.
+    #[inline]
+    pub fn read(&self, arg: f32, position: f32, increment: f32) -> f32 {
+        let level = ((increment.abs() * TABLE_SIZE as f32).log2().ceil().max(0.0) as usize)
+            .min(MIP_LEVELS - 1);
+        let mip_map = &self.mip_maps[level];
+
+        let frame_pos = position.clamp(0.0, 1.0) * (self.frame_count - 1) as f32;
+        let frame1 = frame_pos as usize;
+        let frame2 = (frame1 + 1).min(self.frame_count - 1);
+        let frame_frac = frame_pos - frame1 as f32;
+
+        let index = arg * TABLE_SIZE as f32;
+        let index1 = (index as usize) % TABLE_SIZE;
+        let index2 = (index1 + 1) % TABLE_SIZE;
+        let index_frac = index - index.floor();
+
+        let read_frame = |frame: usize| {
+            let offset = frame * TABLE_SIZE;
+            let y1 = mip_map[offset + index1];
+            let y2 = mip_map[offset + index2];
+            y1 + index_frac * (y2 - y1)
+        };
+
+        let y1 = read_frame(frame1);
+        if frame_frac == 0.0 {
+            return y1;
+        }
+        y1 + frame_frac * (read_frame(frame2) - y1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_level_keeps_fundamental_only() {
+        // square wave: odd harmonics with amplitude 4 / (k * pi)
+        let square: Vec<f32> = (0..512).map(|n| if n < 256 { 1.0 } else { -1.0 }).collect();
+        let mut wavetable = Wavetable::new();
+        assert!(wavetable.load(square, 512));
+        wavetable.finish_loading();
+        assert_eq!(wavetable.frame_count(), 1);
+
+        let amplitude = 4.0 / std::f32::consts::PI;
+        for n in 0..64 {
+            let arg = n as f32 / 64.0;
+            let expected = amplitude * (std::f32::consts::TAU * arg).sin();
+            // an increment of 0.5 can only hold the fundamental
+            let y = wavetable.read(arg, 0.0, 0.5);
+            assert!((y - expected).abs() < 1e-2, "{}: {} vs {}", n, y, expected);
+        }
+    }
+
+    #[test]
+    fn test_rejects_partial_frames() {
+        let mut wavetable = Wavetable::new();
+        assert!(!wavetable.load(vec![0.0; 100], 64));
+        assert_eq!(wavetable.frame_count(), 1);
+    }
+
+    #[test]
+    fn test_swaps_in_after_last_frame() {
+        let mut wavetable = Wavetable::new();
+        assert!(wavetable.load(vec![0.0; 3 * 64], 64));
+        wavetable.build_step();
+        wavetable.build_step();
+        // the sine keeps playing until the new table is complete
+        assert_eq!(wavetable.frame_count(), 1);
+        assert!(wavetable.read(0.25, 0.0, 0.001) > 0.99);
+        wavetable.build_step();
+        assert!(!wavetable.is_loading());
+        assert_eq!(wavetable.frame_count(), 3);
+        assert_eq!(wavetable.read(0.25, 0.0, 0.001), 0.0);
+    }
+
+    #[test]
+    fn test_position_morphs_between_frames() {
+        // sine frames scaled by 1, 0 and -1
+        let table: Vec<f32> = [1.0, 0.0, -1.0]
+            .iter()
+            .flat_map(|gain| {
+                (0..256).map(move |n| gain * (std::f32::consts::TAU * n as f32 / 256.0).sin())
+            })
+            .collect();
+        let mut wavetable = Wavetable::new();
+        assert!(wavetable.load(table, 256));
+        wavetable.finish_loading();
+
+        for position in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let expected = 1.0 - 2.0 * position;
+            let y = wavetable.read(0.25, position, 0.001);
+            assert!(
+                (y - expected).abs() < 1e-3,
+                "{}: {} vs {}",
+                position,
+                y,
+                expected
+            );
+        }
+        // beyond the ends the outer frames are held
+        assert!((wavetable.read(0.25, 1.5, 0.001) + 1.0).abs() < 1e-3);
+    }
+}
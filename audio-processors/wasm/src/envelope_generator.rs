@@ -1,12 +1,15 @@
-use js_sys::Array;
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
-use web_sys::console::log;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
-use crate::dsp::analog_eg::AnalogEG;
+use crate::{block_param, dsp::analog_eg::AnalogEG, invalid_buffer_index, processor::Processor};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "attack", min = 0.0, max = 5000.0, default = 1.0)]
+#[param(name = "decay", min = 0.0, max = 5000.0, default = 1.0)]
+#[param(name = "sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "release", min = 0.0, max = 5000.0, default = 1.0)]
+#[param(name = "applyVelocity", min = 0.0, max = 1.0, default = 0.0)]
 pub struct EnvelopeGenerator {
     envelope_generator: AnalogEG,
     buffer_frame_length: usize,
@@ -45,12 +48,15 @@ impl EnvelopeGenerator {
 
     pub fn process(
         &mut self,
+        frames: usize,
         attack_msec: f32,
         decay_msec: f32,
         sustain_lvl: f32,
         release_msec: f32,
         apply_velocity: bool,
     ) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.envelope_generator.set_params(
             attack_msec,
             decay_msec,
@@ -59,15 +65,12 @@ impl EnvelopeGenerator {
             apply_velocity,
         );
 
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
             let flag = self.trigger_input_buffer[n];
             let retrigger = self.prev_retrig == 0.0 && self.retrigger_input_buffer[n] == 1.0;
             let velocity = self.velocity_input_buffer[n].clamp(0.0, 1.0);
 
             if (self.prev_flag == 0.0 && flag == 1.0) || (retrigger && self.note_on) {
-                let arr = Array::new();
-                arr.push(&velocity.into());
-                log(&arr);
                 self.envelope_generator.note_on(velocity);
                 self.note_on = true;
             } else if self.prev_flag == 1.0 && flag == 0.0 {
@@ -87,4 +90,58 @@ impl EnvelopeGenerator {
         self.prev_flag = 0.0;
         self.prev_retrig = 0.0;
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.envelope_generator.set_sample_rate(sample_rate);
+    }
+}
+
+impl Processor for EnvelopeGenerator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["trigger", "retrigger", "velocity"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.trigger_input_buffer,
+            1 => &mut self.retrigger_input_buffer,
+            2 => &mut self.velocity_input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        EnvelopeGenerator::process(
+            self,
+            frames,
+            block_param!(params, 0, "attack"),
+            block_param!(params, 1, "decay"),
+            block_param!(params, 2, "sustain"),
+            block_param!(params, 3, "release"),
+            block_param!(params, 4, "applyVelocity") != 0.0,
+        );
+    }
+
+    fn reset(&mut self) {
+        EnvelopeGenerator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        EnvelopeGenerator::reconfigure(self, sample_rate, channel_count);
+    }
 }
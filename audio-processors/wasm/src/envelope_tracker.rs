@@ -1,17 +1,22 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
+    block_param,
     dsp::{
         envelope_filter::{self, EnvelopeFilter},
         envelope_follower::EnvelopeFollower,
         smoothed_value::SmoothedValue,
     },
-    linear_smoothed_value, utils,
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
+    utils,
 };
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "sensitivity", min = 0.25, max = 5.0, default = 1.0)]
+#[param(name = "threshold", min = -60.0, max = 0.0, default = -60.0)]
 pub struct EnvelopeTracker {
     buffer_frame_length: usize,
 
@@ -63,11 +68,13 @@ impl EnvelopeTracker {
         }
     }
 
-    pub fn process(&mut self, sensitivity: f32, threshold: f32) {
+    pub fn process(&mut self, frames: usize, sensitivity: f32, threshold: f32) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.sensitivity.set_target_value(sensitivity);
         self.threshold.set_target_value(threshold);
 
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
             self.envelope_follower
                 .set_sensitivity(self.sensitivity.get_next_value());
             self.envelope_follower
@@ -94,4 +101,56 @@ impl EnvelopeTracker {
         self.threshold.reset();
         self.note_currently_on = false;
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.envelope_follower.set_sample_rate(sample_rate);
+        self.sensitivity.set_sample_rate(sample_rate);
+        self.threshold.set_sample_rate(sample_rate);
+    }
+}
+
+impl Processor for EnvelopeTracker {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["modulation", "trigger"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.modulation_output_buffer,
+            1 => &self.trigger_output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        EnvelopeTracker::process(
+            self,
+            frames,
+            block_param!(params, 0, "sensitivity"),
+            block_param!(params, 1, "threshold"),
+        );
+    }
+
+    fn reset(&mut self) {
+        EnvelopeTracker::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        EnvelopeTracker::reconfigure(self, sample_rate, channel_count);
+    }
 }
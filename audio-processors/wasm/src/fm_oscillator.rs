@@ -1,20 +1,26 @@
 use std::{collections::HashMap, f32::consts::TAU as TWO_PI};
 
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
+    block_param,
     dsp::{
         fast_math, phase_counter::PhaseCounter, smoothed_value::SmoothedValue,
         utils::make_pitch_factors,
     },
-    multiplicative_smoothed_value,
+    invalid_buffer_index, multiplicative_smoothed_value,
+    processor::Processor,
 };
 
 const FRAC_1_2PI: f32 = 1.0 / TWO_PI;
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.0, max = 4186.009, default = 0.0)]
+#[param(name = "pitchShift", min = -24, max = 24, default = 0)]
+#[param(name = "phaseShift", min = 0, max = 1, default = 0)]
+#[param(name = "fmIndex", min = 0.0, max = 10.0, default = 0.0)]
 pub struct FMOscillator {
     buffer_frame_length: usize,
     channel_count: usize,
@@ -29,8 +35,14 @@ pub struct FMOscillator {
     frequency_buffer: Vec<f32>,
     #[io_buffer]
     phase_shift_buffer: Vec<f32>,
+    #[io_buffer]
+    fm_index_buffer: Vec<f32>,
 
     // IO buffers
+    // linear FM: the frequency is scaled by `1 + fm_index * modulator` and
+    // runs backwards while that is negative (through-zero FM)
+    #[io_buffer]
+    modulator_buffer: Vec<f32>,
     #[io_buffer]
     output_buffer: Vec<f32>,
 }
@@ -50,17 +62,23 @@ impl FMOscillator {
 
             frequency_buffer: vec![0.0; buffer_frame_length],
             phase_shift_buffer: vec![0.0; buffer_frame_length],
+            fm_index_buffer: vec![0.0; buffer_frame_length],
+            modulator_buffer: vec![0.0; buffer_frame_length],
             output_buffer: vec![0.0; buffer_frame_length * channel_count],
         }
     }
 
-    pub fn process(&mut self, pitch_shift: i32) {
+    pub fn process(&mut self, frames: usize, pitch_shift: i32) {
+        let frames = frames.min(self.buffer_frame_length);
+
         let pitch_shift_factor = self.pitch_factors.get(&pitch_shift).unwrap_or(&1.0);
         self.pitch_factor.set_target_value(*pitch_shift_factor);
 
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
+            let modulation = self.fm_index_buffer[n].mul_add(self.modulator_buffer[n], 1.0);
             let increment = self.frequency_buffer[n]
                 * self.pitch_factor.get_next_value()
+                * modulation
                 * self.sample_rate_inv;
             let mut arg = self.phase_counter.advance(increment);
             arg = (arg + (self.phase_shift_buffer[n] * FRAC_1_2PI)).fract();
@@ -75,7 +93,7 @@ impl FMOscillator {
 
         for channel in 1..self.channel_count {
             let offset = channel * self.buffer_frame_length;
-            for n in 0..self.buffer_frame_length {
+            for n in 0..frames {
                 self.output_buffer[offset + n] = self.output_buffer[n];
             }
         }
@@ -84,4 +102,79 @@ impl FMOscillator {
     pub fn reset(&mut self) {
         self.phase_counter.reset();
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+        self.pitch_factor.set_sample_rate(sample_rate);
+
+        self.channel_count = channel_count;
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+impl Processor for FMOscillator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "phase_shift", "fm_index", "modulator"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.phase_shift_buffer,
+            2 => &mut self.fm_index_buffer,
+            3 => &mut self.modulator_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        FMOscillator::process(self, frames, block_param!(params, 0, "pitchShift") as i32);
+    }
+
+    fn reset(&mut self) {
+        FMOscillator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        FMOscillator::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_through_zero_fm_runs_backwards() {
+        let mut forward = FMOscillator::new(128, 48000.0, 1);
+        forward.frequency_buffer.fill(440.0);
+        forward.process(128, 0);
+
+        // a modulation factor of 1 - 2 = -1 mirrors the phase
+        let mut backward = FMOscillator::new(128, 48000.0, 1);
+        backward.frequency_buffer.fill(440.0);
+        backward.fm_index_buffer.fill(2.0);
+        backward.modulator_buffer.fill(-1.0);
+        backward.process(128, 0);
+
+        for (f, b) in forward.output_buffer.iter().zip(&backward.output_buffer) {
+            assert!((f + b).abs() < 1e-3, "{} vs {}", f, b);
+        }
+    }
 }
@@ -1,15 +1,806 @@
 use std::collections::HashMap;
 
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
-use crate::dsp::{
-    fm_voice::{self, OperatorParams},
-    utils::{make_fine_tune_factors, make_pitch_factors},
+use crate::{
+    block_param,
+    dsp::{
+        dx7::{self, Dx7Voice},
+        fm_voice::{
+            self, KeyScaling, KeyScalingCurve, OperatorEnvelope, OperatorLevels, OperatorParams,
+            VoiceModulation,
+        },
+        random::{derive_seed, DEFAULT_SEED},
+        utils::{make_fine_tune_factors, make_pitch_factors},
+        voice_allocator::{StealMode, VoiceAction, VoiceAllocator, VoiceMode},
+        voice_lfo::VoiceLfoWaveform,
+    },
+    invalid_buffer_index,
+    processor::Processor,
 };
 
+const INPUTS: [&str; 149] = [
+    "frequency",
+    "trigger",
+    "retrigger",
+    "op1_level",
+    "op2_level",
+    "op3_level",
+    "op4_level",
+    "op5_level",
+    "op6_level",
+    "op1_attack",
+    "op2_attack",
+    "op3_attack",
+    "op4_attack",
+    "op5_attack",
+    "op6_attack",
+    "op1_decay",
+    "op2_decay",
+    "op3_decay",
+    "op4_decay",
+    "op5_decay",
+    "op6_decay",
+    "op1_sustain",
+    "op2_sustain",
+    "op3_sustain",
+    "op4_sustain",
+    "op5_sustain",
+    "op6_sustain",
+    "op1_release",
+    "op2_release",
+    "op3_release",
+    "op4_release",
+    "op5_release",
+    "op6_release",
+    "velocity",
+    "op1_velocity_sensitivity",
+    "op2_velocity_sensitivity",
+    "op3_velocity_sensitivity",
+    "op4_velocity_sensitivity",
+    "op5_velocity_sensitivity",
+    "op6_velocity_sensitivity",
+    "op1_key_breakpoint",
+    "op2_key_breakpoint",
+    "op3_key_breakpoint",
+    "op4_key_breakpoint",
+    "op5_key_breakpoint",
+    "op6_key_breakpoint",
+    "op1_key_left_depth",
+    "op2_key_left_depth",
+    "op3_key_left_depth",
+    "op4_key_left_depth",
+    "op5_key_left_depth",
+    "op6_key_left_depth",
+    "op1_key_right_depth",
+    "op2_key_right_depth",
+    "op3_key_right_depth",
+    "op4_key_right_depth",
+    "op5_key_right_depth",
+    "op6_key_right_depth",
+    "op1_key_left_curve",
+    "op2_key_left_curve",
+    "op3_key_left_curve",
+    "op4_key_left_curve",
+    "op5_key_left_curve",
+    "op6_key_left_curve",
+    "op1_key_right_curve",
+    "op2_key_right_curve",
+    "op3_key_right_curve",
+    "op4_key_right_curve",
+    "op5_key_right_curve",
+    "op6_key_right_curve",
+    "op1_key_rate_scaling",
+    "op2_key_rate_scaling",
+    "op3_key_rate_scaling",
+    "op4_key_rate_scaling",
+    "op5_key_rate_scaling",
+    "op6_key_rate_scaling",
+    "op1_eg_rate1",
+    "op1_eg_rate2",
+    "op1_eg_rate3",
+    "op1_eg_rate4",
+    "op2_eg_rate1",
+    "op2_eg_rate2",
+    "op2_eg_rate3",
+    "op2_eg_rate4",
+    "op3_eg_rate1",
+    "op3_eg_rate2",
+    "op3_eg_rate3",
+    "op3_eg_rate4",
+    "op4_eg_rate1",
+    "op4_eg_rate2",
+    "op4_eg_rate3",
+    "op4_eg_rate4",
+    "op5_eg_rate1",
+    "op5_eg_rate2",
+    "op5_eg_rate3",
+    "op5_eg_rate4",
+    "op6_eg_rate1",
+    "op6_eg_rate2",
+    "op6_eg_rate3",
+    "op6_eg_rate4",
+    "op1_eg_level1",
+    "op1_eg_level2",
+    "op1_eg_level3",
+    "op1_eg_level4",
+    "op2_eg_level1",
+    "op2_eg_level2",
+    "op2_eg_level3",
+    "op2_eg_level4",
+    "op3_eg_level1",
+    "op3_eg_level2",
+    "op3_eg_level3",
+    "op3_eg_level4",
+    "op4_eg_level1",
+    "op4_eg_level2",
+    "op4_eg_level3",
+    "op4_eg_level4",
+    "op5_eg_level1",
+    "op5_eg_level2",
+    "op5_eg_level3",
+    "op5_eg_level4",
+    "op6_eg_level1",
+    "op6_eg_level2",
+    "op6_eg_level3",
+    "op6_eg_level4",
+    "pitch_eg_rate1",
+    "pitch_eg_rate2",
+    "pitch_eg_rate3",
+    "pitch_eg_rate4",
+    "pitch_eg_level1",
+    "pitch_eg_level2",
+    "pitch_eg_level3",
+    "pitch_eg_level4",
+    "lfo_speed",
+    "lfo_delay",
+    "lfo_fade",
+    "lfo_pitch_mod_depth",
+    "lfo_amp_mod_depth",
+    "op1_amp_mod_sensitivity",
+    "op2_amp_mod_sensitivity",
+    "op3_amp_mod_sensitivity",
+    "op4_amp_mod_sensitivity",
+    "op5_amp_mod_sensitivity",
+    "op6_amp_mod_sensitivity",
+    "op1_fixed_frequency",
+    "op2_fixed_frequency",
+    "op3_fixed_frequency",
+    "op4_fixed_frequency",
+    "op5_fixed_frequency",
+    "op6_fixed_frequency",
+];
+
+const VOICES: usize = 8;
+// note events queued for the next blocks; more are dropped, see
+// `push_note_event`
+const MAX_NOTE_EVENTS: usize = 64;
+// how quickly a stolen voice is faded out before its new note starts
+const STEAL_FADE_SECONDS: f32 = 0.005;
+
+#[derive(Copy, Clone)]
+enum NoteEventKind {
+    On { note: u8, velocity: f32 },
+    Off { note: u8 },
+    AllOff,
+}
+
+#[derive(Copy, Clone)]
+struct NoteEvent {
+    // in samples from the start of the next block
+    offset: usize,
+    kind: NoteEventKind,
+}
+
+#[derive(Copy, Clone)]
+struct EventNote {
+    key: f32,
+    frequency: f32,
+    velocity: f32,
+}
+
+impl EventNote {
+    fn new(note: u8, velocity: f32) -> EventNote {
+        EventNote {
+            key: note as f32,
+            frequency: 440.0 * ((note as f32 - 69.0) / 12.0).exp2(),
+            velocity,
+        }
+    }
+}
+
+/// MIDI note number of `frequency`, for the keyboard scaling of the lanes.
+fn frequency_to_note(frequency: f32) -> f32 {
+    (69.0 + 12.0 * (frequency / 440.0).log2()).clamp(0.0, 127.0)
+}
+
+/// State of a voice played through the note event API.
+#[derive(Copy, Clone)]
+struct EventVoice {
+    note: Option<EventNote>,
+    // set while the voice fades out to make way for `pending`
+    fading: bool,
+    fade_gain: f32,
+    pending: Option<EventNote>,
+    // the pending note was released before it could start
+    pending_released: bool,
+}
+
+impl Default for EventVoice {
+    fn default() -> Self {
+        EventVoice {
+            note: None,
+            fading: false,
+            fade_gain: 1.0,
+            pending: None,
+            pending_released: false,
+        }
+    }
+}
+
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "algorithm", min = 1, max = 32, default = 1)]
+#[param(name = "op1Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op1PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op1FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op1FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op1Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op1Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op1Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op1Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op1VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op1KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op1EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op1EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op1AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op2Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op2PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op2FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op2FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op2Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op2Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op2Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op2Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op2VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op2KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op2EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op2EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op2AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op3Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op3PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op3FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op3FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op3Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op3Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op3Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op3Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op3VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op3KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op3EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op3EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op3AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op4Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op4PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op4FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op4FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op4Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op4Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op4Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op4Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op4VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op4KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op4EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op4EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op4AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op5Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op5PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op5FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op5FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op5Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op5Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op5Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op5Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op5VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op5KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op5EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op5EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op5AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op6Level", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op6PitchShift", min = -24, max = 72, default = 0)]
+#[param(name = "op6FineTune", min = -100, max = 100, default = 0)]
+#[param(
+    name = "op6FixedFrequency",
+    min = 0,
+    max = 10000,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "op6Attack", min = 0.0, max = 2.0, default = 0.01)]
+#[param(name = "op6Decay", min = 0.0, max = 2.0, default = 0.0)]
+#[param(name = "op6Sustain", min = 0.0, max = 1.0, default = 1.0)]
+#[param(name = "op6Release", min = 0.001, max = 2.0, default = 0.1)]
+#[param(
+    name = "op6VelocitySensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyBreakpoint",
+    min = 0,
+    max = 127,
+    default = 60,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyLeftDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyRightDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyLeftCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyRightCurve",
+    min = 0,
+    max = 3,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "op6KeyRateScaling",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "op6EgRate1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgRate2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgRate3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgRate4", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgLevel1", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgLevel2", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgLevel3", min = 0, max = 99, default = 99, rate = "k-rate")]
+#[param(name = "op6EgLevel4", min = 0, max = 99, default = 0, rate = "k-rate")]
+#[param(
+    name = "op6AmpModSensitivity",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "voiceMode", min = 0, max = 2, default = 0, rate = "k-rate")]
+#[param(name = "stealMode", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(name = "feedback", min = 0, max = 7, default = 5.3485, rate = "k-rate")]
+#[param(name = "envelopeMode", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(
+    name = "pitchEgRate1",
+    min = 0,
+    max = 99,
+    default = 99,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgRate2",
+    min = 0,
+    max = 99,
+    default = 99,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgRate3",
+    min = 0,
+    max = 99,
+    default = 99,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgRate4",
+    min = 0,
+    max = 99,
+    default = 99,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgLevel1",
+    min = 0,
+    max = 99,
+    default = 50,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgLevel2",
+    min = 0,
+    max = 99,
+    default = 50,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgLevel3",
+    min = 0,
+    max = 99,
+    default = 50,
+    rate = "k-rate"
+)]
+#[param(
+    name = "pitchEgLevel4",
+    min = 0,
+    max = 99,
+    default = 50,
+    rate = "k-rate"
+)]
+#[param(
+    name = "lfoSpeed",
+    min = 0.0,
+    max = 50.0,
+    default = 5.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "lfoDelay",
+    min = 0.0,
+    max = 5.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "lfoFade", min = 0.0, max = 5.0, default = 0.0, rate = "k-rate")]
+#[param(
+    name = "lfoPitchModDepth",
+    min = 0.0,
+    max = 12.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(
+    name = "lfoAmpModDepth",
+    min = 0.0,
+    max = 1.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "lfoWaveform", min = 0, max = 5, default = 0, rate = "k-rate")]
+#[param(name = "lfoKeySync", min = 0, max = 1, default = 1, rate = "k-rate")]
 pub struct FMSynthesizer {
     buffer_frame_length: usize,
     channel_count: usize,
@@ -19,12 +810,63 @@ pub struct FMSynthesizer {
 
     voices: [fm_voice::FMVoice; 8],
 
-    // parameter buffers
+    // note event API: voices driven by events ignore their input lanes, and
+    // voices held by a lane are reserved in the allocator
+    allocator: VoiceAllocator,
+    note_events: Vec<NoteEvent>,
+    event_voices: [EventVoice; VOICES],
+    steal_fade_step: f32,
+
+    // parameter buffers: level and ADSR are a-rate, the rest of the patch is
+    // k-rate and only read from the first frame of a block
+    #[io_buffer]
     operator_level_buffers: [Vec<f32>; 6],
+    #[io_buffer]
     operator_attack_buffers: [Vec<f32>; 6],
+    #[io_buffer]
     operator_decay_buffers: [Vec<f32>; 6],
+    #[io_buffer]
     operator_sustain_buffers: [Vec<f32>; 6],
+    #[io_buffer]
     operator_release_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_velocity_sensitivity_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_breakpoint_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_left_depth_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_right_depth_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_left_curve_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_right_curve_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    operator_key_rate_scaling_buffers: [Vec<f32>; 6],
+    // the four rates and levels of each operator: operator * 4 + stage
+    #[io_buffer]
+    operator_eg_rate_buffers: [Vec<f32>; 24],
+    #[io_buffer]
+    operator_eg_level_buffers: [Vec<f32>; 24],
+    #[io_buffer]
+    operator_amp_mod_sensitivity_buffers: [Vec<f32>; 6],
+    // Hz of fixed frequency operators, 0 follows the keyboard
+    #[io_buffer]
+    operator_fixed_frequency_buffers: [Vec<f32>; 6],
+    #[io_buffer]
+    pitch_eg_rate_buffers: [Vec<f32>; 4],
+    #[io_buffer]
+    pitch_eg_level_buffers: [Vec<f32>; 4],
+    #[io_buffer]
+    lfo_speed_buffer: Vec<f32>,
+    #[io_buffer]
+    lfo_delay_buffer: Vec<f32>,
+    #[io_buffer]
+    lfo_fade_buffer: Vec<f32>,
+    #[io_buffer]
+    lfo_pitch_mod_depth_buffer: Vec<f32>,
+    #[io_buffer]
+    lfo_amp_mod_depth_buffer: Vec<f32>,
 
     // gate/trigger state for each voice
     note_on: [bool; 8],
@@ -38,6 +880,9 @@ pub struct FMSynthesizer {
     trigger_input_buffer: Vec<f32>,
     #[io_buffer]
     retrigger_input_buffer: Vec<f32>,
+    // velocity (0 to 1) of each voice, read when it is triggered
+    #[io_buffer]
+    velocity_input_buffer: Vec<f32>,
     #[io_buffer]
     output_buffer: Vec<f32>,
 }
@@ -50,11 +895,11 @@ impl FMSynthesizer {
         sample_rate: f32,
         channel_count: usize,
     ) -> FMSynthesizer {
-        FMSynthesizer {
+        let mut synth = FMSynthesizer {
             buffer_frame_length,
             channel_count,
 
-            pitch_factors: make_pitch_factors(2),
+            pitch_factors: make_pitch_factors(6),
             fine_tune_factors: make_fine_tune_factors(),
 
             voices: [
@@ -68,6 +913,11 @@ impl FMSynthesizer {
                 fm_voice::FMVoice::new(sample_rate),
             ],
 
+            allocator: VoiceAllocator::new(VOICES),
+            note_events: Vec::with_capacity(MAX_NOTE_EVENTS),
+            event_voices: [EventVoice::default(); VOICES],
+            steal_fade_step: 1.0 / (STEAL_FADE_SECONDS * sample_rate),
+
             operator_level_buffers: [
                 vec![1.0; buffer_frame_length],
                 vec![1.0; buffer_frame_length],
@@ -108,6 +958,46 @@ impl FMSynthesizer {
                 vec![0.1; buffer_frame_length],
                 vec![0.1; buffer_frame_length],
             ],
+            operator_velocity_sensitivity_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_key_breakpoint_buffers: std::array::from_fn(|_| {
+                vec![60.0; buffer_frame_length]
+            }),
+            operator_key_left_depth_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_key_right_depth_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_key_left_curve_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_key_right_curve_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_key_rate_scaling_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_eg_rate_buffers: std::array::from_fn(|_| vec![99.0; buffer_frame_length]),
+            // level 4 is 0 for every operator
+            operator_eg_level_buffers: std::array::from_fn(|index| {
+                vec![if index % 4 == 3 { 0.0 } else { 99.0 }; buffer_frame_length]
+            }),
+            operator_amp_mod_sensitivity_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            operator_fixed_frequency_buffers: std::array::from_fn(|_| {
+                vec![0.0; buffer_frame_length]
+            }),
+            pitch_eg_rate_buffers: std::array::from_fn(|_| vec![99.0; buffer_frame_length]),
+            // level 50 leaves the pitch alone
+            pitch_eg_level_buffers: std::array::from_fn(|_| vec![50.0; buffer_frame_length]),
+            lfo_speed_buffer: vec![5.0; buffer_frame_length],
+            lfo_delay_buffer: vec![0.0; buffer_frame_length],
+            lfo_fade_buffer: vec![0.0; buffer_frame_length],
+            lfo_pitch_mod_depth_buffer: vec![0.0; buffer_frame_length],
+            lfo_amp_mod_depth_buffer: vec![0.0; buffer_frame_length],
 
             note_on: [false; 8],
             prev_flag: [0.0; 8],
@@ -116,32 +1006,17 @@ impl FMSynthesizer {
             frequency_input_buffer: vec![0.0; buffer_frame_length * 8],
             trigger_input_buffer: vec![0.0; buffer_frame_length * 8],
             retrigger_input_buffer: vec![0.0; buffer_frame_length * 8],
+            velocity_input_buffer: vec![1.0; buffer_frame_length * 8],
             output_buffer: vec![0.0; buffer_frame_length * channel_count],
-        }
-    }
-
-    pub fn operator_level_ptr(&mut self, operator: usize) -> *mut f32 {
-        &mut self.operator_level_buffers[operator][0]
-    }
-
-    pub fn operator_attack_ptr(&mut self, operator: usize) -> *mut f32 {
-        &mut self.operator_attack_buffers[operator][0]
-    }
-
-    pub fn operator_decay_ptr(&mut self, operator: usize) -> *mut f32 {
-        &mut self.operator_decay_buffers[operator][0]
-    }
-
-    pub fn operator_sustain_ptr(&mut self, operator: usize) -> *mut f32 {
-        &mut self.operator_sustain_buffers[operator][0]
-    }
-
-    pub fn operator_release_ptr(&mut self, operator: usize) -> *mut f32 {
-        &mut self.operator_release_buffers[operator][0]
+        };
+        synth.set_seed(DEFAULT_SEED);
+        synth
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn process(
         &mut self,
+        frames: usize,
         algorithm: i32,
         op1_pitch_shift: i32,
         op2_pitch_shift: i32,
@@ -155,7 +1030,25 @@ impl FMSynthesizer {
         op4_fine_tune: i32,
         op5_fine_tune: i32,
         op6_fine_tune: i32,
+        voice_mode: u8,
+        steal_mode: u8,
+        feedback: f32,
+        envelope_mode: u8,
+        lfo_waveform: u8,
+        lfo_key_sync: u8,
     ) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let voice_mode = VoiceMode::from(voice_mode);
+        if voice_mode != self.allocator.mode() {
+            self.release_event_voices();
+        }
+        self.allocator
+            .set_mode(voice_mode, StealMode::from(steal_mode));
+        let mut next_event = 0;
+        let envelope = OperatorEnvelope::from(envelope_mode);
+        let lfo_waveform = VoiceLfoWaveform::from(lfo_waveform);
+
         let op_1_pitch_factor = *self.pitch_factors.get(&op1_pitch_shift).unwrap_or(&1.0);
         let op_1_fine_tune_factor = *self.fine_tune_factors.get(&op1_fine_tune).unwrap_or(&1.0);
         let op_1_freq_mod = op_1_pitch_factor * op_1_fine_tune_factor;
@@ -180,69 +1073,52 @@ impl FMSynthesizer {
         let op_6_fine_tune_factor = *self.fine_tune_factors.get(&op6_fine_tune).unwrap_or(&1.0);
         let op_6_freq_mod = op_6_pitch_factor * op_6_fine_tune_factor;
 
-        for n in 0..self.buffer_frame_length {
+        // the patch is set once per block, only the levels follow the
+        // a-rate buffers
+        let op1_params = self.operator_params(0, op_1_freq_mod, envelope);
+        let op2_params = self.operator_params(1, op_2_freq_mod, envelope);
+        let op3_params = self.operator_params(2, op_3_freq_mod, envelope);
+        let op4_params = self.operator_params(3, op_4_freq_mod, envelope);
+        let op5_params = self.operator_params(4, op_5_freq_mod, envelope);
+        let op6_params = self.operator_params(5, op_6_freq_mod, envelope);
+        let modulation = VoiceModulation {
+            pitch_eg_rates: [0, 1, 2, 3].map(|index| self.pitch_eg_rate_buffers[index][0]),
+            pitch_eg_levels: [0, 1, 2, 3].map(|index| self.pitch_eg_level_buffers[index][0]),
+            lfo_waveform,
+            lfo_frequency: self.lfo_speed_buffer[0],
+            lfo_delay: self.lfo_delay_buffer[0],
+            lfo_fade: self.lfo_fade_buffer[0],
+            lfo_key_sync: lfo_key_sync != 0,
+            lfo_pitch_depth: self.lfo_pitch_mod_depth_buffer[0],
+            lfo_amp_depth: self.lfo_amp_mod_depth_buffer[0],
+        };
+        for voice in self.voices.iter_mut() {
+            voice.set_algorithm(algorithm.into());
+            voice.set_feedback(feedback);
+            voice.set_modulation(&modulation);
+            voice.set_parameters([
+                &op1_params,
+                &op2_params,
+                &op3_params,
+                &op4_params,
+                &op5_params,
+                &op6_params,
+            ]);
+        }
+
+        for n in 0..frames {
+            while next_event < self.note_events.len() && self.note_events[next_event].offset <= n {
+                let event = self.note_events[next_event];
+                self.apply_note_event(event.kind);
+                next_event += 1;
+            }
+
             self.output_buffer[n] = 0.0;
             // Process each voice
-            let op1_params = OperatorParams {
-                freq_mod: op_1_freq_mod,
-                level: self.operator_level_buffers[0][n],
-                env_attack: self.operator_attack_buffers[0][n],
-                env_decay: self.operator_decay_buffers[0][n],
-                env_sustain: self.operator_sustain_buffers[0][n],
-                env_release: self.operator_release_buffers[0][n],
-            };
-            let op2_params = OperatorParams {
-                freq_mod: op_2_freq_mod,
-                level: self.operator_level_buffers[1][n],
-                env_attack: self.operator_attack_buffers[1][n],
-                env_decay: self.operator_decay_buffers[1][n],
-                env_sustain: self.operator_sustain_buffers[1][n],
-                env_release: self.operator_release_buffers[1][n],
-            };
-            let op3_params = OperatorParams {
-                freq_mod: op_3_freq_mod,
-                level: self.operator_level_buffers[2][n],
-                env_attack: self.operator_attack_buffers[2][n],
-                env_decay: self.operator_decay_buffers[2][n],
-                env_sustain: self.operator_sustain_buffers[2][n],
-                env_release: self.operator_release_buffers[2][n],
-            };
-            let op4_params = OperatorParams {
-                freq_mod: op_4_freq_mod,
-                level: self.operator_level_buffers[3][n],
-                env_attack: self.operator_attack_buffers[3][n],
-                env_decay: self.operator_decay_buffers[3][n],
-                env_sustain: self.operator_sustain_buffers[3][n],
-                env_release: self.operator_release_buffers[3][n],
-            };
-            let op5_params = OperatorParams {
-                freq_mod: op_5_freq_mod,
-                level: self.operator_level_buffers[4][n],
-                env_attack: self.operator_attack_buffers[4][n],
-                env_decay: self.operator_decay_buffers[4][n],
-                env_sustain: self.operator_sustain_buffers[4][n],
-                env_release: self.operator_release_buffers[4][n],
-            };
-            let op6_params = OperatorParams {
-                freq_mod: op_6_freq_mod,
-                level: self.operator_level_buffers[5][n],
-                env_attack: self.operator_attack_buffers[5][n],
-                env_decay: self.operator_decay_buffers[5][n],
-                env_sustain: self.operator_sustain_buffers[5][n],
-                env_release: self.operator_release_buffers[5][n],
-            };
+            let levels = [0, 1, 2, 3, 4, 5].map(|op| self.operator_levels(op, n));
             let mut voice_offset = 0;
             for voice_idx in 0..8 {
-                self.voices[voice_idx].set_algorithm(algorithm.into());
-
-                self.voices[voice_idx].set_parameters([
-                    &op1_params,
-                    &op2_params,
-                    &op3_params,
-                    &op4_params,
-                    &op5_params,
-                    &op6_params,
-                ]);
+                self.voices[voice_idx].set_levels(&levels);
 
                 let sample_index = voice_offset + n;
                 let flag = self.trigger_input_buffer[sample_index];
@@ -251,37 +1127,623 @@ impl FMSynthesizer {
                 if (self.prev_flag[voice_idx] == 0.0 && flag == 1.0)
                     || (retrigger && self.note_on[voice_idx])
                 {
-                    self.voices[voice_idx].note_on();
+                    // the lanes take the voice back from the note events
+                    self.event_voices[voice_idx] = EventVoice::default();
+                    self.allocator.set_reserved(voice_idx, true);
+                    self.voices[voice_idx].note_on(
+                        frequency_to_note(self.frequency_input_buffer[sample_index]),
+                        self.velocity_input_buffer[sample_index],
+                    );
                     self.note_on[voice_idx] = true;
                 } else if self.prev_flag[voice_idx] == 1.0 && flag == 0.0 {
                     self.voices[voice_idx].note_off();
                     self.note_on[voice_idx] = false;
+                    self.allocator.set_reserved(voice_idx, false);
                 }
                 self.prev_flag[voice_idx] = flag;
                 self.prev_retrig[voice_idx] = self.retrigger_input_buffer[sample_index];
 
-                let voice_output =
-                    self.voices[voice_idx].process(self.frequency_input_buffer[sample_index]);
+                let event_voice = &mut self.event_voices[voice_idx];
+                let voice_output = event_voice.fade_gain
+                    * self.voices[voice_idx].process(match event_voice.note {
+                        Some(note) => note.frequency,
+                        None => self.frequency_input_buffer[sample_index],
+                    });
+                if event_voice.fading {
+                    event_voice.fade_gain -= self.steal_fade_step;
+                    if event_voice.fade_gain <= 0.0 {
+                        let EventVoice {
+                            pending,
+                            pending_released,
+                            ..
+                        } = *event_voice;
+                        self.voices[voice_idx].reset();
+                        *event_voice = EventVoice {
+                            note: pending,
+                            ..EventVoice::default()
+                        };
+                        if let Some(note) = pending {
+                            self.voices[voice_idx].note_on(note.key, note.velocity);
+                            if pending_released {
+                                self.voices[voice_idx].note_off();
+                            }
+                        }
+                    }
+                }
                 self.output_buffer[n] += voice_output;
                 voice_offset += self.buffer_frame_length;
             }
         }
 
+        self.note_events.drain(..next_event);
+        for event in self.note_events.iter_mut() {
+            event.offset -= frames;
+        }
+
         let mut offset = self.buffer_frame_length;
         for _channel in 1..self.channel_count {
-            for n in 0..self.buffer_frame_length {
+            for n in 0..frames {
                 self.output_buffer[offset + n] = self.output_buffer[n];
             }
             offset += self.buffer_frame_length;
         }
     }
 
+    /// Plays `note` (MIDI note number) `offset` samples into the next
+    /// block, on a voice of the synthesizer's choosing. A `velocity` of 0
+    /// releases the note.
+    pub fn note_on(&mut self, note: u8, velocity: f32, offset: usize) {
+        let velocity = velocity.clamp(0.0, 1.0);
+        self.push_note_event(offset, NoteEventKind::On { note, velocity });
+    }
+
+    pub fn note_off(&mut self, note: u8, offset: usize) {
+        self.push_note_event(offset, NoteEventKind::Off { note });
+    }
+
+    /// Releases every voice started by `note_on`.
+    pub fn all_notes_off(&mut self, offset: usize) {
+        self.push_note_event(offset, NoteEventKind::AllOff);
+    }
+
     pub fn reset(&mut self) {
+        self.allocator.reset();
+        self.note_events.clear();
+        self.event_voices = [EventVoice::default(); VOICES];
         for voice_idx in 0..8 {
+            self.allocator.set_reserved(voice_idx, false);
             self.note_on[voice_idx] = false;
             self.prev_flag[voice_idx] = 0.0;
             self.prev_retrig[voice_idx] = 0.0;
             self.voices[voice_idx].reset();
         }
     }
+
+    /// Restarts the sample-and-hold LFOs from `seed`, each voice on a
+    /// sequence of its own.
+    pub fn set_seed(&mut self, seed: u32) {
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.set_seed(derive_seed(seed, index as u32));
+        }
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        for voice in self.voices.iter_mut() {
+            voice.set_sample_rate(sample_rate);
+        }
+        self.steal_fade_step = 1.0 / (STEAL_FADE_SECONDS * sample_rate);
+
+        self.channel_count = channel_count;
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+/// Parses a DX7 single voice or 32 voice bank sysex dump into a JSON array
+/// of presets: `{ name, params }`, `params` holding the values of the
+/// `FMSynthesizer` parameters.
+#[wasm_bindgen]
+pub fn parse_dx7_sysex(data: &[u8]) -> Result<String, JsValue> {
+    let voices = dx7::parse_sysex(data).map_err(|error| JsValue::from(error.to_string()))?;
+    let presets: Vec<String> = voices.iter().map(dx7_preset_json).collect();
+    Ok(format!("[{}]", presets.join(",")))
+}
+
+fn dx7_preset_json(voice: &Dx7Voice) -> String {
+    let mut params = vec![
+        format!("\"algorithm\":{}", voice.algorithm),
+        format!("\"feedback\":{}", voice.feedback),
+        format!("\"envelopeMode\":{}", OperatorEnvelope::RateLevel as u8),
+    ];
+    let modulation = voice.modulation();
+    for stage in 0..4 {
+        let (rate, level) = (
+            modulation.pitch_eg_rates[stage],
+            modulation.pitch_eg_levels[stage],
+        );
+        params.push(format!("\"pitchEgRate{}\":{}", stage + 1, rate));
+        params.push(format!("\"pitchEgLevel{}\":{}", stage + 1, level));
+    }
+    params.push(format!("\"lfoWaveform\":{}", modulation.lfo_waveform as u8));
+    params.push(format!("\"lfoSpeed\":{}", modulation.lfo_frequency));
+    params.push(format!("\"lfoDelay\":{}", modulation.lfo_delay));
+    params.push(format!("\"lfoFade\":{}", modulation.lfo_fade));
+    params.push(format!("\"lfoKeySync\":{}", modulation.lfo_key_sync as u8));
+    params.push(format!(
+        "\"lfoPitchModDepth\":{}",
+        modulation.lfo_pitch_depth
+    ));
+    params.push(format!("\"lfoAmpModDepth\":{}", modulation.lfo_amp_depth));
+    for (index, operator) in voice.operator_params().iter().enumerate() {
+        // the transposition moves every operator alike, except for those at
+        // a fixed frequency
+        let cents = match operator.fixed_frequency {
+            Some(_) => 0.0,
+            None => 1200.0 * operator.freq_mod.log2() + 100.0 * voice.transpose as f32,
+        };
+        let pitch_shift = (cents / 100.0).round().clamp(-24.0, 72.0);
+        let fine_tune = (cents - 100.0 * pitch_shift).round().clamp(-100.0, 100.0);
+        let op = index + 1;
+        params.push(format!("\"op{}PitchShift\":{}", op, pitch_shift));
+        params.push(format!("\"op{}FineTune\":{}", op, fine_tune));
+        params.push(format!(
+            "\"op{}FixedFrequency\":{}",
+            op,
+            operator.fixed_frequency.unwrap_or(0.0)
+        ));
+        params.push(format!("\"op{}Level\":{}", op, operator.level));
+        params.push(format!("\"op{}Attack\":{}", op, operator.env_attack));
+        params.push(format!("\"op{}Decay\":{}", op, operator.env_decay));
+        params.push(format!("\"op{}Sustain\":{}", op, operator.env_sustain));
+        params.push(format!("\"op{}Release\":{}", op, operator.env_release));
+        params.push(format!(
+            "\"op{}VelocitySensitivity\":{}",
+            op, operator.velocity_sensitivity
+        ));
+        for stage in 0..4 {
+            let (rate, level) = (operator.eg_rates[stage], operator.eg_levels[stage]);
+            params.push(format!("\"op{}EgRate{}\":{}", op, stage + 1, rate));
+            params.push(format!("\"op{}EgLevel{}\":{}", op, stage + 1, level));
+        }
+        let key_scaling = &operator.key_scaling;
+        params.push(format!(
+            "\"op{}KeyBreakpoint\":{}",
+            op, key_scaling.breakpoint
+        ));
+        params.push(format!(
+            "\"op{}KeyLeftDepth\":{}",
+            op, key_scaling.left_depth
+        ));
+        params.push(format!(
+            "\"op{}KeyRightDepth\":{}",
+            op, key_scaling.right_depth
+        ));
+        params.push(format!(
+            "\"op{}KeyLeftCurve\":{}",
+            op, key_scaling.left_curve as u8
+        ));
+        params.push(format!(
+            "\"op{}KeyRightCurve\":{}",
+            op, key_scaling.right_curve as u8
+        ));
+        params.push(format!(
+            "\"op{}KeyRateScaling\":{}",
+            op, key_scaling.rate_scaling
+        ));
+        params.push(format!(
+            "\"op{}AmpModSensitivity\":{}",
+            op, operator.amp_mod_sensitivity
+        ));
+    }
+    format!(
+        "{{\"name\":{},\"params\":{{{}}}}}",
+        json_string(&voice.name),
+        params.join(",")
+    )
+}
+
+/// `value` as a quoted JSON string.
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl FMSynthesizer {
+    /// The patch of operator `op`, from the first frame of the k-rate
+    /// buffers.
+    fn operator_params(
+        &self,
+        op: usize,
+        freq_mod: f32,
+        envelope: OperatorEnvelope,
+    ) -> OperatorParams {
+        let levels = self.operator_levels(op, 0);
+        let stage = |buffers: &[Vec<f32>; 24], index: usize| buffers[op * 4 + index][0];
+        OperatorParams {
+            freq_mod,
+            fixed_frequency: Some(self.operator_fixed_frequency_buffers[op][0])
+                .filter(|hz| *hz > 0.0),
+            level: levels.level,
+            env_attack: levels.env_attack,
+            env_decay: levels.env_decay,
+            env_sustain: levels.env_sustain,
+            env_release: levels.env_release,
+            envelope,
+            eg_rates: [0, 1, 2, 3].map(|index| stage(&self.operator_eg_rate_buffers, index)),
+            eg_levels: [0, 1, 2, 3].map(|index| stage(&self.operator_eg_level_buffers, index)),
+            velocity_sensitivity: self.operator_velocity_sensitivity_buffers[op][0],
+            key_scaling: KeyScaling {
+                breakpoint: self.operator_key_breakpoint_buffers[op][0],
+                left_depth: self.operator_key_left_depth_buffers[op][0],
+                right_depth: self.operator_key_right_depth_buffers[op][0],
+                left_curve: KeyScalingCurve::from(
+                    self.operator_key_left_curve_buffers[op][0] as u8,
+                ),
+                right_curve: KeyScalingCurve::from(
+                    self.operator_key_right_curve_buffers[op][0] as u8,
+                ),
+                rate_scaling: self.operator_key_rate_scaling_buffers[op][0],
+            },
+            amp_mod_sensitivity: self.operator_amp_mod_sensitivity_buffers[op][0],
+        }
+    }
+
+    fn operator_levels(&self, op: usize, n: usize) -> OperatorLevels {
+        OperatorLevels {
+            level: self.operator_level_buffers[op][n],
+            env_attack: self.operator_attack_buffers[op][n],
+            env_decay: self.operator_decay_buffers[op][n],
+            env_sustain: self.operator_sustain_buffers[op][n],
+            env_release: self.operator_release_buffers[op][n],
+        }
+    }
+
+    /// Inserts the event after those at the same or an earlier offset, so
+    /// the queue stays sorted without allocating. Once it is full, a note
+    /// on is dropped, while a release takes the place of the latest queued
+    /// note on, or turns the latest queued release into an all notes off,
+    /// so no note is left hanging.
+    fn push_note_event(&mut self, offset: usize, kind: NoteEventKind) {
+        let starts_note = |kind: &NoteEventKind| matches!(kind, NoteEventKind::On { velocity, .. } if *velocity > 0.0);
+        if self.note_events.len() == MAX_NOTE_EVENTS {
+            if starts_note(&kind) {
+                return;
+            }
+            match self
+                .note_events
+                .iter()
+                .rposition(|event| starts_note(&event.kind))
+            {
+                Some(index) => {
+                    self.note_events.remove(index);
+                }
+                None => {
+                    if let Some(last) = self.note_events.last_mut() {
+                        last.kind = NoteEventKind::AllOff;
+                    }
+                    return;
+                }
+            }
+        }
+        let index = self
+            .note_events
+            .partition_point(|event| event.offset <= offset);
+        self.note_events.insert(index, NoteEvent { offset, kind });
+    }
+
+    fn apply_note_event(&mut self, kind: NoteEventKind) {
+        let action = match kind {
+            NoteEventKind::On { note, velocity } if velocity > 0.0 => {
+                let mut levels = [0.0; VOICES];
+                for (level, voice) in levels.iter_mut().zip(self.voices.iter()) {
+                    *level = voice.level();
+                }
+                self.allocator.note_on(note, velocity, &levels)
+            }
+            NoteEventKind::On { note, .. } | NoteEventKind::Off { note } => {
+                self.allocator.note_off(note)
+            }
+            NoteEventKind::AllOff => {
+                self.allocator.reset();
+                self.release_event_voices();
+                None
+            }
+        };
+
+        match action {
+            Some(VoiceAction::NoteOn {
+                voice,
+                note,
+                velocity,
+                steal,
+            }) => {
+                let event_voice = &mut self.event_voices[voice];
+                let note = EventNote::new(note, velocity);
+                if steal || event_voice.fading {
+                    event_voice.fading = true;
+                    event_voice.pending = Some(note);
+                    event_voice.pending_released = false;
+                } else {
+                    event_voice.note = Some(note);
+                    self.voices[voice].note_on(note.key, note.velocity);
+                }
+            }
+            Some(VoiceAction::ChangeNote { voice, note }) => {
+                let event_voice = &mut self.event_voices[voice];
+                let current = if event_voice.fading {
+                    &mut event_voice.pending
+                } else {
+                    &mut event_voice.note
+                };
+                if let Some(current) = current {
+                    *current = EventNote::new(note, current.velocity);
+                }
+            }
+            Some(VoiceAction::NoteOff { voice }) => self.release_event_voice(voice),
+            None => (),
+        }
+    }
+
+    fn release_event_voice(&mut self, voice: usize) {
+        let event_voice = &mut self.event_voices[voice];
+        if event_voice.fading {
+            event_voice.pending_released = true;
+        } else if event_voice.note.is_some() {
+            self.voices[voice].note_off();
+        }
+    }
+
+    fn release_event_voices(&mut self) {
+        for voice in 0..VOICES {
+            self.release_event_voice(voice);
+        }
+    }
+}
+
+impl Processor for FMSynthesizer {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &INPUTS
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_input_buffer,
+            1 => &mut self.trigger_input_buffer,
+            2 => &mut self.retrigger_input_buffer,
+            3..=8 => &mut self.operator_level_buffers[index - 3],
+            9..=14 => &mut self.operator_attack_buffers[index - 9],
+            15..=20 => &mut self.operator_decay_buffers[index - 15],
+            21..=26 => &mut self.operator_sustain_buffers[index - 21],
+            27..=32 => &mut self.operator_release_buffers[index - 27],
+            33 => &mut self.velocity_input_buffer,
+            34..=39 => &mut self.operator_velocity_sensitivity_buffers[index - 34],
+            40..=45 => &mut self.operator_key_breakpoint_buffers[index - 40],
+            46..=51 => &mut self.operator_key_left_depth_buffers[index - 46],
+            52..=57 => &mut self.operator_key_right_depth_buffers[index - 52],
+            58..=63 => &mut self.operator_key_left_curve_buffers[index - 58],
+            64..=69 => &mut self.operator_key_right_curve_buffers[index - 64],
+            70..=75 => &mut self.operator_key_rate_scaling_buffers[index - 70],
+            76..=99 => &mut self.operator_eg_rate_buffers[index - 76],
+            100..=123 => &mut self.operator_eg_level_buffers[index - 100],
+            124..=127 => &mut self.pitch_eg_rate_buffers[index - 124],
+            128..=131 => &mut self.pitch_eg_level_buffers[index - 128],
+            132 => &mut self.lfo_speed_buffer,
+            133 => &mut self.lfo_delay_buffer,
+            134 => &mut self.lfo_fade_buffer,
+            135 => &mut self.lfo_pitch_mod_depth_buffer,
+            136 => &mut self.lfo_amp_mod_depth_buffer,
+            137..=142 => &mut self.operator_amp_mod_sensitivity_buffers[index - 137],
+            143..=148 => &mut self.operator_fixed_frequency_buffers[index - 143],
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        FMSynthesizer::process(
+            self,
+            frames,
+            block_param!(params, 0, "algorithm") as i32,
+            block_param!(params, 1, "op1PitchShift") as i32,
+            block_param!(params, 2, "op2PitchShift") as i32,
+            block_param!(params, 3, "op3PitchShift") as i32,
+            block_param!(params, 4, "op4PitchShift") as i32,
+            block_param!(params, 5, "op5PitchShift") as i32,
+            block_param!(params, 6, "op6PitchShift") as i32,
+            block_param!(params, 7, "op1FineTune") as i32,
+            block_param!(params, 8, "op2FineTune") as i32,
+            block_param!(params, 9, "op3FineTune") as i32,
+            block_param!(params, 10, "op4FineTune") as i32,
+            block_param!(params, 11, "op5FineTune") as i32,
+            block_param!(params, 12, "op6FineTune") as i32,
+            block_param!(params, 13, "voiceMode") as u8,
+            block_param!(params, 14, "stealMode") as u8,
+            block_param!(params, 15, "feedback"),
+            block_param!(params, 16, "envelopeMode") as u8,
+            block_param!(params, 17, "lfoWaveform") as u8,
+            block_param!(params, 18, "lfoKeySync") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        FMSynthesizer::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        FMSynthesizer::reconfigure(self, sample_rate, channel_count);
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        FMSynthesizer::set_seed(self, seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_event_offsets() {
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        synth.note_on(69, 1.0, 64);
+        synth.note_off(69, 128 + 32);
+        let process = |synth: &mut FMSynthesizer| {
+            synth.process(
+                128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5.0, 0, 0, 1,
+            );
+            synth.output_buffer.clone()
+        };
+
+        let first = process(&mut synth);
+        assert!(first[..64].iter().all(|y| *y == 0.0));
+        assert!(first[65..].iter().any(|y| *y != 0.0));
+        // the note off was carried over into the second block
+        process(&mut synth);
+        assert!(synth.note_events.is_empty());
+        let level = synth.voices[0].level();
+        process(&mut synth);
+        assert!(synth.voices[0].level() < level);
+    }
+
+    #[test]
+    fn test_patch_is_read_from_the_first_frame() {
+        let render = |synth: &mut FMSynthesizer| {
+            synth.note_on(69, 1.0, 0);
+            synth.process(
+                128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5.0, 1, 0, 1,
+            );
+            synth.output_buffer[..128].to_vec()
+        };
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        let expected = render(&mut synth);
+
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        for buffer in synth.operator_eg_rate_buffers.iter_mut() {
+            buffer[1..].fill(0.0);
+        }
+        synth.lfo_pitch_mod_depth_buffer[1..].fill(12.0);
+        assert_eq!(render(&mut synth), expected);
+    }
+
+    #[test]
+    fn test_preset_keeps_fixed_frequencies() {
+        let mut voice = Dx7Voice {
+            transpose: 12,
+            ..Dx7Voice::default()
+        };
+        voice.operators[5].fixed_frequency = true;
+        voice.operators[5].coarse = 2;
+        let json = dx7_preset_json(&voice);
+        // the transposition leaves the fixed operator alone
+        assert!(json.contains(r#""op6PitchShift":0,"op6FineTune":0,"op6FixedFrequency":100"#));
+        assert!(json.contains(r#""op1FixedFrequency":0"#));
+    }
+
+    #[test]
+    fn test_json_string_escapes() {
+        assert_eq!(json_string(r#"E.PIANO "1"\"#), r#""E.PIANO \"1\"\\""#);
+        assert_eq!(json_string("\t"), r#""\u0009""#);
+    }
+
+    #[test]
+    fn test_note_released_during_steal_fade() {
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        let process = |synth: &mut FMSynthesizer| {
+            synth.process(
+                128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5.0, 0, 0, 1,
+            );
+        };
+        for note in 60..60 + VOICES as u8 {
+            synth.note_on(note, 1.0, 0);
+        }
+        process(&mut synth);
+        // steals a voice, and releases the new note before the fade is over
+        synth.note_on(80, 1.0, 0);
+        synth.note_off(80, 16);
+        for _ in 0..10 {
+            process(&mut synth);
+        }
+        let voice = synth
+            .event_voices
+            .iter()
+            .position(|event_voice| event_voice.note.map(|note| note.key) == Some(80.0))
+            .unwrap();
+        // released right after it started, instead of held
+        assert_eq!(synth.voices[voice].level(), 0.0);
+    }
+
+    #[test]
+    fn test_note_event_queue_stays_sorted_and_bounded() {
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        synth.note_on(60, 1.0, 32);
+        synth.note_on(61, 1.0, 0);
+        synth.note_off(60, 32);
+        let offsets: Vec<usize> = synth.note_events.iter().map(|event| event.offset).collect();
+        assert_eq!(offsets, [0, 32, 32]);
+        // same offset: the note off stays behind its note on
+        assert!(matches!(
+            synth.note_events[2].kind,
+            NoteEventKind::Off { note: 60 }
+        ));
+
+        for note in 0..MAX_NOTE_EVENTS as u8 {
+            synth.note_on(note, 1.0, 64);
+        }
+        assert_eq!(synth.note_events.len(), MAX_NOTE_EVENTS);
+        assert_eq!(synth.note_events.capacity(), MAX_NOTE_EVENTS);
+        // a release replaces the latest note on
+        synth.note_off(61, 96);
+        assert!(matches!(
+            synth.note_events.last().unwrap().kind,
+            NoteEventKind::Off { note: 61 }
+        ));
+        assert_eq!(synth.note_events.len(), MAX_NOTE_EVENTS);
+    }
+
+    #[test]
+    fn test_lane_held_voices_are_not_allocated() {
+        let mut synth = FMSynthesizer::new(128, 48000.0, 1);
+        synth.trigger_input_buffer[..128].fill(1.0);
+        synth.frequency_input_buffer[..128].fill(220.0);
+        synth.velocity_input_buffer[..128].fill(1.0);
+        let process = |synth: &mut FMSynthesizer| {
+            synth.process(
+                128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5.0, 0, 0, 1,
+            );
+        };
+        process(&mut synth);
+        for note in 60..60 + VOICES as u8 {
+            synth.note_on(note, 1.0, 0);
+        }
+        process(&mut synth);
+        // voice 0 keeps playing its lane, the events share the other seven
+        assert!(synth.event_voices[0].note.is_none());
+        assert!(synth.event_voices[1..]
+            .iter()
+            .all(|event_voice| event_voice.note.is_some() || event_voice.fading));
+    }
 }
@@ -1,25 +1,48 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
+    block_param,
     dsp::{
         delay::Delay,
         envelope_filter::{EnvelopeFilter, LevelCalcType},
         envelope_follower::EnvelopeFollower,
         granular::{GranularProcessor, PlaybackDirection},
         linkwitz_riley_filter::{LinkwitzRileyFilter, LinkwitzRileyFilterType},
+        oversampling::OversamplingFactor,
         smoothed_value::SmoothedValue,
         utils::{make_fine_tune_factors, make_pitch_factors},
     },
-    linear_smoothed_value, multiplicative_smoothed_value, utils,
+    invalid_buffer_index, linear_smoothed_value, multiplicative_smoothed_value,
+    processor::Processor,
+    utils,
 };
 use std::{collections::HashMap, f32::consts::FRAC_PI_2};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "dryWetMix", min = 0.0, max = 1.0, default = 0.0)]
+#[param(name = "grainSizeMs", min = 1.0, max = 1000.0, default = 50.0)]
+#[param(name = "pitchShift", min = -12, max = 12, default = 0)]
+#[param(name = "fineTune", min = -100, max = 100, default = 0)]
+#[param(name = "texture", min = 0.0, max = 1.0, default = 0.5)]
+#[param(name = "stretch", min = 1, max = 4, default = 1)]
+#[param(name = "shimmer", min = 0.0, max = 1.0, default = 0)]
+#[param(name = "feedback", min = 0.0, max = 1.0, default = 0)]
+#[param(name = "hiCut", min = 20.0, max = 22000.0, default = 22000.0)]
+#[param(
+    name = "playbackDirection",
+    min = 0,
+    max = 2,
+    default = 0,
+    rate = "k-rate"
+)]
+#[param(name = "toneType", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(name = "oversampling", min = 0, max = 3, default = 0, rate = "k-rate")]
 pub struct Graindr {
     buffer_frame_length: usize,
     channel_count: usize,
+    sample_rate: f32,
 
     graindr: Vec<GraindrProcessor>,
 
@@ -67,6 +90,7 @@ impl Graindr {
         Graindr {
             buffer_frame_length,
             channel_count,
+            sample_rate,
             graindr: proc_vec,
             fine_tune_factors: make_fine_tune_factors(),
             pitch_factors: make_pitch_factors(1),
@@ -89,15 +113,20 @@ impl Graindr {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn process(
         &mut self,
+        frames: usize,
         dry_wet_mix: f32,
         pitch_shift: i32,
         fine_tune: i32,
         stretch: u8,
         playback_direction: u8,
         tone_type: u8,
+        oversampling: u8,
     ) {
+        let frames = frames.min(self.buffer_frame_length);
+
         if dry_wet_mix != self.dry_wet_mix {
             self.dry_gain
                 .set_target_value((FRAC_PI_2 * (1.0 - dry_wet_mix)).sin());
@@ -115,7 +144,12 @@ impl Graindr {
         let playback_direction = playback_direction.into();
         let tone_type = tone_type.into();
 
-        for n in 0..self.buffer_frame_length {
+        let oversampling = oversampling.into();
+        self.graindr
+            .iter_mut()
+            .for_each(|graindr| graindr.set_oversampling(oversampling));
+
+        for n in 0..frames {
             let next_dry_gain = self.dry_gain.get_next_value();
             let next_wet_gain = self.wet_gain.get_next_value();
             let next_grain_size = self.grain_size_ms_buffer[n] * self.ms_to_samples_factor;
@@ -159,6 +193,31 @@ impl Graindr {
         self.wet_gain.reset();
         self.pitch_factor.reset();
     }
+
+    /// Grain and delay buffers are sized in samples, so they are resized
+    /// when the sample rate changes, keeping what they hold; a channel count
+    /// change keeps the existing channels.
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            for graindr in self.graindr.iter_mut() {
+                graindr.set_sample_rate(sample_rate);
+            }
+
+            self.dry_gain.set_sample_rate(sample_rate);
+            self.wet_gain.set_sample_rate(sample_rate);
+            self.pitch_factor.set_sample_rate(sample_rate);
+            self.ms_to_samples_factor = 0.001 * sample_rate;
+        }
+
+        self.channel_count = channel_count;
+        self.graindr
+            .resize_with(channel_count, || GraindrProcessor::new(sample_rate));
+        self.input_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -223,6 +282,18 @@ impl GraindrProcessor {
         }
     }
 
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.envelope_follower.set_sample_rate(sample_rate);
+        self.pre_envelope_filter.set_sample_rate(sample_rate);
+        self.granular_processor.set_sample_rate(
+            sample_rate,
+            MAX_GRAIN_SIZE_SEC,
+            MAX_PITCH_SHIFT_FACTOR,
+        );
+        self.delay
+            .set_sample_rate(sample_rate, MAX_GRAIN_SIZE_SEC * sample_rate as usize);
+    }
+
     pub fn process(
         &mut self,
         x: f32,
@@ -291,6 +362,12 @@ impl GraindrProcessor {
         )
     }
 
+    /// Oversampling of the tape soft clippers, only used in tape mode.
+    pub fn set_oversampling(&mut self, factor: OversamplingFactor) {
+        self.granular_processor.set_oversampling(factor);
+        self.delay.set_oversampling(factor);
+    }
+
     pub fn reset(&mut self) {
         self.granular_processor.reset();
         self.envelope_follower.reset();
@@ -300,3 +377,65 @@ impl GraindrProcessor {
         self.delay.reset();
     }
 }
+
+impl Processor for Graindr {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &[
+            "input",
+            "feedback",
+            "grain_size_ms",
+            "hi_cut",
+            "shimmer",
+            "texture",
+        ]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            1 => &mut self.feedback_buffer,
+            2 => &mut self.grain_size_ms_buffer,
+            3 => &mut self.hi_cut_freq_buffer,
+            4 => &mut self.shimmer_buffer,
+            5 => &mut self.texture_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        Graindr::process(
+            self,
+            frames,
+            block_param!(params, 0, "dryWetMix"),
+            block_param!(params, 1, "pitchShift") as i32,
+            block_param!(params, 2, "fineTune") as i32,
+            block_param!(params, 3, "stretch") as u8,
+            block_param!(params, 4, "playbackDirection") as u8,
+            block_param!(params, 5, "toneType") as u8,
+            block_param!(params, 6, "oversampling") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        Graindr::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        Graindr::reconfigure(self, sample_rate, channel_count);
+    }
+}
@@ -0,0 +1,277 @@
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    block_param,
+    dsp::{
+        circular_buffer::{CircularBuffer, InterpolationType},
+        noise::WhiteNoiseGenerator,
+        random::DEFAULT_SEED,
+    },
+    invalid_buffer_index,
+    processor::Processor,
+    utils,
+};
+
+const MIN_FREQUENCY: f32 = 20.0;
+// the cubic interpolation reads one sample on either side of the delay
+const MIN_DELAY: f32 = 2.0;
+// slight loss on every pass, so nothing (not even DC) rings forever
+const LOOP_GAIN: f32 = 0.9995;
+const MAX_DAMPING: f32 = 0.9;
+const MAX_EXCITATION_DAMPING: f32 = 0.95;
+
+/// Karplus-Strong plucked string: a delay line one period long, fed back
+/// through a lowpass loop filter.
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 20.0, max = 20000.0, default = 220.0)]
+#[param(name = "excitation", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(name = "damping", min = 0, max = 1, default = 0.2, rate = "k-rate")]
+#[param(name = "brightness", min = 0, max = 1, default = 0.8, rate = "k-rate")]
+#[param(name = "stretch", min = 0, max = 1, default = 0.5, rate = "k-rate")]
+pub struct KarplusStrong {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+
+    delay_line: CircularBuffer,
+    noise: WhiteNoiseGenerator,
+    // samples left of the current noise burst
+    burst_countdown: usize,
+    last_trigger: f32,
+    // one-pole lowpass states
+    excitation_state: f32,
+    damping_state: f32,
+    // previous input of the stretch filter
+    stretch_state: f32,
+
+    // parameter buffers
+    #[io_buffer]
+    frequency_buffer: Vec<f32>,
+
+    // IO buffers
+    // 1V/oct: the frequency is scaled by `2^pitch`
+    #[io_buffer]
+    pitch_buffer: Vec<f32>,
+    // A rising zero crossing plucks the string.
+    #[io_buffer]
+    trigger_buffer: Vec<f32>,
+    // Fed into the string with `KarplusStrongExcitation::External`.
+    #[io_buffer]
+    excitation_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl KarplusStrong {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32) -> KarplusStrong {
+        utils::set_panic_hook();
+
+        KarplusStrong {
+            buffer_frame_length,
+            sample_rate,
+
+            delay_line: make_delay_line(sample_rate),
+            noise: WhiteNoiseGenerator::new(DEFAULT_SEED),
+            burst_countdown: 0,
+            last_trigger: 0.0,
+            excitation_state: 0.0,
+            damping_state: 0.0,
+            stretch_state: 0.0,
+
+            frequency_buffer: vec![0.0; buffer_frame_length],
+
+            pitch_buffer: vec![0.0; buffer_frame_length],
+            trigger_buffer: vec![0.0; buffer_frame_length],
+            excitation_buffer: vec![0.0; buffer_frame_length],
+            output_buffer: vec![0.0; buffer_frame_length],
+        }
+    }
+
+    /// `damping` darkens the string as it rings, `brightness` opens up the
+    /// lowpass on the excitation and `stretch` lengthens the decay by
+    /// weakening the classic two-point average in the loop.
+    pub fn process(
+        &mut self,
+        frames: usize,
+        excitation: u8,
+        damping: f32,
+        brightness: f32,
+        stretch: f32,
+    ) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let excitation = KarplusStrongExcitation::from(excitation);
+        let damping = MAX_DAMPING * damping.clamp(0.0, 1.0);
+        let excitation_damping = MAX_EXCITATION_DAMPING * (1.0 - brightness.clamp(0.0, 1.0));
+        // weight of the previous sample: 0.5 is the plain average, towards 0
+        // the high end decays more and more slowly (Jaffe & Smith)
+        let stretch = 0.5 * (1.0 - 0.99 * stretch.clamp(0.0, 1.0));
+        // the loop filters delay the low end by this many samples
+        let filter_delay = stretch + damping / (1.0 - damping);
+        let max_delay = (self.sample_rate / MIN_FREQUENCY).ceil();
+
+        for n in 0..frames {
+            let frequency = self.frequency_buffer[n] * self.pitch_buffer[n].exp2();
+            let period = self.sample_rate / frequency.max(MIN_FREQUENCY);
+
+            let trigger = self.trigger_buffer[n];
+            if self.last_trigger <= 0.0 && trigger > 0.0 {
+                self.burst_countdown = period as usize;
+            }
+            self.last_trigger = trigger;
+
+            let input = match excitation {
+                KarplusStrongExcitation::Noise if self.burst_countdown > 0 => {
+                    self.burst_countdown -= 1;
+                    self.noise.next()
+                }
+                KarplusStrongExcitation::Noise => 0.0,
+                KarplusStrongExcitation::External => self.excitation_buffer[n],
+            };
+            self.excitation_state = input + excitation_damping * (self.excitation_state - input);
+
+            let delay = (period - filter_delay).clamp(MIN_DELAY, max_delay);
+            let delayed = self
+                .delay_line
+                .read_fractional(delay, InterpolationType::Cubic);
+
+            let stretched = (1.0 - stretch) * delayed + stretch * self.stretch_state;
+            self.stretch_state = delayed;
+            self.damping_state = stretched + damping * (self.damping_state - stretched);
+
+            let output = self.excitation_state + LOOP_GAIN * self.damping_state;
+            self.delay_line.write(output);
+            self.output_buffer[n] = output;
+        }
+    }
+
+    /// Restarts the noise bursts from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.noise.set_seed(seed);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.delay_line.reset();
+        self.noise.reset();
+        self.burst_countdown = 0;
+        self.last_trigger = 0.0;
+        self.excitation_state = 0.0;
+        self.damping_state = 0.0;
+        self.stretch_state = 0.0;
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.delay_line = make_delay_line(sample_rate);
+        self.reset();
+    }
+}
+
+fn make_delay_line(sample_rate: f32) -> CircularBuffer {
+    // room for the lowest frequency plus the cubic interpolation's lookahead
+    CircularBuffer::new((sample_rate / MIN_FREQUENCY).ceil() as usize + 2)
+}
+
+/// What sets the string in motion.
+#[derive(Copy, Clone)]
+pub enum KarplusStrongExcitation {
+    /// A burst of white noise, one period long, on every trigger.
+    Noise,
+    /// The excitation input, continuously.
+    External,
+}
+
+impl From<u8> for KarplusStrongExcitation {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => KarplusStrongExcitation::External,
+            _ => KarplusStrongExcitation::Noise,
+        }
+    }
+}
+
+impl Processor for KarplusStrong {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "pitch", "trigger", "excitation"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.pitch_buffer,
+            2 => &mut self.trigger_buffer,
+            3 => &mut self.excitation_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        KarplusStrong::process(
+            self,
+            frames,
+            block_param!(params, 0, "excitation") as u8,
+            block_param!(params, 1, "damping"),
+            block_param!(params, 2, "brightness"),
+            block_param!(params, 3, "stretch"),
+        );
+    }
+
+    fn reset(&mut self) {
+        KarplusStrong::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        KarplusStrong::reconfigure(self, sample_rate, channel_count);
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        KarplusStrong::set_seed(self, seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pluck_rings_at_frequency() {
+        let mut string = KarplusStrong::new(128, 48000.0);
+        string.frequency_buffer.fill(480.0);
+        string.process(128, 0, 0.0, 1.0, 1.0);
+        assert!(string.output_buffer.iter().all(|y| *y == 0.0));
+
+        string.trigger_buffer.fill(1.0);
+        let mut output = vec![];
+        for _ in 0..4 {
+            string.process(128, 0, 0.0, 1.0, 1.0);
+            output.extend_from_slice(&string.output_buffer);
+        }
+
+        // once the burst is over, every period repeats the previous one
+        let period = 100;
+        let peak = output.iter().fold(0.0f32, |max, y| max.max(y.abs()));
+        assert!(peak > 0.1);
+        for n in 2 * period..3 * period {
+            assert!((output[n + period] - output[n]).abs() < 0.05 * peak);
+        }
+    }
+}
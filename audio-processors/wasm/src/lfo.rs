@@ -1,17 +1,42 @@
-use rustfft::num_traits::ToPrimitive;
 use std::f32::consts::TAU as TWO_PI;
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 use crate::{
-    dsp::{fast_math, phase_counter::PhaseCounter, smoothed_value::SmoothedValue},
-    linear_smoothed_value,
+    block_param,
+    dsp::{
+        custom_waveform::CustomWaveform,
+        phase_counter::PhaseCounter,
+        random::{derive_seed, Random, DEFAULT_SEED},
+        smoothed_value::SmoothedValue,
+        waveforms,
+    },
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
 };
 
 const FRAC_1_2PI: f32 = 1.0 / TWO_PI;
 
+/// Channels of the spread output.
+pub const SPREAD_CHANNELS: usize = 4;
+
+// random steps of the main, the quadrature and the spread outputs
+const QUADRATURE_TAP: usize = 1;
+const SPREAD_TAP: usize = 2;
+const TAPS: usize = SPREAD_TAP + SPREAD_CHANNELS - 1;
+
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.1, max = 20, default = 0.1)]
+#[param(name = "phase", min = 0, max = 1, default = 0)]
+#[param(name = "polarity", min = 0, max = 1, default = 0)]
+#[param(name = "waveform", min = 0, max = 10, default = 0)]
+#[param(name = "tempoSync", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(name = "bpm", min = 20, max = 300, default = 120, rate = "k-rate")]
+#[param(name = "division", min = 0, max = 6, default = 2, rate = "k-rate")]
+#[param(name = "divisionType", min = 0, max = 2, default = 0, rate = "k-rate")]
+#[param(name = "skew", min = 0, max = 1, default = 0.5, rate = "k-rate")]
+#[param(name = "spreadPhases", min = 1, max = 4, default = 4, rate = "k-rate")]
 pub struct LFO {
     // sample_rate: f32,
     sample_rate_inv: f32,
@@ -21,17 +46,31 @@ pub struct LFO {
     frequency: f32,
     phase_increment: SmoothedValue,
 
-    rsh_counter: u32,
-    rsh_state: SmoothedValue,
+    // one sequence per output, so shifted outputs step at their own phase
+    random_steps: Vec<RandomSteps>,
+
+    custom_waveform: CustomWaveform,
+
+    last_clock: f32,
 
     #[io_buffer]
     phase_shift_buffer: Vec<f32>,
+    // A rising zero crossing restarts the cycle; the phase shift still
+    // applies on top.
+    #[io_buffer]
+    clock_buffer: Vec<f32>,
     #[io_buffer]
     output_buffer: Vec<f32>,
+    // 90° ahead of the output
+    #[io_buffer]
+    quadrature_buffer: Vec<f32>,
+    #[io_buffer]
+    inverted_buffer: Vec<f32>,
+    // `SPREAD_CHANNELS` channels, spread evenly over the cycle
+    #[io_buffer]
+    spread_buffer: Vec<f32>,
 }
 
-const ONE_THIRD: f32 = 1.0 / 3.0;
-
 #[wasm_bindgen]
 impl LFO {
     #[wasm_bindgen(constructor)]
@@ -45,54 +84,110 @@ impl LFO {
             frequency: 0.0,
             phase_increment: linear_smoothed_value!(0.0, sample_rate, 0.05),
 
-            rsh_counter: 0,
-            rsh_state: linear_smoothed_value!(0.0, sample_rate, 0.0001),
+            random_steps: (0..TAPS)
+                .map(|tap| RandomSteps::new(derive_seed(DEFAULT_SEED, tap as u32)))
+                .collect(),
+
+            custom_waveform: CustomWaveform::new(),
+
+            last_clock: 0.0,
 
             phase_shift_buffer: vec![0.0; buffer_frame_length],
+            clock_buffer: vec![0.0; buffer_frame_length],
             output_buffer: vec![0.0; buffer_frame_length],
+            quadrature_buffer: vec![0.0; buffer_frame_length],
+            inverted_buffer: vec![0.0; buffer_frame_length],
+            spread_buffer: vec![0.0; buffer_frame_length * SPREAD_CHANNELS],
         }
     }
 
-    pub fn process(&mut self, frequency: f32, polarity: LFOPolarity, waveform: LFOWaveform) {
+    /// With `tempo_sync` set, `frequency` is ignored and one cycle lasts
+    /// `division` (modified by `division_type`) at `bpm` quarter notes per
+    /// minute. `skew` moves the peak of the skewed triangle.
+    ///
+    /// Spread channel `k` is shifted by `k / spread_phases` of a cycle;
+    /// channels from `spread_phases` on repeat the first ones.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process(
+        &mut self,
+        frames: usize,
+        frequency: f32,
+        polarity: LFOPolarity,
+        waveform: LFOWaveform,
+        tempo_sync: bool,
+        bpm: f32,
+        division: u8,
+        division_type: u8,
+        skew: f32,
+        spread_phases: u8,
+    ) {
+        let frequency = if tempo_sync {
+            tempo_frequency(bpm, division.into(), division_type.into())
+        } else {
+            frequency
+        };
         if self.frequency != frequency {
             self.phase_increment
                 .set_target_value(frequency * self.sample_rate_inv);
             self.frequency = frequency;
         }
 
-        for (out, phase_shift) in self
-            .output_buffer
-            .iter_mut()
-            .zip(self.phase_shift_buffer.iter().map(|v| v * FRAC_1_2PI))
-        {
+        let buffer_frame_length = self.output_buffer.len();
+        let spread_phases = (spread_phases as usize).clamp(1, SPREAD_CHANNELS);
+
+        for n in 0..frames.min(buffer_frame_length) {
+            let clock = self.clock_buffer[n];
+            if self.last_clock <= 0.0 && clock > 0.0 {
+                self.phase_counter.reset();
+                // draw new random steps right away
+                self.random_steps.iter_mut().for_each(RandomSteps::restart);
+            }
+            self.last_clock = clock;
+
             let increment = self.phase_increment.get_next_value();
 
-            let mut arg = self.phase_counter.advance(increment);
-            arg = (arg + phase_shift).fract();
-
-            let bipolar = match waveform {
-                LFOWaveform::Sine => Self::sine_wave(arg),
-                LFOWaveform::Triangle => Self::triangle_wave(arg, increment),
-                LFOWaveform::Ramp => Self::ramp_wave(arg, increment),
-                LFOWaveform::Saw => Self::saw_wave(arg, increment),
-                LFOWaveform::Square => Self::square_wave(arg, increment),
-                LFOWaveform::RSH => {
-                    let thresh = 1.0 / (2.0 * increment);
-                    self.rsh_counter = if self.rsh_counter >= thresh.to_u32().unwrap() {
-                        self.rsh_state.set_target_value(
-                            js_sys::Math::random().to_f32().unwrap().mul_add(2.0, -1.0),
-                        );
-                        0
-                    } else {
-                        self.rsh_counter + 1
-                    };
-                    self.rsh_state.get_next_value()
-                }
-            };
-
-            *out = match polarity {
-                LFOPolarity::Bipolar => bipolar,
-                LFOPolarity::Unipolar => bipolar.mul_add(0.5, 0.5),
+            let arg = self.phase_counter.advance(increment);
+            let arg = (arg + self.phase_shift_buffer[n] * FRAC_1_2PI).fract();
+
+            let bipolar = shape(
+                &waveform,
+                arg,
+                increment,
+                skew,
+                &mut self.random_steps[0],
+                &self.custom_waveform,
+            );
+            self.output_buffer[n] = polarise(&polarity, bipolar);
+            self.inverted_buffer[n] = polarise(&polarity, -bipolar);
+
+            let quadrature = shape(
+                &waveform,
+                (arg + 0.25).fract(),
+                increment,
+                skew,
+                &mut self.random_steps[QUADRATURE_TAP],
+                &self.custom_waveform,
+            );
+            self.quadrature_buffer[n] = polarise(&polarity, quadrature);
+
+            let mut spread = [self.output_buffer[n]; SPREAD_CHANNELS];
+            for (phase, out) in spread.iter_mut().enumerate().take(spread_phases).skip(1) {
+                let y = shape(
+                    &waveform,
+                    (arg + phase as f32 / spread_phases as f32).fract(),
+                    increment,
+                    skew,
+                    &mut self.random_steps[SPREAD_TAP + phase - 1],
+                    &self.custom_waveform,
+                );
+                *out = polarise(&polarity, y);
+            }
+            for (channel, out) in self
+                .spread_buffer
+                .chunks_exact_mut(buffer_frame_length)
+                .enumerate()
+            {
+                out[n] = spread[channel % spread_phases];
             }
         }
     }
@@ -101,86 +196,81 @@ impl LFO {
         self.phase_counter.reset();
         self.phase_increment.reset();
 
-        self.rsh_counter = 0;
-        self.rsh_state.set_current_and_target_value(0.0);
+        self.random_steps.iter_mut().for_each(RandomSteps::reset);
+        self.last_clock = 0.0;
     }
 
-    #[inline(always)]
-    fn sine_wave(arg: f32) -> f32 {
-        let arg1 = (arg + 0.25).fract();
-        if arg1 < 0.5 {
-            return fast_math::sin(TWO_PI * (arg1 - 0.25));
-        }
-        0.0 - fast_math::sin(TWO_PI * (arg1 - 0.75))
+    /// Uses `table` as one cycle of the custom waveform. Returns `false` if
+    /// `table` is empty.
+    pub fn load_custom_table(&mut self, table: &[f32]) -> bool {
+        self.custom_waveform.load_table(table)
     }
 
-    #[inline(always)]
-    fn triangle_wave(arg: f32, increment: f32) -> f32 {
-        let arg1 = (arg + 0.25).fract();
-        let arg2 = (arg + 0.75).fract();
-        let mut y = arg * 4.0;
-        if y >= 3.0 {
-            y -= 4.0;
-        } else if y > 1.0 {
-            y = 2.0 - y;
-        }
-
-        y +=
-            4.0 * increment * (Self::polyblamp(arg1, increment) - Self::polyblamp(arg2, increment));
-
-        // 1.0 - 2.0 * (2.0 * arg - 1.0).abs()
-        // let abs = (arg + arg - 1.0).abs();
-        // 1.0 - (abs + abs)
-        y
-    }
-
-    #[inline(always)]
-    fn ramp_wave(arg: f32, increment: f32) -> f32 {
-        1.0 - (arg + arg) + Self::polyblep(arg, increment)
+    /// Builds the custom waveform from interleaved `(phase, value)` pairs
+    /// with rising phases in `[0, 1]`, connected by straight lines. Returns
+    /// `false` if the breakpoints are invalid.
+    pub fn load_custom_breakpoints(&mut self, breakpoints: &[f32]) -> bool {
+        self.custom_waveform.load_breakpoints(breakpoints)
     }
 
-    #[inline(always)]
-    fn saw_wave(arg: f32, increment: f32) -> f32 {
-        let arg1 = (arg + 0.5).fract();
-        (arg1 + arg1) - 1.0 - Self::polyblep(arg1, increment)
+    /// Restarts the random sample & hold sequence from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        for (tap, random_steps) in self.random_steps.iter_mut().enumerate() {
+            random_steps.rng.set_seed(derive_seed(seed, tap as u32));
+        }
     }
 
-    #[inline(always)]
-    fn square_wave(arg: f32, increment: f32) -> f32 {
-        let arg1 = (arg + 0.5).fract();
-        let mut y: f32 = if arg < 0.5 { 1.0 } else { -1.0 };
-        y += Self::polyblep(arg, increment) - Self::polyblep(arg1, increment);
-        y
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+        self.phase_increment.set_sample_rate(sample_rate);
+        self.phase_increment
+            .set_current_and_target_value(self.frequency * self.sample_rate_inv);
     }
+}
 
-    #[inline(always)]
-    fn polyblep(arg: f32, increment: f32) -> f32 {
-        if arg < increment {
-            let t = arg / increment - 1.0;
-            return -(t * t);
-        }
+/// Random values for the sample & hold waveforms, a new one every half
+/// cycle.
+struct RandomSteps {
+    rng: Random,
+    step: u8,
+    value: f32,
+    previous_value: f32,
+}
 
-        if arg > 1.0 - increment {
-            let t = (arg - 1.0) / increment + 1.0;
-            return t * t;
+impl RandomSteps {
+    fn new(seed: u32) -> RandomSteps {
+        RandomSteps {
+            rng: Random::new(seed),
+            step: 0,
+            value: 0.0,
+            previous_value: 0.0,
         }
-
-        0.0
     }
 
-    #[inline(always)]
-    fn polyblamp(arg: f32, increment: f32) -> f32 {
-        if arg < increment {
-            let t = arg / increment - 1.0;
-            return -ONE_THIRD * (t * t * t);
+    /// Draws a new value whenever `arg` enters the other half of the cycle
+    /// and returns the position within the current half.
+    #[inline]
+    fn advance(&mut self, arg: f32) -> f32 {
+        let position = arg + arg;
+        let step = position as u8;
+        if step != self.step {
+            self.step = step;
+            self.previous_value = self.value;
+            self.value = self.rng.next_bipolar();
         }
+        position.fract()
+    }
 
-        if arg > 1.0 - increment {
-            let t = (arg - 1.0) / increment + 1.0;
-            return ONE_THIRD * (t * t * t);
-        }
+    /// Makes the next `advance` draw a new value.
+    fn restart(&mut self) {
+        self.step = u8::MAX;
+    }
 
-        0.0
+    fn reset(&mut self) {
+        self.rng.reset();
+        self.step = 0;
+        self.value = 0.0;
+        self.previous_value = 0.0;
     }
 }
 
@@ -190,6 +280,15 @@ pub enum LFOPolarity {
     Unipolar,
 }
 
+impl From<u8> for LFOPolarity {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => LFOPolarity::Unipolar,
+            _ => LFOPolarity::Bipolar,
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub enum LFOWaveform {
     Sine,
@@ -197,5 +296,268 @@ pub enum LFOWaveform {
     Saw,
     Ramp,
     Square,
+    /// Stepped sample & hold.
     RSH,
+    SmoothRandom,
+    ExpRamp,
+    LogRamp,
+    SkewedTriangle,
+    /// Loaded with `load_custom_table` or `load_custom_breakpoints`.
+    Custom,
+}
+
+impl From<u8> for LFOWaveform {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => LFOWaveform::Triangle,
+            2 => LFOWaveform::Saw,
+            3 => LFOWaveform::Ramp,
+            4 => LFOWaveform::Square,
+            5 => LFOWaveform::RSH,
+            6 => LFOWaveform::SmoothRandom,
+            7 => LFOWaveform::ExpRamp,
+            8 => LFOWaveform::LogRamp,
+            9 => LFOWaveform::SkewedTriangle,
+            10 => LFOWaveform::Custom,
+            _ => LFOWaveform::Sine,
+        }
+    }
+}
+
+/// Note length of one LFO cycle in tempo sync mode.
+#[derive(Copy, Clone)]
+pub enum LFODivision {
+    Sixteenth,
+    Eighth,
+    Quarter,
+    Half,
+    Bar,
+    TwoBars,
+    FourBars,
+}
+
+impl LFODivision {
+    fn beats(self) -> f32 {
+        match self {
+            LFODivision::Sixteenth => 0.25,
+            LFODivision::Eighth => 0.5,
+            LFODivision::Quarter => 1.0,
+            LFODivision::Half => 2.0,
+            LFODivision::Bar => 4.0,
+            LFODivision::TwoBars => 8.0,
+            LFODivision::FourBars => 16.0,
+        }
+    }
+}
+
+impl From<u8> for LFODivision {
+    fn from(val: u8) -> Self {
+        match val {
+            0 => LFODivision::Sixteenth,
+            1 => LFODivision::Eighth,
+            3 => LFODivision::Half,
+            4 => LFODivision::Bar,
+            5 => LFODivision::TwoBars,
+            6 => LFODivision::FourBars,
+            _ => LFODivision::Quarter,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum LFODivisionType {
+    Straight,
+    Dotted,
+    Triplet,
+}
+
+impl From<u8> for LFODivisionType {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => LFODivisionType::Dotted,
+            2 => LFODivisionType::Triplet,
+            _ => LFODivisionType::Straight,
+        }
+    }
+}
+
+/// Frequency in Hz of a cycle lasting `division` at `bpm`, assuming 4/4.
+fn tempo_frequency(bpm: f32, division: LFODivision, division_type: LFODivisionType) -> f32 {
+    let beats = match division_type {
+        LFODivisionType::Straight => division.beats(),
+        LFODivisionType::Dotted => 1.5 * division.beats(),
+        LFODivisionType::Triplet => 2.0 / 3.0 * division.beats(),
+    };
+    bpm / (60.0 * beats)
+}
+
+#[inline(always)]
+fn shape(
+    waveform: &LFOWaveform,
+    arg: f32,
+    increment: f32,
+    skew: f32,
+    random_steps: &mut RandomSteps,
+    custom_waveform: &CustomWaveform,
+) -> f32 {
+    match waveform {
+        LFOWaveform::Sine => waveforms::sine(arg),
+        LFOWaveform::Triangle => waveforms::triangle(arg, increment),
+        LFOWaveform::Ramp => waveforms::ramp(arg, increment),
+        LFOWaveform::Saw => waveforms::saw(arg, increment),
+        LFOWaveform::Square => waveforms::square(arg, increment),
+        LFOWaveform::RSH => {
+            random_steps.advance(arg);
+            random_steps.value
+        }
+        LFOWaveform::SmoothRandom => {
+            let position = random_steps.advance(arg);
+            let RandomSteps {
+                value,
+                previous_value,
+                ..
+            } = *random_steps;
+            // glide from the previous value along a raised cosine
+            let glide = 0.5 - 0.5 * waveforms::sine(0.5 * position + 0.25);
+            previous_value + glide * (value - previous_value)
+        }
+        LFOWaveform::ExpRamp => waveforms::exp_ramp(arg),
+        LFOWaveform::LogRamp => waveforms::log_ramp(arg),
+        LFOWaveform::SkewedTriangle => waveforms::skewed_triangle(arg, skew),
+        LFOWaveform::Custom => custom_waveform.read(arg),
+    }
+}
+
+#[inline(always)]
+fn polarise(polarity: &LFOPolarity, bipolar: f32) -> f32 {
+    match polarity {
+        LFOPolarity::Bipolar => bipolar,
+        LFOPolarity::Unipolar => bipolar.mul_add(0.5, 0.5),
+    }
+}
+
+impl Processor for LFO {
+    fn buffer_frame_length(&self) -> usize {
+        self.output_buffer.len()
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["phase_shift", "clock"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output", "quadrature", "inverted", "spread"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.phase_shift_buffer,
+            1 => &mut self.clock_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            1 => &self.quadrature_buffer,
+            2 => &self.inverted_buffer,
+            3 => &self.spread_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        LFO::process(
+            self,
+            frames,
+            block_param!(params, 0, "frequency"),
+            (block_param!(params, 1, "polarity") as u8).into(),
+            (block_param!(params, 2, "waveform") as u8).into(),
+            block_param!(params, 3, "tempoSync") != 0.0,
+            block_param!(params, 4, "bpm"),
+            block_param!(params, 5, "division") as u8,
+            block_param!(params, 6, "divisionType") as u8,
+            block_param!(params, 7, "skew"),
+            block_param!(params, 8, "spreadPhases") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        LFO::reset(self);
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        LFO::set_seed(self, seed);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        LFO::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tempo_frequency() {
+        let quarter = tempo_frequency(120.0, LFODivision::Quarter, LFODivisionType::Straight);
+        assert_eq!(quarter, 2.0);
+        let dotted = tempo_frequency(120.0, LFODivision::Quarter, LFODivisionType::Dotted);
+        assert!((dotted - 4.0 / 3.0).abs() < 1e-6);
+        let triplet = tempo_frequency(120.0, LFODivision::Bar, LFODivisionType::Triplet);
+        assert!((triplet - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clock_retriggers_with_phase_offset() {
+        let mut lfo = LFO::new(128, 48000.0);
+        lfo.phase_shift_buffer.fill(0.5 * TWO_PI);
+        lfo.clock_buffer[64] = 1.0;
+        lfo.process(
+            128,
+            10.0,
+            LFOPolarity::Bipolar,
+            LFOWaveform::Sine,
+            false,
+            120.0,
+            2,
+            0,
+            0.5,
+            4,
+        );
+
+        // the retrigger jumps back to half a cycle, where the sine crosses zero
+        assert!(lfo.output_buffer[63] < -1e-4);
+        assert!(lfo.output_buffer[64].abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_phase_shifted_outputs() {
+        let mut lfo = LFO::new(128, 48000.0);
+        lfo.process(
+            128,
+            10.0,
+            LFOPolarity::Bipolar,
+            LFOWaveform::Sine,
+            false,
+            120.0,
+            2,
+            0,
+            0.5,
+            2,
+        );
+
+        let (main, spread) = (&lfo.output_buffer, &lfo.spread_buffer);
+        for n in 0..128 {
+            assert_eq!(lfo.inverted_buffer[n], -main[n]);
+            // the sine is still close to its start, so the cosine is near 1
+            assert!(lfo.quadrature_buffer[n] > 0.99);
+            assert_eq!(spread[n], main[n]);
+            assert!((spread[128 + n] + main[n]).abs() < 1e-5);
+            assert_eq!(spread[256 + n], main[n]);
+            assert_eq!(spread[384 + n], spread[128 + n]);
+        }
+    }
 }
@@ -1,3 +1,8 @@
+// `#[param(...)]` attributes repeat keys such as `min = 0.0` across parameters.
+#![allow(clippy::duplicated_attributes)]
+// `FMSynthesizer` has more `#[param(...)]` attributes than the default limit.
+#![recursion_limit = "256"]
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
@@ -13,13 +18,22 @@ pub mod envelope_tracker;
 pub mod fm_oscillator;
 pub mod fm_voice;
 pub mod graindr;
+pub mod karplus_strong;
 pub mod lfo;
 pub mod multi_filter;
 pub mod multiplier;
+pub mod noise_generator;
+pub mod offline;
+pub mod patch_graph;
+pub mod phase_distortion_oscillator;
 pub mod phaser;
 pub mod pitch_tracker;
+pub mod processor;
 pub mod sequencer;
 pub mod toggle_util;
+pub mod unison_oscillator;
+pub mod vco;
+pub mod wavetable_oscillator;
 
 mod dsp;
 mod utils;
@@ -1,14 +1,31 @@
-use crate::dsp::vasv_filter::VASVFilter;
+use crate::{
+    dsp::vasv_filter::{FrameCoeffs, VASVFilter},
+    invalid_buffer_index,
+    processor::Processor,
+};
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(
+    name = "cutoff",
+    min = 20.0,
+    max = 20480.0,
+    default = 1000.0,
+    rate = "a-rate"
+)]
+#[param(name = "q", min = 0.707, max = 20.0, default = 0.707, rate = "a-rate")]
 pub struct MultiFilter {
     buffer_frame_length: usize,
     channel_count: usize,
+    sample_rate_inv: f32,
     filters: Vec<VASVFilter>,
 
+    // all channels share the cutoff and q buffers, hence the coefficients
+    // of a modulated block
+    coeffs: FrameCoeffs,
+
     // IO buffers
     #[io_buffer]
     input_buffer: Vec<f32>,
@@ -41,7 +58,9 @@ impl MultiFilter {
         MultiFilter {
             buffer_frame_length,
             channel_count,
+            sample_rate_inv: 1.0 / sample_rate,
             filters,
+            coeffs: FrameCoeffs::new(buffer_frame_length),
             input_buffer: vec![0.0; buffer_frame_length * channel_count],
             bpf_out_buffer: vec![0.0; buffer_frame_length * channel_count],
             bsf_out_buffer: vec![0.0; buffer_frame_length * channel_count],
@@ -53,38 +72,48 @@ impl MultiFilter {
         }
     }
 
-    pub fn process(&mut self) {
-        // for n in 0..self.buffer_frame_length {
-        //     let mut channel_offset = 0;
-        //     for channel in 0..self.channel_count {
-        //         let channel_filter = &mut self.filters[channel];
-        //         channel_filter.set_params(self.f_c_buffer[n], self.q_buffer[n], 1.0, 1.0, 1.0, 1.0);
-
-        //         let sample_index = channel_offset + n;
-        //         let sample = self.input_buffer[sample_index];
-        //         let (bpf, bsf, hpf, lpf) = channel_filter.process_multi_out(sample);
-        //         self.bpf_out_buffer[sample_index] = bpf;
-        //         self.bsf_out_buffer[sample_index] = bsf;
-        //         self.hpf_out_buffer[sample_index] = hpf;
-        //         self.lpf_out_buffer[sample_index] = lpf;
-
-        //         channel_offset += self.buffer_frame_length;
-        //     }
-        // }
+    pub fn process(&mut self, frames: usize) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let f_c = &self.f_c_buffer[..frames];
+        let q = &self.q_buffer[..frames];
+        // an unmodulated block runs on the coefficients of each filter
+        let modulated = f_c.iter().zip(q).fold(false, |modulated, (f, q_n)| {
+            modulated | (*f != f_c[0]) | (*q_n != q[0])
+        });
+        if modulated {
+            VASVFilter::calc_frame_coeffs(f_c, q, self.sample_rate_inv, &mut self.coeffs);
+        }
 
         let mut channel_offset = 0;
         for channel in 0..self.channel_count {
             let channel_filter = &mut self.filters[channel];
+            let outputs = (
+                &mut self.bpf_out_buffer[channel_offset..channel_offset + frames],
+                &mut self.bsf_out_buffer[channel_offset..channel_offset + frames],
+                &mut self.hpf_out_buffer[channel_offset..channel_offset + frames],
+                &mut self.lpf_out_buffer[channel_offset..channel_offset + frames],
+            );
+            let input = &self.input_buffer[channel_offset..channel_offset + frames];
 
-            for n in 0..self.buffer_frame_length {
-                channel_filter.set_params(self.f_c_buffer[n], self.q_buffer[n], 1.0, 1.0, 1.0, 1.0);
-                let sample_index = channel_offset + n;
-                let sample = self.input_buffer[sample_index];
-                let (bpf, bsf, hpf, lpf) = channel_filter.process_multi_out(sample);
-                self.bpf_out_buffer[sample_index] = bpf;
-                self.bsf_out_buffer[sample_index] = bsf;
-                self.hpf_out_buffer[sample_index] = hpf;
-                self.lpf_out_buffer[sample_index] = lpf;
+            if modulated {
+                for (n, sample) in input.iter().enumerate() {
+                    let (bpf, bsf, hpf, lpf) =
+                        channel_filter.process_multi_out_with(*sample, &self.coeffs, n);
+                    outputs.0[n] = bpf;
+                    outputs.1[n] = bsf;
+                    outputs.2[n] = hpf;
+                    outputs.3[n] = lpf;
+                }
+            } else if let (Some(f_c), Some(q)) = (f_c.first(), q.first()) {
+                channel_filter.set_params(*f_c, *q, 1.0, 1.0, 1.0, 1.0);
+                for (n, sample) in input.iter().enumerate() {
+                    let (bpf, bsf, hpf, lpf) = channel_filter.process_multi_out(*sample);
+                    outputs.0[n] = bpf;
+                    outputs.1[n] = bsf;
+                    outputs.2[n] = hpf;
+                    outputs.3[n] = lpf;
+                }
             }
             channel_offset += self.buffer_frame_length;
         }
@@ -93,4 +122,69 @@ impl MultiFilter {
     pub fn reset(&mut self) {
         self.filters.iter_mut().for_each(VASVFilter::reset);
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+        self.filters
+            .iter_mut()
+            .for_each(|filter| filter.set_sample_rate(sample_rate));
+
+        self.channel_count = channel_count;
+        self.filters
+            .resize_with(channel_count, || VASVFilter::new(sample_rate));
+        for buffer in [
+            &mut self.input_buffer,
+            &mut self.bpf_out_buffer,
+            &mut self.bsf_out_buffer,
+            &mut self.hpf_out_buffer,
+            &mut self.lpf_out_buffer,
+        ] {
+            buffer.resize(self.buffer_frame_length * channel_count, 0.0);
+        }
+    }
+}
+
+impl Processor for MultiFilter {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input", "cutoff", "q"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["bpf", "bsf", "hpf", "lpf"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            1 => &mut self.f_c_buffer,
+            2 => &mut self.q_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.bpf_out_buffer,
+            1 => &self.bsf_out_buffer,
+            2 => &self.hpf_out_buffer,
+            3 => &self.lpf_out_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, _params: &[f32]) {
+        MultiFilter::process(self, frames);
+    }
+
+    fn reset(&mut self) {
+        MultiFilter::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        MultiFilter::reconfigure(self, sample_rate, channel_count);
+    }
 }
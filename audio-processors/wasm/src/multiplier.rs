@@ -1,10 +1,12 @@
 use wasm_bindgen::prelude::*;
 use wasm_utils::IOBufferPtrs;
 
+use crate::{dsp::simd, invalid_buffer_index, processor::Processor};
+
 #[wasm_bindgen]
 #[derive(IOBufferPtrs)]
 pub struct Multiplier {
-    // buffer_frame_length: usize,
+    buffer_frame_length: usize,
     // channel_count: usize,
 
     // IO buffers
@@ -22,7 +24,7 @@ impl Multiplier {
     pub fn new(buffer_frame_length: usize, channel_count: usize) -> Multiplier {
         crate::utils::set_panic_hook();
         Multiplier {
-            // buffer_frame_length,
+            buffer_frame_length,
             // channel_count,
             input_1_buffer: vec![0.0; buffer_frame_length * channel_count],
             input_2_buffer: vec![0.0; buffer_frame_length * channel_count],
@@ -30,16 +32,70 @@ impl Multiplier {
         }
     }
 
-    pub fn process(&mut self) {
-        self.input_1_buffer
-            .iter()
-            .zip(self.input_2_buffer.iter())
-            .map(|(in1, in2)| in1 * in2)
-            .zip(self.output_buffer.iter_mut())
-            .for_each(|(product, out)| *out = product);
+    pub fn process(&mut self, frames: usize) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let mut channel_offset = 0;
+        while channel_offset < self.output_buffer.len() {
+            let block = channel_offset..channel_offset + frames;
+            simd::multiply(
+                &mut self.output_buffer[block.clone()],
+                &self.input_1_buffer[block.clone()],
+                &self.input_2_buffer[block],
+            );
+            channel_offset += self.buffer_frame_length;
+        }
 
         // for n in 0..(self.buffer_frame_length * self.channel_count) {
         //     self.output_buffer[n] = self.input_1_buffer[n] * self.input_2_buffer[n]
         // }
     }
+
+    pub fn reconfigure(&mut self, _sample_rate: f32, channel_count: usize) {
+        self.input_1_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.input_2_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+impl Processor for Multiplier {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input_1", "input_2"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_1_buffer,
+            1 => &mut self.input_2_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, _params: &[f32]) {
+        Multiplier::process(self, frames);
+    }
+
+    fn reset(&mut self) {}
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        Multiplier::reconfigure(self, sample_rate, channel_count);
+    }
 }
@@ -0,0 +1,320 @@
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    block_param,
+    dsp::{
+        noise::{BrownianNoiseGenerator, WhiteNoiseGenerator},
+        pinking_filter::PinkingFilter,
+        random::{derive_seed, DEFAULT_SEED},
+        smoothed_value::SmoothedValue,
+    },
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
+    utils,
+};
+
+// brings the differentiated (blue, violet) noise close to the level of white
+const BLUE_GAIN: f32 = 4.0;
+const VIOLET_GAIN: f32 = 0.5;
+
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "noiseType", min = 0, max = 4, default = 0, rate = "k-rate")]
+#[param(name = "stereo", min = 0, max = 1, default = 0, rate = "k-rate")]
+pub struct NoiseGenerator {
+    buffer_frame_length: usize,
+    channel_count: usize,
+
+    seed: u32,
+    channels: Vec<NoiseChannel>,
+
+    noise_type: NoiseType,
+    previous_noise_type: NoiseType,
+    crossfade: SmoothedValue,
+
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl NoiseGenerator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        buffer_frame_length: usize,
+        sample_rate: f32,
+        channel_count: usize,
+    ) -> NoiseGenerator {
+        utils::set_panic_hook();
+
+        NoiseGenerator {
+            buffer_frame_length,
+            channel_count,
+
+            seed: DEFAULT_SEED,
+            channels: (0..channel_count)
+                .map(|channel| NoiseChannel::new(buffer_frame_length, DEFAULT_SEED, channel))
+                .collect(),
+
+            noise_type: NoiseType::White,
+            previous_noise_type: NoiseType::White,
+            crossfade: linear_smoothed_value!(1.0, sample_rate, 0.05),
+
+            output_buffer: vec![0.0; buffer_frame_length * channel_count],
+        }
+    }
+
+    /// With `stereo` set, every channel runs its own generators and is
+    /// decorrelated from the others; otherwise the first channel is copied.
+    pub fn process(&mut self, frames: usize, noise_type: u8, stereo: bool) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let noise_type = NoiseType::from(noise_type);
+        if noise_type != self.noise_type {
+            self.previous_noise_type = self.noise_type;
+            self.noise_type = noise_type;
+            self.crossfade.set_current_and_target_value(0.0);
+            self.crossfade.set_target_value(1.0);
+        }
+
+        let generated_channels = if stereo { self.channel_count } else { 1 };
+
+        for n in 0..frames {
+            let crossfade = self.crossfade.get_next_value();
+
+            let mut channel_offset = 0;
+            for channel in &mut self.channels[..generated_channels] {
+                let colours = channel.next();
+                let y = colours.get(self.noise_type);
+                self.output_buffer[channel_offset + n] = if crossfade < 1.0 {
+                    let previous = colours.get(self.previous_noise_type);
+                    previous + crossfade * (y - previous)
+                } else {
+                    y
+                };
+                channel_offset += self.buffer_frame_length;
+            }
+        }
+
+        let (first, rest) = self.output_buffer.split_at_mut(self.buffer_frame_length);
+        for channel in rest
+            .chunks_exact_mut(self.buffer_frame_length)
+            .skip(generated_channels - 1)
+        {
+            channel[..frames].copy_from_slice(&first[..frames]);
+        }
+    }
+
+    /// Restarts the noise of every channel from `seed`; the same seed always
+    /// yields the same output.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+        for (channel, noise_channel) in self.channels.iter_mut().enumerate() {
+            noise_channel.set_seed(seed, channel);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.channels.iter_mut().for_each(NoiseChannel::reset);
+        self.crossfade.reset();
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.crossfade.set_sample_rate(sample_rate);
+
+        self.channel_count = channel_count;
+        let buffer_frame_length = self.buffer_frame_length;
+        let seed = self.seed;
+        let mut channel = self.channels.len();
+        self.channels.resize_with(channel_count, || {
+            channel += 1;
+            NoiseChannel::new(buffer_frame_length, seed, channel - 1)
+        });
+        self.output_buffer
+            .resize(buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+/// Values are kept from the former JS generator, which only had white and
+/// brownian noise.
+#[derive(Copy, Clone, PartialEq)]
+pub enum NoiseType {
+    White,
+    Brown,
+    Pink,
+    Blue,
+    Violet,
+}
+
+impl From<u8> for NoiseType {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => NoiseType::Brown,
+            2 => NoiseType::Pink,
+            3 => NoiseType::Blue,
+            4 => NoiseType::Violet,
+            _ => NoiseType::White,
+        }
+    }
+}
+
+struct NoiseColours {
+    white: f32,
+    pink: f32,
+    brown: f32,
+    blue: f32,
+    violet: f32,
+}
+
+impl NoiseColours {
+    #[inline(always)]
+    fn get(&self, noise_type: NoiseType) -> f32 {
+        match noise_type {
+            NoiseType::White => self.white,
+            NoiseType::Pink => self.pink,
+            NoiseType::Brown => self.brown,
+            NoiseType::Blue => self.blue,
+            NoiseType::Violet => self.violet,
+        }
+    }
+}
+
+/// All colours of one channel derive from the same white noise: pink through
+/// the pinking filter, blue and violet as the first difference of pink and
+/// white.
+struct NoiseChannel {
+    white: WhiteNoiseGenerator,
+    brownian: BrownianNoiseGenerator,
+    pinking_filter: PinkingFilter,
+
+    last_white: f32,
+    last_pink: f32,
+}
+
+impl NoiseChannel {
+    fn new(buffer_frame_length: usize, seed: u32, channel: usize) -> NoiseChannel {
+        let (white_seed, brownian_seed) = NoiseChannel::seeds(seed, channel);
+        NoiseChannel {
+            white: WhiteNoiseGenerator::new(white_seed),
+            brownian: BrownianNoiseGenerator::new(buffer_frame_length, brownian_seed),
+            pinking_filter: PinkingFilter::new(),
+
+            last_white: 0.0,
+            last_pink: 0.0,
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> NoiseColours {
+        let white = self.white.next();
+        let pink = self.pinking_filter.process(white);
+
+        let colours = NoiseColours {
+            white,
+            pink,
+            brown: self.brownian.next(),
+            blue: BLUE_GAIN * (pink - self.last_pink),
+            violet: VIOLET_GAIN * (white - self.last_white),
+        };
+
+        self.last_white = white;
+        self.last_pink = pink;
+        colours
+    }
+
+    fn seeds(seed: u32, channel: usize) -> (u32, u32) {
+        let stream = 2 * channel as u32;
+        (derive_seed(seed, stream), derive_seed(seed, stream + 1))
+    }
+
+    fn set_seed(&mut self, seed: u32, channel: usize) {
+        let (white_seed, brownian_seed) = NoiseChannel::seeds(seed, channel);
+        self.white.set_seed(white_seed);
+        self.brownian.set_seed(brownian_seed);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.white.reset();
+        self.brownian.reset();
+        self.pinking_filter.reset();
+        self.last_white = 0.0;
+        self.last_pink = 0.0;
+    }
+}
+
+impl Processor for NoiseGenerator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        invalid_buffer_index!("input", index)
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        NoiseGenerator::process(
+            self,
+            frames,
+            block_param!(params, 0, "noiseType") as u8,
+            block_param!(params, 1, "stereo") != 0.0,
+        );
+    }
+
+    fn reset(&mut self) {
+        NoiseGenerator::reset(self);
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        NoiseGenerator::set_seed(self, seed);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        NoiseGenerator::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_decorrelates_channels() {
+        let mut noise = NoiseGenerator::new(128, 48000.0, 2);
+        noise.process(128, 0, false);
+        assert_eq!(noise.output_buffer[..128], noise.output_buffer[128..]);
+
+        noise.process(128, 0, true);
+        assert_ne!(noise.output_buffer[..128], noise.output_buffer[128..]);
+    }
+
+    #[test]
+    fn test_seed_reproduces_output() {
+        let mut noise = NoiseGenerator::new(128, 48000.0, 2);
+        noise.set_seed(1234);
+        noise.process(128, NoiseType::White as u8, true);
+        let first = noise.output_buffer.clone();
+
+        noise.process(128, NoiseType::White as u8, true);
+        assert_ne!(first, noise.output_buffer);
+
+        noise.set_seed(1234);
+        noise.process(128, NoiseType::White as u8, true);
+        assert_eq!(first, noise.output_buffer);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::processor::{channel_count, Processor};
+
+/// Drives a [`Processor`] block by block outside of an AudioWorklet, feeding
+/// its input buffers from whole signals and collecting the outputs.
+pub struct OfflineRenderer<P: Processor> {
+    processor: P,
+    // per input buffer: one signal per channel, `None` leaves the buffer as is
+    inputs: Vec<Option<Vec<Vec<f32>>>>,
+    params: Vec<f32>,
+}
+
+impl<P: Processor> OfflineRenderer<P> {
+    pub fn new(processor: P) -> OfflineRenderer<P> {
+        let input_count = processor.inputs().len();
+        OfflineRenderer {
+            processor,
+            inputs: vec![None; input_count],
+            params: Vec::new(),
+        }
+    }
+
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    pub fn into_processor(self) -> P {
+        self.processor
+    }
+
+    /// Sets the signal streamed into input buffer `index`. Missing channels
+    /// repeat the last given one, samples past the end of a signal are zero.
+    pub fn set_input(&mut self, index: usize, channels: Vec<Vec<f32>>) {
+        assert!(!channels.is_empty());
+        self.inputs[index] = Some(channels);
+    }
+
+    /// Fills input buffer `index` with a constant value once, e.g. for
+    /// audio-rate parameter buffers that do not need automation.
+    pub fn set_constant_input(&mut self, index: usize, value: f32) {
+        self.inputs[index] = None;
+        self.processor.input_buffer(index).fill(value);
+    }
+
+    pub fn set_params(&mut self, params: &[f32]) {
+        self.params = params.to_vec();
+    }
+
+    /// Renders `frames` frames and returns them per output buffer and channel.
+    pub fn render(&mut self, frames: usize) -> Vec<Vec<Vec<f32>>> {
+        let block_length = self.processor.buffer_frame_length();
+
+        let mut outputs: Vec<Vec<Vec<f32>>> = (0..self.processor.outputs().len())
+            .map(|index| {
+                let channels = channel_count(self.processor.output_buffer(index), block_length);
+                vec![Vec::with_capacity(frames); channels]
+            })
+            .collect();
+
+        let mut block_start = 0;
+        while block_start < frames {
+            let block_frames = block_length.min(frames - block_start);
+
+            for (index, signal) in self.inputs.iter().enumerate() {
+                if let Some(signal) = signal {
+                    Self::fill_input(
+                        self.processor.input_buffer(index),
+                        signal,
+                        block_start,
+                        block_length,
+                        block_frames,
+                    );
+                }
+            }
+
+            self.processor.process(block_frames, &self.params);
+
+            for (index, output) in outputs.iter_mut().enumerate() {
+                let buffer = self.processor.output_buffer(index);
+                for (channel, out) in output.iter_mut().enumerate() {
+                    let offset = channel * block_length;
+                    out.extend_from_slice(&buffer[offset..offset + block_frames]);
+                }
+            }
+
+            block_start += block_length;
+        }
+
+        outputs
+    }
+
+    fn fill_input(
+        buffer: &mut [f32],
+        signal: &[Vec<f32>],
+        block_start: usize,
+        block_length: usize,
+        block_frames: usize,
+    ) {
+        for (channel, channel_buffer) in buffer.chunks_mut(block_length).enumerate() {
+            let channel_signal = &signal[channel.min(signal.len() - 1)];
+            for (n, sample) in channel_buffer[..block_frames].iter_mut().enumerate() {
+                *sample = channel_signal.get(block_start + n).copied().unwrap_or(0.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{multi_filter::MultiFilter, multiplier::Multiplier, phaser::Phaser};
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn test_render_partial_block() {
+        let mut renderer = OfflineRenderer::new(Multiplier::new(128, 2));
+        renderer.set_input(0, vec![(0..300).map(|n| n as f32).collect()]);
+        renderer.set_input(1, vec![vec![2.0; 300], vec![-1.0; 300]]);
+
+        let outputs = renderer.render(300);
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].len(), 2);
+        assert_eq!(outputs[0][0].len(), 300);
+        assert_eq!(outputs[0][0][299], 598.0);
+        assert_eq!(outputs[0][1][299], -299.0);
+    }
+
+    #[test]
+    fn test_lowpass_attenuates_nyquist() {
+        let mut renderer = OfflineRenderer::new(MultiFilter::new(128, SAMPLE_RATE, 1));
+        renderer.set_constant_input(1, 500.0);
+        renderer.set_constant_input(2, 0.707);
+        renderer.set_input(
+            0,
+            vec![(0..4096)
+                .map(|n| if n % 2 == 0 { 1.0 } else { -1.0 })
+                .collect()],
+        );
+
+        let lpf = renderer.processor().output_index("lpf").unwrap();
+        let outputs = renderer.render(4096);
+        let peak = outputs[lpf][0][2048..]
+            .iter()
+            .fold(0.0_f32, |max, s| max.max(s.abs()));
+        assert!(peak < 0.01, "peak {}", peak);
+    }
+
+    #[test]
+    fn test_reset_renders_identically() {
+        let mut renderer = OfflineRenderer::new(Phaser::new(128, SAMPLE_RATE, 2));
+        renderer.set_constant_input(1, 2.0);
+        renderer.set_input(
+            0,
+            vec![(0..2048).map(|n| (n as f32 * 0.05).sin()).collect()],
+        );
+
+        let first = renderer.render(2048);
+        renderer.processor_mut().reset();
+        let second = renderer.render(2048);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_block_size_independent() {
+        let input: Vec<f32> = (0..1000).map(|n| (n as f32 * 0.37).sin()).collect();
+        let render = |block_length| {
+            let mut renderer = OfflineRenderer::new(MultiFilter::new(block_length, SAMPLE_RATE, 2));
+            renderer.set_constant_input(1, 2000.0);
+            renderer.set_constant_input(2, 4.0);
+            renderer.set_input(0, vec![input.clone()]);
+            renderer.render(input.len())
+        };
+
+        assert_eq!(render(128), render(48));
+    }
+
+    #[test]
+    fn test_reconfigure_matches_new() {
+        let input: Vec<f32> = (0..1000).map(|n| (n as f32 * 0.37).sin()).collect();
+        let render = |filter: MultiFilter| {
+            let mut renderer = OfflineRenderer::new(filter);
+            renderer.set_constant_input(1, 500.0);
+            renderer.set_constant_input(2, 2.0);
+            renderer.set_input(0, vec![input.clone()]);
+            renderer.render(input.len())
+        };
+
+        let mut reconfigured = MultiFilter::new(128, 44100.0, 1);
+        reconfigured.reconfigure(SAMPLE_RATE, 2);
+        assert_eq!(
+            render(reconfigured),
+            render(MultiFilter::new(128, SAMPLE_RATE, 2))
+        );
+    }
+}
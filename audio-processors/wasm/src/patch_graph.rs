@@ -0,0 +1,651 @@
+use std::convert::TryFrom;
+
+use wasm_bindgen::prelude::*;
+use wasm_utils::IOBufferPtrs;
+
+use crate::{
+    adsr::ADSR,
+    bit_crusher::BitCrusher,
+    decimator::Decimator,
+    envelope_generator::EnvelopeGenerator,
+    fm_oscillator::FMOscillator,
+    invalid_buffer_index,
+    karplus_strong::KarplusStrong,
+    lfo::LFO,
+    multi_filter::MultiFilter,
+    multiplier::Multiplier,
+    phase_distortion_oscillator::PhaseDistortionOscillator,
+    phaser::Phaser,
+    processor::{channel_count, Processor},
+    sequencer::Sequencer,
+    unison_oscillator::UnisonOscillator,
+    utils,
+    vco::VCO,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatchNodeKind {
+    ADSR,
+    BitCrusher,
+    Decimator,
+    EnvelopeGenerator,
+    FMOscillator,
+    LFO,
+    MultiFilter,
+    Multiplier,
+    Phaser,
+    Sequencer,
+    VCO,
+    UnisonOscillator,
+    PhaseDistortionOscillator,
+    KarplusStrong,
+}
+
+impl TryFrom<u8> for PatchNodeKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PatchNodeKind::ADSR),
+            1 => Ok(PatchNodeKind::BitCrusher),
+            2 => Ok(PatchNodeKind::Decimator),
+            3 => Ok(PatchNodeKind::EnvelopeGenerator),
+            4 => Ok(PatchNodeKind::FMOscillator),
+            5 => Ok(PatchNodeKind::LFO),
+            6 => Ok(PatchNodeKind::MultiFilter),
+            7 => Ok(PatchNodeKind::Multiplier),
+            8 => Ok(PatchNodeKind::Phaser),
+            9 => Ok(PatchNodeKind::Sequencer),
+            10 => Ok(PatchNodeKind::VCO),
+            11 => Ok(PatchNodeKind::UnisonOscillator),
+            12 => Ok(PatchNodeKind::PhaseDistortionOscillator),
+            13 => Ok(PatchNodeKind::KarplusStrong),
+            _ => Err(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Connection {
+    from_node: usize,
+    from_output: usize,
+    to_node: usize,
+    to_input: usize,
+}
+
+struct PatchNode {
+    processor: Box<dyn Processor>,
+    params: Vec<f32>,
+    // constant values of unconnected inputs, as (input, value)
+    constants: Vec<(usize, f32)>,
+}
+
+/// Runs a whole sub-patch inside one worklet. Nodes are processed in
+/// topological order, so a connection never adds a block of latency; inputs
+/// fed by several connections are summed, and channel counts are matched by
+/// repeating the source channels (mono into stereo) or dropping extra ones.
+#[wasm_bindgen]
+#[derive(IOBufferPtrs)]
+pub struct PatchGraph {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+    channel_count: usize,
+
+    nodes: Vec<PatchNode>,
+    connections: Vec<Connection>,
+    order: Vec<usize>,
+
+    // node inputs fed from the graph input, as (node, input)
+    input_routes: Vec<(usize, usize)>,
+    // node output copied to the graph output, as (node, output)
+    output_source: Option<(usize, usize)>,
+
+    #[io_buffer]
+    input_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl PatchGraph {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32, channel_count: usize) -> PatchGraph {
+        utils::set_panic_hook();
+        PatchGraph {
+            buffer_frame_length,
+            sample_rate,
+            channel_count,
+
+            nodes: Vec::new(),
+            connections: Vec::new(),
+            order: Vec::new(),
+
+            input_routes: Vec::new(),
+            output_source: None,
+
+            input_buffer: vec![0.0; buffer_frame_length * channel_count],
+            output_buffer: vec![0.0; buffer_frame_length * channel_count],
+        }
+    }
+
+    /// Adds a node of the given `PatchNodeKind` and returns its index, or
+    /// `undefined` for an unknown kind.
+    pub fn add_node(&mut self, kind: u8) -> Option<usize> {
+        PatchNodeKind::try_from(kind)
+            .ok()
+            .map(|kind| self.add(kind))
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Routes output `from_output` of `from_node` into input `to_input` of
+    /// `to_node`. Returns `false` for invalid indices or if the connection
+    /// would create a cycle.
+    pub fn connect(
+        &mut self,
+        from_node: usize,
+        from_output: usize,
+        to_node: usize,
+        to_input: usize,
+    ) -> bool {
+        if !self.has_output(from_node, from_output) || !self.has_input(to_node, to_input) {
+            return false;
+        }
+
+        let connection = Connection {
+            from_node,
+            from_output,
+            to_node,
+            to_input,
+        };
+        if self.connections.contains(&connection) {
+            return true;
+        }
+
+        self.connections.push(connection);
+        match self.sorted_nodes() {
+            Some(order) => {
+                self.order = order;
+                true
+            }
+            None => {
+                self.connections.pop();
+                false
+            }
+        }
+    }
+
+    pub fn disconnect(
+        &mut self,
+        from_node: usize,
+        from_output: usize,
+        to_node: usize,
+        to_input: usize,
+    ) {
+        let connection = Connection {
+            from_node,
+            from_output,
+            to_node,
+            to_input,
+        };
+        self.connections.retain(|c| *c != connection);
+        // removing edges keeps the graph acyclic
+        self.order = self.sorted_nodes().unwrap();
+        self.release_input(to_node, to_input);
+    }
+
+    /// Feeds the graph input into input `to_input` of `to_node`.
+    pub fn connect_input(&mut self, to_node: usize, to_input: usize) -> bool {
+        if !self.has_input(to_node, to_input) {
+            return false;
+        }
+        if !self.input_routes.contains(&(to_node, to_input)) {
+            self.input_routes.push((to_node, to_input));
+        }
+        true
+    }
+
+    pub fn disconnect_input(&mut self, to_node: usize, to_input: usize) {
+        self.input_routes
+            .retain(|route| *route != (to_node, to_input));
+        self.release_input(to_node, to_input);
+    }
+
+    /// Selects the node output that is copied to the graph output.
+    pub fn set_output(&mut self, from_node: usize, from_output: usize) -> bool {
+        if !self.has_output(from_node, from_output) {
+            return false;
+        }
+        self.output_source = Some((from_node, from_output));
+        true
+    }
+
+    /// Sets the block-rate parameters of a node, in the order of its
+    /// `process` arguments. Returns `false` for an invalid node index.
+    pub fn set_params(&mut self, node: usize, params: &[f32]) -> bool {
+        match self.nodes.get_mut(node) {
+            Some(node) => {
+                node.params = params.to_vec();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fills an unconnected node input, e.g. an audio-rate parameter buffer,
+    /// with a constant value. The input goes back to it whenever its last
+    /// connection is removed. Returns `false` for invalid indices.
+    pub fn set_constant_input(&mut self, node: usize, input: usize, value: f32) -> bool {
+        if !self.has_input(node, input) {
+            return false;
+        }
+        let node = &mut self.nodes[node];
+        node.constants
+            .retain(|(constant_input, _)| *constant_input != input);
+        node.constants.push((input, value));
+        node.processor.input_buffer(input).fill(value);
+        true
+    }
+
+    /// Seeds the random sequence of a node; nodes without randomness ignore
+    /// it. Returns `false` for an invalid node index.
+    pub fn set_seed(&mut self, node: usize, seed: u32) -> bool {
+        match self.nodes.get_mut(node) {
+            Some(node) => {
+                node.processor.set_seed(seed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn process(&mut self, frames: usize) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        for i in 0..self.order.len() {
+            let node = self.order[i];
+            self.gather_inputs(node, frames);
+
+            let PatchNode {
+                processor, params, ..
+            } = &mut self.nodes[node];
+            processor.process(frames, params);
+        }
+
+        self.output_buffer.fill(0.0);
+        if let Some((node, output)) = self.output_source {
+            mix_into(
+                &mut self.output_buffer,
+                self.nodes[node].processor.output_buffer(output),
+                self.buffer_frame_length,
+                frames,
+            );
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.processor.reset();
+        }
+    }
+
+    /// Reconfigures every node and the graph IO buffers. Constant inputs are
+    /// refilled, since resized buffers start out zeroed.
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.channel_count = channel_count;
+        for node in self.nodes.iter_mut() {
+            node.processor.reconfigure(sample_rate, channel_count);
+            for (input, value) in node.constants.iter() {
+                node.processor.input_buffer(*input).fill(*value);
+            }
+        }
+        self.input_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+    }
+}
+
+impl PatchGraph {
+    pub fn add(&mut self, kind: PatchNodeKind) -> usize {
+        let buffer_frame_length = self.buffer_frame_length;
+        let sample_rate = self.sample_rate;
+        let channel_count = self.channel_count;
+
+        let processor: Box<dyn Processor> = match kind {
+            PatchNodeKind::ADSR => Box::new(ADSR::new(buffer_frame_length, sample_rate)),
+            PatchNodeKind::BitCrusher => Box::new(BitCrusher::new(
+                buffer_frame_length,
+                sample_rate,
+                channel_count,
+            )),
+            PatchNodeKind::Decimator => Box::new(Decimator::new(
+                buffer_frame_length,
+                sample_rate,
+                channel_count,
+            )),
+            PatchNodeKind::EnvelopeGenerator => {
+                Box::new(EnvelopeGenerator::new(buffer_frame_length, sample_rate))
+            }
+            PatchNodeKind::FMOscillator => Box::new(FMOscillator::new(
+                buffer_frame_length,
+                sample_rate,
+                channel_count,
+            )),
+            PatchNodeKind::LFO => Box::new(LFO::new(buffer_frame_length, sample_rate)),
+            PatchNodeKind::MultiFilter => Box::new(MultiFilter::new(
+                buffer_frame_length,
+                sample_rate,
+                channel_count,
+            )),
+            PatchNodeKind::Multiplier => {
+                Box::new(Multiplier::new(buffer_frame_length, channel_count))
+            }
+            PatchNodeKind::Phaser => {
+                Box::new(Phaser::new(buffer_frame_length, sample_rate, channel_count))
+            }
+            PatchNodeKind::Sequencer => Box::new(Sequencer::new(buffer_frame_length)),
+            PatchNodeKind::VCO => Box::new(VCO::new(buffer_frame_length, sample_rate)),
+            PatchNodeKind::UnisonOscillator => {
+                Box::new(UnisonOscillator::new(buffer_frame_length, sample_rate))
+            }
+            PatchNodeKind::PhaseDistortionOscillator => Box::new(PhaseDistortionOscillator::new(
+                buffer_frame_length,
+                sample_rate,
+            )),
+            PatchNodeKind::KarplusStrong => {
+                Box::new(KarplusStrong::new(buffer_frame_length, sample_rate))
+            }
+        };
+
+        self.add_processor(processor)
+    }
+
+    /// Adds any processor running at the graph's block length as a node.
+    pub fn add_processor(&mut self, processor: Box<dyn Processor>) -> usize {
+        assert_eq!(processor.buffer_frame_length(), self.buffer_frame_length);
+
+        let node = self.nodes.len();
+        self.nodes.push(PatchNode {
+            processor,
+            params: Vec::new(),
+            constants: Vec::new(),
+        });
+        // a node without connections can run last
+        self.order.push(node);
+        node
+    }
+
+    pub fn node(&self, node: usize) -> &dyn Processor {
+        self.nodes[node].processor.as_ref()
+    }
+
+    fn has_input(&self, node: usize, input: usize) -> bool {
+        self.nodes
+            .get(node)
+            .is_some_and(|n| input < n.processor.inputs().len())
+    }
+
+    fn has_output(&self, node: usize, output: usize) -> bool {
+        self.nodes
+            .get(node)
+            .is_some_and(|n| output < n.processor.outputs().len())
+    }
+
+    // Inputs are only rewritten while routed, so one that lost its last
+    // route would replay its final block forever. Go back to the constant,
+    // or silence, instead.
+    fn release_input(&mut self, node: usize, input: usize) {
+        let routed = self
+            .connections
+            .iter()
+            .any(|c| c.to_node == node && c.to_input == input)
+            || self.input_routes.contains(&(node, input));
+        if routed || !self.has_input(node, input) {
+            return;
+        }
+        let node = &mut self.nodes[node];
+        let value = node
+            .constants
+            .iter()
+            .find(|(constant_input, _)| *constant_input == input)
+            .map_or(0.0, |(_, value)| *value);
+        node.processor.input_buffer(input).fill(value);
+    }
+
+    // Kahn's algorithm, `None` if the connections contain a cycle
+    fn sorted_nodes(&self) -> Option<Vec<usize>> {
+        let mut in_degree = vec![0; self.nodes.len()];
+        for connection in self.connections.iter() {
+            in_degree[connection.to_node] += 1;
+        }
+
+        let mut ready: Vec<usize> = (0..self.nodes.len())
+            .rev()
+            .filter(|&node| in_degree[node] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for connection in self.connections.iter() {
+                if connection.from_node == node {
+                    in_degree[connection.to_node] -= 1;
+                    if in_degree[connection.to_node] == 0 {
+                        ready.push(connection.to_node);
+                    }
+                }
+            }
+        }
+
+        if order.len() == self.nodes.len() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+
+    fn gather_inputs(&mut self, node: usize, frames: usize) {
+        let buffer_frame_length = self.buffer_frame_length;
+
+        let routed_inputs = self
+            .connections
+            .iter()
+            .filter(|c| c.to_node == node)
+            .map(|c| c.to_input)
+            .chain(
+                self.input_routes
+                    .iter()
+                    .filter(|(to_node, _)| *to_node == node)
+                    .map(|(_, to_input)| *to_input),
+            );
+        for input in routed_inputs {
+            self.nodes[node].processor.input_buffer(input).fill(0.0);
+        }
+
+        for &(to_node, to_input) in self.input_routes.iter() {
+            if to_node == node {
+                mix_into(
+                    self.nodes[node].processor.input_buffer(to_input),
+                    &self.input_buffer,
+                    buffer_frame_length,
+                    frames,
+                );
+            }
+        }
+
+        for connection in self.connections.iter() {
+            if connection.to_node == node {
+                let (from, to) = node_pair(&mut self.nodes, connection.from_node, node);
+                mix_into(
+                    to.processor.input_buffer(connection.to_input),
+                    from.processor.output_buffer(connection.from_output),
+                    buffer_frame_length,
+                    frames,
+                );
+            }
+        }
+    }
+}
+
+// `from` and `to` are never equal since self-connections are cycles
+fn node_pair(nodes: &mut [PatchNode], from: usize, to: usize) -> (&PatchNode, &mut PatchNode) {
+    if from < to {
+        let (head, tail) = nodes.split_at_mut(to);
+        (&head[from], &mut tail[0])
+    } else {
+        let (head, tail) = nodes.split_at_mut(from);
+        (&tail[0], &mut head[to])
+    }
+}
+
+fn mix_into(destination: &mut [f32], source: &[f32], buffer_frame_length: usize, frames: usize) {
+    let source_channels = channel_count(source, buffer_frame_length);
+    for (channel, destination_channel) in destination.chunks_mut(buffer_frame_length).enumerate() {
+        let offset = (channel % source_channels) * buffer_frame_length;
+        destination_channel
+            .iter_mut()
+            .take(frames)
+            .zip(source[offset..offset + frames].iter())
+            .for_each(|(out, sample)| *out += sample);
+    }
+}
+
+impl Processor for PatchGraph {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, _params: &[f32]) {
+        PatchGraph::process(self, frames);
+    }
+
+    fn reset(&mut self) {
+        PatchGraph::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        PatchGraph::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offline::OfflineRenderer;
+
+    #[test]
+    fn test_rejects_cycles() {
+        let mut graph = PatchGraph::new(128, 48000.0, 2);
+        let a = graph.add(PatchNodeKind::Multiplier);
+        let b = graph.add(PatchNodeKind::Multiplier);
+
+        assert!(graph.connect(a, 0, b, 0));
+        assert!(!graph.connect(b, 0, a, 1));
+        assert!(!graph.connect(a, 0, a, 1));
+        assert!(!graph.connect(a, 1, b, 0));
+
+        graph.disconnect(a, 0, b, 0);
+        assert!(graph.connect(b, 0, a, 1));
+    }
+
+    #[test]
+    fn test_processes_in_topological_order() {
+        let mut graph = PatchGraph::new(128, 48000.0, 2);
+        // added before its source, still runs after it
+        let last = graph.add(PatchNodeKind::Multiplier);
+        let first = graph.add(PatchNodeKind::Multiplier);
+
+        graph.set_constant_input(first, 1, 2.0);
+        graph.set_constant_input(last, 1, 3.0);
+        assert!(graph.connect_input(first, 0));
+        assert!(graph.connect_input(last, 0));
+        assert!(graph.connect(first, 0, last, 0));
+        assert!(graph.set_output(last, 0));
+
+        let mut renderer = OfflineRenderer::new(graph);
+        renderer.set_input(0, vec![(0..300).map(|n| n as f32).collect()]);
+        let outputs = renderer.render(300);
+
+        // (x * 2 + x) * 3, without a block of delay per connection
+        for channel in outputs[0].iter() {
+            for (n, sample) in channel.iter().enumerate() {
+                assert_eq!(*sample, n as f32 * 9.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_disconnected_inputs_fall_back() {
+        let mut graph = PatchGraph::new(128, 48000.0, 2);
+        let source = graph.add(PatchNodeKind::Multiplier);
+        let sink = graph.add(PatchNodeKind::Multiplier);
+
+        graph.set_constant_input(source, 1, 1.0);
+        graph.set_constant_input(sink, 0, 1.0);
+        graph.set_constant_input(sink, 1, 0.5);
+        assert!(graph.connect_input(source, 0));
+        assert!(graph.connect(source, 0, sink, 0));
+        assert!(graph.connect_input(sink, 1));
+        assert!(graph.set_output(sink, 0));
+
+        graph.input_buffer.fill(4.0);
+        graph.process(128);
+        assert!(graph.output_buffer.iter().all(|y| *y == 16.0));
+
+        // no longer the last block of 4s, but the constants
+        graph.disconnect(source, 0, sink, 0);
+        graph.disconnect_input(sink, 1);
+        graph.process(128);
+        assert!(graph.output_buffer.iter().all(|y| *y == 0.5));
+
+        // an input without a constant goes silent
+        graph.disconnect_input(source, 0);
+        assert!(graph.connect(source, 0, sink, 0));
+        graph.process(128);
+        assert!(graph.output_buffer.iter().all(|y| *y == 0.0));
+
+        assert!(!graph.set_params(2, &[]));
+        assert!(!graph.set_constant_input(sink, 2, 1.0));
+        assert!(!graph.set_seed(2, 1));
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_constant_inputs() {
+        let mut graph = PatchGraph::new(128, 48000.0, 1);
+        let node = graph.add(PatchNodeKind::Multiplier);
+        graph.set_constant_input(node, 0, 2.0);
+        graph.set_constant_input(node, 1, 0.25);
+        assert!(graph.set_output(node, 0));
+
+        graph.reconfigure(44100.0, 2);
+        graph.process(128);
+        assert_eq!(graph.output_buffer.len(), 256);
+        assert!(graph.output_buffer.iter().all(|y| *y == 0.5));
+    }
+}
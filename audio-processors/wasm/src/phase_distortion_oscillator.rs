@@ -0,0 +1,212 @@
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    block_param,
+    dsp::{phase_counter::PhaseCounter, waveforms},
+    invalid_buffer_index,
+    processor::Processor,
+    utils,
+};
+
+// keeps the warped segments from getting infinitely steep
+const MAX_AMOUNT: f32 = 0.98;
+// resonance frequency at full amount, relative to the fundamental
+const MAX_RESONANCE: f32 = 16.0;
+
+/// Casio CZ style phase distortion: a cosine read through a warped phase.
+/// At zero amount every warp but the resonant one reads a plain cosine.
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.0, max = 20000.0, default = 440.0)]
+#[param(name = "amount", min = 0.0, max = 1.0, default = 0.0)]
+#[param(name = "waveform", min = 0, max = 3, default = 0, rate = "k-rate")]
+pub struct PhaseDistortionOscillator {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+    sample_rate_inv: f32,
+
+    phase_counter: PhaseCounter,
+
+    // parameter buffers
+    #[io_buffer]
+    frequency_buffer: Vec<f32>,
+    #[io_buffer]
+    amount_buffer: Vec<f32>,
+
+    // IO buffers
+    // 1V/oct: the frequency is scaled by `2^pitch`
+    #[io_buffer]
+    pitch_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl PhaseDistortionOscillator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32) -> PhaseDistortionOscillator {
+        utils::set_panic_hook();
+
+        PhaseDistortionOscillator {
+            buffer_frame_length,
+            sample_rate,
+            sample_rate_inv: 1.0 / sample_rate,
+
+            phase_counter: PhaseCounter::new(),
+
+            frequency_buffer: vec![0.0; buffer_frame_length],
+            amount_buffer: vec![0.0; buffer_frame_length],
+
+            pitch_buffer: vec![0.0; buffer_frame_length],
+            output_buffer: vec![0.0; buffer_frame_length],
+        }
+    }
+
+    pub fn process(&mut self, frames: usize, waveform: u8) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let waveform = PhaseDistortionWaveform::from(waveform);
+        let nyquist = 0.5 * self.sample_rate;
+
+        for n in 0..frames {
+            let frequency = self.frequency_buffer[n] * self.pitch_buffer[n].exp2();
+            let increment = frequency.clamp(0.0, nyquist) * self.sample_rate_inv;
+            let arg = self.phase_counter.advance(increment);
+
+            let amount = self.amount_buffer[n].clamp(0.0, 1.0);
+            self.output_buffer[n] = match waveform {
+                PhaseDistortionWaveform::Saw => cos(warp_saw(arg, amount)),
+                PhaseDistortionWaveform::Square => cos(warp_square(arg, amount)),
+                PhaseDistortionWaveform::Pulse => cos(warp_pulse(arg, amount)),
+                PhaseDistortionWaveform::Resonant => {
+                    // a cosine at the resonance frequency, restarted every
+                    // cycle and faded out towards its end by a falling saw
+                    let resonance = 1.0 + amount * (MAX_RESONANCE - 1.0);
+                    let window = 1.0 - arg;
+                    1.0 - window * (1.0 - cos((arg * resonance).fract()))
+                }
+            };
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase_counter.reset();
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.sample_rate_inv = 1.0 / sample_rate;
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum PhaseDistortionWaveform {
+    Saw,
+    Square,
+    Pulse,
+    Resonant,
+}
+
+impl From<u8> for PhaseDistortionWaveform {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => PhaseDistortionWaveform::Square,
+            2 => PhaseDistortionWaveform::Pulse,
+            3 => PhaseDistortionWaveform::Resonant,
+            _ => PhaseDistortionWaveform::Saw,
+        }
+    }
+}
+
+#[inline(always)]
+fn cos(arg: f32) -> f32 {
+    waveforms::sine((arg + 0.25).fract())
+}
+
+/// Races through the first half of the cosine and crawls through the second.
+#[inline(always)]
+fn warp_saw(arg: f32, amount: f32) -> f32 {
+    let knee = 0.5 - 0.5 * MAX_AMOUNT * amount;
+    if arg < knee {
+        0.5 * arg / knee
+    } else {
+        0.5 + 0.5 * (arg - knee) / (1.0 - knee)
+    }
+}
+
+/// Runs through each half of the cosine early and holds at its extremes.
+#[inline(always)]
+fn warp_square(arg: f32, amount: f32) -> f32 {
+    let width = 1.0 - MAX_AMOUNT * amount;
+    let half = if arg < 0.5 { 0.0 } else { 0.5 };
+    let rise = (2.0 * (arg - half) / width).min(1.0);
+    half + 0.5 * rise
+}
+
+/// Runs through the whole cosine early and rests at its top.
+#[inline(always)]
+fn warp_pulse(arg: f32, amount: f32) -> f32 {
+    let width = 1.0 - MAX_AMOUNT * amount;
+    (arg / width).min(1.0).fract()
+}
+
+impl Processor for PhaseDistortionOscillator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "amount", "pitch"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.amount_buffer,
+            2 => &mut self.pitch_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        PhaseDistortionOscillator::process(self, frames, block_param!(params, 0, "waveform") as u8);
+    }
+
+    fn reset(&mut self) {
+        PhaseDistortionOscillator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        PhaseDistortionOscillator::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warps_reduce_to_cosine() {
+        for arg in (0..100).map(|n| n as f32 / 100.0) {
+            let expected = (std::f32::consts::TAU * arg).cos();
+            assert!((cos(warp_saw(arg, 0.0)) - expected).abs() < 1e-3);
+            assert!((cos(warp_square(arg, 0.0)) - expected).abs() < 1e-3);
+            assert!((cos(warp_pulse(arg, 0.0)) - expected).abs() < 1e-3);
+        }
+        // fully warped, the square holds at its extremes
+        assert_eq!(warp_square(0.3, 1.0), 0.5);
+        assert_eq!(warp_square(0.8, 1.0), 1.0);
+    }
+}
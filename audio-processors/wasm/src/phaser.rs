@@ -1,12 +1,23 @@
-use crate::dsp::{
-    allpass_filter::AllpassFilter, fast_math, phase_counter::PhaseCounter, vasv_filter::VASVFilter,
+use crate::{
+    dsp::{
+        allpass_filter::AllpassFilter,
+        phase_counter::PhaseCounter,
+        simd::{self, F32x4, LANES},
+        waveforms,
+    },
+    invalid_buffer_index,
+    processor::Processor,
 };
-use std::f32::consts::TAU as TWO_PI;
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+const F_MIN: f32 = 160.0;
+const F_MAX: f32 = 16000.0;
+const FDBK: f32 = 0.25;
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "rate", min = 0.1, max = 20.0, default = 0.5, rate = "a-rate")]
 pub struct Phaser {
     buffer_frame_length: usize,
     channel_count: usize,
@@ -19,6 +30,11 @@ pub struct Phaser {
     stage3_apfs: Vec<AllpassFilter>,
     stage4_apfs: Vec<AllpassFilter>,
 
+    // per-frame values shared by all channels, padded to whole lane groups
+    f_c_buffer: Vec<f32>,
+    alpha_buffer: Vec<f32>,
+    alpha0_buffer: Vec<f32>,
+
     // IO buffers
     #[io_buffer]
     input_buffer: Vec<f32>,
@@ -30,8 +46,6 @@ pub struct Phaser {
     rate_buffer: Vec<f32>,
 }
 
-const ONE_THIRD: f32 = 1.0 / 3.0;
-
 #[wasm_bindgen]
 impl Phaser {
     #[wasm_bindgen(constructor)]
@@ -43,11 +57,12 @@ impl Phaser {
         let mut stage3_apfs: Vec<AllpassFilter> = Vec::with_capacity(channel_count);
         let mut stage4_apfs: Vec<AllpassFilter> = Vec::with_capacity(channel_count);
         for _ in 0..channel_count {
-            stage1_apfs.push(AllpassFilter::new(sample_rate));
-            stage2_apfs.push(AllpassFilter::new(sample_rate));
-            stage3_apfs.push(AllpassFilter::new(sample_rate));
-            stage4_apfs.push(AllpassFilter::new(sample_rate));
+            stage1_apfs.push(AllpassFilter::new());
+            stage2_apfs.push(AllpassFilter::new());
+            stage3_apfs.push(AllpassFilter::new());
+            stage4_apfs.push(AllpassFilter::new());
         }
+        let padded_len = simd::padded_len(buffer_frame_length);
         Self {
             buffer_frame_length,
             channel_count,
@@ -60,6 +75,10 @@ impl Phaser {
             stage3_apfs,
             stage4_apfs,
 
+            f_c_buffer: vec![F_MIN; padded_len],
+            alpha_buffer: vec![0.0; padded_len],
+            alpha0_buffer: vec![0.0; padded_len],
+
             input_buffer: vec![0.0; buffer_frame_length * channel_count],
             output_buffer: vec![0.0; buffer_frame_length * channel_count],
 
@@ -67,7 +86,33 @@ impl Phaser {
         }
     }
 
-    pub fn process(&mut self) {
+    pub fn process(&mut self, frames: usize) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        // one LFO step per frame, shared by all channels
+        for n in 0..frames {
+            let phase_incr = self.rate_buffer[n] * self.sample_rate_inv;
+            let arg = self.phase_counter.advance(phase_incr);
+            let bipolar = waveforms::triangle(arg, phase_incr);
+            let unipolar = bipolar.mul_add(0.5, 0.5);
+
+            // all four stages share the cutoff
+            self.f_c_buffer[n] = F_MIN + (unipolar * (F_MAX - F_MIN));
+        }
+
+        AllpassFilter::calc_alphas(
+            &self.f_c_buffer[..frames],
+            self.sample_rate_inv,
+            &mut self.alpha_buffer,
+        );
+        for n in (0..frames).step_by(LANES) {
+            let alpha = F32x4::from_slice(&self.alpha_buffer[n..]);
+            let alpha2 = alpha * alpha;
+            let gamma4 = alpha2 * alpha2;
+            let alpha0 = F32x4::splat(1.0) / (F32x4::splat(1.0) + F32x4::splat(FDBK) * gamma4);
+            alpha0.write_to_slice(&mut self.alpha0_buffer[n..]);
+        }
+
         let mut channel_offset = 0;
         for channel in 0..self.channel_count {
             let apf1 = &mut self.stage1_apfs[channel];
@@ -75,48 +120,26 @@ impl Phaser {
             let apf3 = &mut self.stage3_apfs[channel];
             let apf4 = &mut self.stage4_apfs[channel];
 
-            for n in 0..self.buffer_frame_length {
+            for n in 0..frames {
                 let sample_index = channel_offset + n;
                 let sample = self.input_buffer[sample_index];
 
-                let phase_incr = self.rate_buffer[n] * self.sample_rate_inv;
-                let arg = self.phase_counter.advance(phase_incr);
-                let bipolar = Self::triangle_wave(arg, phase_incr);
-                let unipolar = bipolar.mul_add(0.5, 0.5);
-
-                let f_min = 160.0;
-                let f_max = 16000.0;
-
-                let apf1_fc = f_min + (unipolar * (f_max - f_min));
-                apf1.set_params(apf1_fc);
-                let (alpha1, s1) = apf1.get_state();
-
-                let apf2_fc = apf1_fc; // 33.0 + (unipolar * (3300.0 - 33.0));
-                apf2.set_params(apf2_fc);
-                let (alpha2, s2) = apf2.get_state();
-
-                let apf3_fc = apf1_fc; // 48.0 + (unipolar * (4800.0 - 48.0));
-                apf3.set_params(apf3_fc);
-                let (alpha3, s3) = apf3.get_state();
+                let alpha = self.alpha_buffer[n];
+                let s1 = apf1.get_state();
+                let s2 = apf2.get_state();
+                let s3 = apf3.get_state();
+                let s4 = apf4.get_state();
 
-                let apf4_fc = apf1_fc; // 98.0 + (unipolar * (9800.0 - 98.0));
-                apf4.set_params(apf4_fc);
-                let (alpha4, s4) = apf4.get_state();
-
-                let gamma1 = alpha4;
-                let gamma2 = alpha3 * gamma1;
-                let gamma3 = alpha2 * gamma2;
-                let gamma4 = alpha1 * gamma3;
-
-                let fdbk = 0.25;
-                let alpha0 = 1.0 / (1.0 + fdbk * gamma4);
+                let gamma1 = alpha;
+                let gamma2 = alpha * gamma1;
+                let gamma3 = alpha * gamma2;
                 let s0 = gamma3 * s1 + gamma2 * s2 + gamma1 * s3 + s4;
 
-                let sample = alpha0 * (sample - fdbk * s0);
-                let apf1_out = apf1.process(sample);
-                let apf2_out = apf2.process(apf1_out);
-                let apf3_out = apf3.process(apf2_out);
-                let apf4_out = apf4.process(apf3_out);
+                let sample = self.alpha0_buffer[n] * (sample - FDBK * s0);
+                let apf1_out = apf1.process(sample, alpha);
+                let apf2_out = apf2.process(apf1_out, alpha);
+                let apf3_out = apf3.process(apf2_out, alpha);
+                let apf4_out = apf4.process(apf3_out, alpha);
 
                 self.output_buffer[sample_index] = 0.707 * (sample + apf4_out);
             }
@@ -129,45 +152,66 @@ impl Phaser {
 
         self.stage1_apfs.iter_mut().for_each(AllpassFilter::reset);
         self.stage2_apfs.iter_mut().for_each(AllpassFilter::reset);
+        self.stage3_apfs.iter_mut().for_each(AllpassFilter::reset);
+        self.stage4_apfs.iter_mut().for_each(AllpassFilter::reset);
     }
 
-    #[inline(always)]
-    fn sine_wave(arg: f32) -> f32 {
-        let arg1 = (arg + 0.25).fract();
-        if arg1 < 0.5 {
-            return fast_math::sin(TWO_PI * (arg1 - 0.25));
+    pub fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        self.sample_rate_inv = 1.0 / sample_rate;
+
+        self.channel_count = channel_count;
+        for apfs in [
+            &mut self.stage1_apfs,
+            &mut self.stage2_apfs,
+            &mut self.stage3_apfs,
+            &mut self.stage4_apfs,
+        ] {
+            apfs.resize_with(channel_count, AllpassFilter::new);
         }
-        0.0 - fast_math::sin(TWO_PI * (arg1 - 0.75))
+        self.input_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
+        self.output_buffer
+            .resize(self.buffer_frame_length * channel_count, 0.0);
     }
+}
 
-    #[inline(always)]
-    fn triangle_wave(arg: f32, increment: f32) -> f32 {
-        let arg1 = (arg + 0.25).fract();
-        let arg2 = (arg + 0.75).fract();
-        let mut y = arg * 4.0;
-        if y >= 3.0 {
-            y -= 4.0;
-        } else if y > 1.0 {
-            y = 2.0 - y;
-        }
+impl Processor for Phaser {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input", "rate"]
+    }
 
-        y +=
-            4.0 * increment * (Self::polyblamp(arg1, increment) - Self::polyblamp(arg2, increment));
-        y
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
     }
 
-    #[inline(always)]
-    fn polyblamp(arg: f32, increment: f32) -> f32 {
-        if arg < increment {
-            let t = arg / increment - 1.0;
-            return -ONE_THIRD * (t * t * t);
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            1 => &mut self.rate_buffer,
+            _ => invalid_buffer_index!("input", index),
         }
+    }
 
-        if arg > 1.0 - increment {
-            let t = (arg - 1.0) / increment + 1.0;
-            return ONE_THIRD * (t * t * t);
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
         }
+    }
+
+    fn process(&mut self, frames: usize, _params: &[f32]) {
+        Phaser::process(self, frames);
+    }
+
+    fn reset(&mut self) {
+        Phaser::reset(self);
+    }
 
-        0.0
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        Phaser::reconfigure(self, sample_rate, channel_count);
     }
 }
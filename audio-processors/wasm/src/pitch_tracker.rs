@@ -1,10 +1,14 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
 
-use crate::{dsp::smoothed_value::SmoothedValue, linear_smoothed_value, utils};
+use crate::{
+    block_param, dsp::smoothed_value::SmoothedValue, invalid_buffer_index, linear_smoothed_value,
+    processor::Processor, utils,
+};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "harmonicThreshold", min = 0.01, max = 1.0, default = 0.1)]
 pub struct PitchTracker {
     buffer_frame_length: usize,
 
@@ -46,9 +50,11 @@ impl PitchTracker {
         }
     }
 
-    pub fn process(&mut self, harmonic_threshold: f32) {
+    pub fn process(&mut self, frames: usize, harmonic_threshold: f32) {
+        let frames = frames.min(self.buffer_frame_length);
+
         self.harmonic_threshold.set_target_value(harmonic_threshold);
-        for n in 0..self.buffer_frame_length {
+        for n in 0..frames {
             self.pitch_tracker
                 .set_harmonic_threshold(self.harmonic_threshold.get_next_value());
 
@@ -63,4 +69,50 @@ impl PitchTracker {
         self.frequency.reset();
         self.harmonic_threshold.reset();
     }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.pitch_tracker.set_sample_rate(sample_rate);
+        self.frequency.set_sample_rate(sample_rate);
+        self.harmonic_threshold.set_sample_rate(sample_rate);
+    }
+}
+
+impl Processor for PitchTracker {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["input"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.input_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        PitchTracker::process(self, frames, block_param!(params, 0, "harmonicThreshold"));
+    }
+
+    fn reset(&mut self) {
+        PitchTracker::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        PitchTracker::reconfigure(self, sample_rate, channel_count);
+    }
 }
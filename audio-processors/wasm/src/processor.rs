@@ -0,0 +1,174 @@
+/// Block-based interface implemented by every processor, so they can be driven
+/// from plain Rust (offline rendering, native tests) and not only through the
+/// wasm-bindgen buffer pointers used by the AudioWorklets.
+///
+/// Buffers are laid out the same way as on the JS side: channels are stored
+/// one after another, each `buffer_frame_length` samples long. Audio-rate
+/// parameter buffers (e.g. `MultiFilter`'s cutoff) count as inputs, while
+/// block-rate parameters are passed to [`Processor::process`].
+pub trait Processor {
+    /// Frames per channel of every IO buffer, i.e. the largest block that can
+    /// be processed at once.
+    fn buffer_frame_length(&self) -> usize;
+
+    /// Names of the input buffers, in index order.
+    fn inputs(&self) -> &'static [&'static str];
+
+    /// Names of the output buffers, in index order.
+    fn outputs(&self) -> &'static [&'static str];
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32];
+
+    fn output_buffer(&self, index: usize) -> &[f32];
+
+    /// Processes the first `frames` frames of every channel, at most
+    /// `buffer_frame_length`. `params` holds the block-rate parameters in the
+    /// order of the remaining wasm-bindgen `process` arguments; missing values
+    /// fall back to the defaults of the JS parameter descriptors (see
+    /// [`block_param!`]).
+    fn process(&mut self, frames: usize, params: &[f32]);
+
+    fn reset(&mut self);
+
+    /// Switches to a new sample rate and channel count without rebuilding the
+    /// processor, keeping state that does not depend on them. Channel-sized
+    /// buffers are resized, which may move them in memory.
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize);
+
+    /// Restarts the random sequence of processors that use randomness, so
+    /// the same seed renders the same output. Deterministic processors
+    /// ignore it.
+    fn set_seed(&mut self, _seed: u32) {}
+
+    fn input_index(&self, name: &str) -> Option<usize> {
+        self.inputs().iter().position(|n| *n == name)
+    }
+
+    fn output_index(&self, name: &str) -> Option<usize> {
+        self.outputs().iter().position(|n| *n == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AutomationRate {
+    ARate,
+    KRate,
+}
+
+/// Rust side of a worklet parameter descriptor, generated by
+/// `#[derive(ParameterDescriptors)]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamDescriptor {
+    pub name: &'static str,
+    pub default_value: f32,
+    pub min_value: f32,
+    pub max_value: f32,
+    pub automation_rate: AutomationRate,
+}
+
+#[inline]
+pub fn param(params: &[f32], index: usize, default: f32) -> f32 {
+    params.get(index).copied().unwrap_or(default)
+}
+
+/// Default value of the descriptor called `name`. Panics if there is none,
+/// which fails the build when evaluated in a `const` block.
+pub const fn param_default(parameters: &[ParamDescriptor], name: &str) -> f32 {
+    let name = name.as_bytes();
+    let mut i = 0;
+    'descriptors: while i < parameters.len() {
+        let candidate = parameters[i].name.as_bytes();
+        i += 1;
+        if candidate.len() != name.len() {
+            continue;
+        }
+        let mut j = 0;
+        while j < name.len() {
+            if candidate[j] != name[j] {
+                continue 'descriptors;
+            }
+            j += 1;
+        }
+        return parameters[i - 1].default_value;
+    }
+    panic!("unknown parameter name");
+}
+
+#[inline]
+pub fn channel_count(buffer: &[f32], buffer_frame_length: usize) -> usize {
+    buffer.len() / buffer_frame_length
+}
+
+#[macro_export]
+macro_rules! invalid_buffer_index {
+    ($kind: expr, $index: expr) => {
+        panic!("{} buffer index {} out of range", $kind, $index)
+    };
+}
+
+/// Reads the block-rate parameter at `index` inside a `Processor::process`
+/// impl, falling back to the default of `Self::PARAMETERS[name]`, so the
+/// defaults are only written down in the `#[param]` attributes.
+#[macro_export]
+macro_rules! block_param {
+    ($params: expr, $index: expr, $name: literal) => {
+        $crate::processor::param(
+            $params,
+            $index,
+            const { $crate::processor::param_default(Self::PARAMETERS, $name) },
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        envelope_tracker::EnvelopeTracker, fm_voice::FMSynthesizer, multi_filter::MultiFilter,
+    };
+
+    #[test]
+    fn test_parameter_descriptors() {
+        assert_eq!(
+            MultiFilter::parameter_descriptors(),
+            r#"[{"name":"cutoff","defaultValue":1000,"minValue":20,"maxValue":20480,"automationRate":"a-rate"},{"name":"q","defaultValue":0.707,"minValue":0.707,"maxValue":20,"automationRate":"a-rate"}]"#
+        );
+        assert_eq!(
+            EnvelopeTracker::PARAMETERS[1],
+            ParamDescriptor {
+                name: "threshold",
+                default_value: -60.0,
+                min_value: -60.0,
+                max_value: 0.0,
+                automation_rate: AutomationRate::ARate,
+            }
+        );
+    }
+
+    #[test]
+    fn test_param_default() {
+        assert_eq!(
+            param_default(EnvelopeTracker::PARAMETERS, "threshold"),
+            -60.0
+        );
+        assert_eq!(param_default(FMSynthesizer::PARAMETERS, "lfoKeySync"), 1.0);
+        assert_eq!(
+            param(&[], 0, param_default(MultiFilter::PARAMETERS, "q")),
+            0.707
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown parameter name")]
+    fn test_param_default_unknown_name() {
+        param_default(MultiFilter::PARAMETERS, "cut");
+    }
+
+    #[test]
+    #[should_panic(expected = "operator_level_buffers index 6 out of range (len 6)")]
+    fn test_indexed_buffer_ptr_bounds() {
+        let mut synth = FMSynthesizer::new(128, 48000.0, 2);
+        assert!(!synth.operator_level_buffers_ptr(5).is_null());
+        synth.operator_level_buffers_ptr(6);
+    }
+}
@@ -1,8 +1,28 @@
 use wasm_bindgen::prelude::*;
-use wasm_utils::IOBufferPtrs;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{block_param, invalid_buffer_index, processor::Processor};
 
 #[wasm_bindgen]
-#[derive(IOBufferPtrs)]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "gateThreshold", min = -1, max = 1, default = 0)]
+#[param(name = "numSteps", min = 1, max = 16, default = 4)]
+#[param(name = "value0", default = 0, rate = "k-rate")]
+#[param(name = "value1", default = 0, rate = "k-rate")]
+#[param(name = "value2", default = 0, rate = "k-rate")]
+#[param(name = "value3", default = 0, rate = "k-rate")]
+#[param(name = "value4", default = 0, rate = "k-rate")]
+#[param(name = "value5", default = 0, rate = "k-rate")]
+#[param(name = "value6", default = 0, rate = "k-rate")]
+#[param(name = "value7", default = 0, rate = "k-rate")]
+#[param(name = "value8", default = 0, rate = "k-rate")]
+#[param(name = "value9", default = 0, rate = "k-rate")]
+#[param(name = "value10", default = 0, rate = "k-rate")]
+#[param(name = "value11", default = 0, rate = "k-rate")]
+#[param(name = "value12", default = 0, rate = "k-rate")]
+#[param(name = "value13", default = 0, rate = "k-rate")]
+#[param(name = "value14", default = 0, rate = "k-rate")]
+#[param(name = "value15", default = 0, rate = "k-rate")]
 pub struct Sequencer {
     current_step: usize,
     prev_gate_value: f32,
@@ -31,13 +51,14 @@ impl Sequencer {
         }
     }
 
-    pub fn process(&mut self, gate_thresh: f32, num_steps: usize) -> usize {
+    pub fn process(&mut self, frames: usize, gate_thresh: f32, num_steps: usize) -> usize {
         let steps = num_steps.min(32);
-        
+
         for (out, gate_value) in self
             .output_buffer
             .iter_mut()
             .zip(self.gate_in_buffer.iter())
+            .take(frames)
         {
             if *gate_value > gate_thresh && self.prev_gate_value <= gate_thresh {
                 self.current_step += 1;
@@ -56,4 +77,52 @@ impl Sequencer {
         self.current_step = 0;
         self.prev_gate_value = 0.0;
     }
+
+    pub fn reconfigure(&mut self, _sample_rate: f32, _channel_count: usize) {}
+}
+
+impl Processor for Sequencer {
+    fn buffer_frame_length(&self) -> usize {
+        self.output_buffer.len()
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["gate", "values"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.gate_in_buffer,
+            1 => &mut self.values_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        Sequencer::process(
+            self,
+            frames,
+            block_param!(params, 0, "gateThreshold"),
+            block_param!(params, 1, "numSteps") as usize,
+        );
+    }
+
+    fn reset(&mut self) {
+        Sequencer::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        Sequencer::reconfigure(self, sample_rate, channel_count);
+    }
 }
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    block_param,
+    dsp::{
+        phase_counter::PhaseCounter,
+        random::{Random, DEFAULT_SEED},
+        utils::make_fine_tune_factors,
+        waveforms,
+    },
+    invalid_buffer_index,
+    processor::Processor,
+    utils,
+};
+
+pub const MAX_VOICES: usize = 16;
+const CHANNELS: usize = 2;
+
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.0, max = 20000.0, default = 440.0)]
+#[param(name = "voices", min = 1, max = 16, default = 7, rate = "k-rate")]
+#[param(name = "detune", min = 0, max = 100, default = 20, rate = "k-rate")]
+#[param(name = "spread", min = 0, max = 1, default = 0.5, rate = "k-rate")]
+#[param(name = "centerMix", min = 0, max = 1, default = 0.5, rate = "k-rate")]
+#[param(name = "waveform", min = 0, max = 1, default = 0, rate = "k-rate")]
+#[param(name = "phaseReset", min = 0, max = 1, default = 0, rate = "k-rate")]
+pub struct UnisonOscillator {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+    sample_rate_inv: f32,
+
+    // voice 0 is the center voice, the others are detuned around it
+    phase_counters: Vec<PhaseCounter>,
+    fine_tune_factors: HashMap<i32, f32>,
+    rng: Random,
+    last_gate: f32,
+
+    // parameter buffers
+    #[io_buffer]
+    frequency_buffer: Vec<f32>,
+
+    // IO buffers
+    // 1V/oct: the frequency is scaled by `2^pitch`
+    #[io_buffer]
+    pitch_buffer: Vec<f32>,
+    // A rising zero crossing resets the voice phases.
+    #[io_buffer]
+    gate_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl UnisonOscillator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32) -> UnisonOscillator {
+        utils::set_panic_hook();
+
+        let mut oscillator = UnisonOscillator {
+            buffer_frame_length,
+            sample_rate,
+            sample_rate_inv: 1.0 / sample_rate,
+
+            phase_counters: (0..MAX_VOICES).map(|_| PhaseCounter::new()).collect(),
+            fine_tune_factors: make_fine_tune_factors(),
+            rng: Random::new(DEFAULT_SEED),
+            last_gate: 0.0,
+
+            frequency_buffer: vec![0.0; buffer_frame_length],
+
+            pitch_buffer: vec![0.0; buffer_frame_length],
+            gate_buffer: vec![0.0; buffer_frame_length],
+            output_buffer: vec![0.0; buffer_frame_length * CHANNELS],
+        };
+        oscillator.reset();
+        oscillator
+    }
+
+    /// Mixes `voices` copies of `waveform`, the side voices spread evenly
+    /// over +/- `detune` cents and panned across the stereo field by
+    /// `spread`. `center_mix` crossfades from the side voices (0) to the
+    /// center voice (1).
+    #[allow(clippy::too_many_arguments)]
+    pub fn process(
+        &mut self,
+        frames: usize,
+        voices: u8,
+        detune: i32,
+        spread: f32,
+        center_mix: f32,
+        waveform: u8,
+        phase_reset: u8,
+    ) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        let voices = (voices as usize).clamp(1, MAX_VOICES);
+        let waveform = UnisonWaveform::from(waveform);
+        let phase_reset = UnisonPhaseReset::from(phase_reset);
+
+        let mut ratios = [1.0; MAX_VOICES];
+        let mut left_gains = [0.0; MAX_VOICES];
+        let mut right_gains = [0.0; MAX_VOICES];
+
+        let sides = voices - 1;
+        let (center_gain, side_gain) = if sides == 0 {
+            (1.0, 0.0)
+        } else {
+            // keep the level of the uncorrelated side voices independent of
+            // their number
+            (center_mix, (1.0 - center_mix) / (sides as f32).sqrt())
+        };
+        left_gains[0] = center_gain * FRAC_PI_4.cos();
+        right_gains[0] = center_gain * FRAC_PI_4.sin();
+        for side in 0..sides {
+            // from -1 (lowest, left) to 1 (highest, right)
+            let position = if sides == 1 {
+                1.0
+            } else {
+                2.0 * side as f32 / (sides - 1) as f32 - 1.0
+            };
+            let cents = (position * detune.clamp(0, 100) as f32).round() as i32;
+            ratios[side + 1] = self.fine_tune_factors[&cents];
+
+            let pan = (spread * position + 1.0) * FRAC_PI_4;
+            left_gains[side + 1] = side_gain * pan.cos();
+            right_gains[side + 1] = side_gain * pan.sin();
+        }
+
+        let nyquist = 0.5 * self.sample_rate;
+        let (left, right) = self.output_buffer.split_at_mut(self.buffer_frame_length);
+
+        for n in 0..frames {
+            let gate = self.gate_buffer[n];
+            if self.last_gate <= 0.0 && gate > 0.0 {
+                for phase_counter in self.phase_counters.iter_mut() {
+                    match phase_reset {
+                        UnisonPhaseReset::Random => phase_counter.set_phase(self.rng.next_f32()),
+                        UnisonPhaseReset::Fixed => phase_counter.reset(),
+                    }
+                }
+            }
+            self.last_gate = gate;
+
+            let frequency = self.frequency_buffer[n] * self.pitch_buffer[n].exp2();
+
+            let mut y_left = 0.0;
+            let mut y_right = 0.0;
+            for voice in 0..voices {
+                let increment =
+                    (frequency * ratios[voice]).clamp(0.0, nyquist) * self.sample_rate_inv;
+                let arg = self.phase_counters[voice].advance(increment);
+                let y = match waveform {
+                    UnisonWaveform::Saw => waveforms::saw(arg, increment),
+                    UnisonWaveform::Square => waveforms::square(arg, increment),
+                };
+                y_left += left_gains[voice] * y;
+                y_right += right_gains[voice] * y;
+            }
+            left[n] = y_left;
+            right[n] = y_right;
+        }
+    }
+
+    /// Restarts the random phases from `seed`.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng.set_seed(seed);
+        self.reset();
+    }
+
+    /// Scatters the voices over random phases, so they do not start out in
+    /// unison.
+    pub fn reset(&mut self) {
+        self.rng.reset();
+        for phase_counter in self.phase_counters.iter_mut() {
+            phase_counter.set_phase(self.rng.next_f32());
+        }
+        self.last_gate = 0.0;
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.sample_rate_inv = 1.0 / sample_rate;
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum UnisonWaveform {
+    Saw,
+    Square,
+}
+
+impl From<u8> for UnisonWaveform {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => UnisonWaveform::Square,
+            _ => UnisonWaveform::Saw,
+        }
+    }
+}
+
+/// Where the voices restart on a rising gate.
+#[derive(Copy, Clone)]
+pub enum UnisonPhaseReset {
+    Random,
+    Fixed,
+}
+
+impl From<u8> for UnisonPhaseReset {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => UnisonPhaseReset::Fixed,
+            _ => UnisonPhaseReset::Random,
+        }
+    }
+}
+
+impl Processor for UnisonOscillator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "pitch", "gate"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.pitch_buffer,
+            2 => &mut self.gate_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        UnisonOscillator::process(
+            self,
+            frames,
+            block_param!(params, 0, "voices") as u8,
+            block_param!(params, 1, "detune") as i32,
+            block_param!(params, 2, "spread"),
+            block_param!(params, 3, "centerMix"),
+            block_param!(params, 4, "waveform") as u8,
+            block_param!(params, 5, "phaseReset") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        UnisonOscillator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        UnisonOscillator::reconfigure(self, sample_rate, channel_count);
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        UnisonOscillator::set_seed(self, seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_phase_reset_aligns_voices() {
+        let mut oscillator = UnisonOscillator::new(128, 48000.0);
+        oscillator.frequency_buffer.fill(100.0);
+        oscillator.gate_buffer[64..].fill(1.0);
+        oscillator.process(128, 8, 0, 0.0, 0.5, 0, UnisonPhaseReset::Fixed as u8);
+
+        // without detune, the aligned saws rise from 0 in lockstep
+        let increment = 100.0 / 48000.0;
+        let (left, right) = oscillator.output_buffer.split_at(128);
+        assert!(left[63].abs() > 0.01);
+        assert!(left[64].abs() < 1e-6);
+        assert!(left[65] > 0.0 && left[65] < 4.0 * increment);
+        assert_eq!(left[64..], right[64..]);
+    }
+}
@@ -0,0 +1,275 @@
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    block_param,
+    dsp::{phase_counter::PhaseCounter, smoothed_value::SmoothedValue, waveforms},
+    invalid_buffer_index, linear_smoothed_value,
+    processor::Processor,
+    utils,
+};
+
+const MIN_PULSE_WIDTH: f32 = 0.01;
+const MAX_PULSE_WIDTH: f32 = 0.99;
+
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.0, max = 20000.0, default = 440.0)]
+#[param(name = "pulseWidth", min = 0.01, max = 0.99, default = 0.5)]
+#[param(
+    name = "fmDepth",
+    min = 0.0,
+    max = 20000.0,
+    default = 0.0,
+    rate = "k-rate"
+)]
+#[param(name = "waveform", min = 0, max = 3, default = 0, rate = "k-rate")]
+pub struct VCO {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+    sample_rate_inv: f32,
+
+    phase_counter: PhaseCounter,
+    last_sync: f32,
+
+    fm_depth: SmoothedValue,
+
+    // parameter buffers
+    #[io_buffer]
+    frequency_buffer: Vec<f32>,
+    #[io_buffer]
+    pulse_width_buffer: Vec<f32>,
+
+    // IO buffers
+    // 1V/oct: the frequency is scaled by `2^pitch`.
+    #[io_buffer]
+    pitch_buffer: Vec<f32>,
+    // Linear FM, added to the frequency after scaling by the FM depth (Hz).
+    #[io_buffer]
+    fm_buffer: Vec<f32>,
+    // Added to the pulse width.
+    #[io_buffer]
+    pwm_buffer: Vec<f32>,
+    // A rising zero crossing restarts the cycle.
+    #[io_buffer]
+    sync_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl VCO {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32) -> VCO {
+        utils::set_panic_hook();
+
+        VCO {
+            buffer_frame_length,
+            sample_rate,
+            sample_rate_inv: 1.0 / sample_rate,
+
+            phase_counter: PhaseCounter::new(),
+            last_sync: 0.0,
+
+            fm_depth: linear_smoothed_value!(0.0, sample_rate, 0.05),
+
+            frequency_buffer: vec![0.0; buffer_frame_length],
+            pulse_width_buffer: vec![0.5; buffer_frame_length],
+
+            pitch_buffer: vec![0.0; buffer_frame_length],
+            fm_buffer: vec![0.0; buffer_frame_length],
+            pwm_buffer: vec![0.0; buffer_frame_length],
+            sync_buffer: vec![0.0; buffer_frame_length],
+            output_buffer: vec![0.0; buffer_frame_length],
+        }
+    }
+
+    pub fn process(&mut self, frames: usize, fm_depth: f32, waveform: u8) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        self.fm_depth.set_target_value(fm_depth);
+        let waveform = VCOWaveform::from(waveform);
+
+        let nyquist = 0.5 * self.sample_rate;
+
+        for n in 0..frames {
+            let frequency = self.frequency_buffer[n] * self.pitch_buffer[n].exp2()
+                + self.fm_depth.get_next_value() * self.fm_buffer[n];
+            let increment = frequency.clamp(0.0, nyquist) * self.sample_rate_inv;
+
+            let width = (self.pulse_width_buffer[n] + self.pwm_buffer[n])
+                .clamp(MIN_PULSE_WIDTH, MAX_PULSE_WIDTH);
+
+            let sync = self.sync_buffer[n];
+            // polyBLEP correction of this sample for the sync step
+            let mut sync_blep = 0.0;
+            if self.last_sync <= 0.0 && sync > 0.0 {
+                // restart the cycle where the sync signal crossed zero
+                // between the two samples
+                let crossing = self.last_sync / (self.last_sync - sync);
+                let reset_arg =
+                    (self.phase_counter.phase() - (1.0 - crossing) * increment + 1.0).fract();
+                let before = waveform.naive(reset_arg, width);
+                let after = waveform.naive(0.0, width);
+                let cycle_end = waveform.naive(1.0 - f32::EPSILON, width);
+                // the previous block is out already, so a step right at its
+                // start only gets the half after it
+                if n > 0 {
+                    self.output_buffer[n - 1] +=
+                        0.5 * (after - before) * (1.0 - crossing) * (1.0 - crossing);
+                }
+                // the waveform's own correction already covers the step from
+                // the end of a cycle into the next one
+                sync_blep = -0.5 * (cycle_end - before) * crossing * crossing;
+                self.phase_counter.set_phase((1.0 - crossing) * increment);
+            }
+            self.last_sync = sync;
+
+            let arg = self.phase_counter.advance(increment);
+
+            self.output_buffer[n] = waveform.render(arg, increment, width) + sync_blep;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase_counter.reset();
+        self.last_sync = 0.0;
+        self.fm_depth.reset();
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.sample_rate_inv = 1.0 / sample_rate;
+        self.fm_depth.set_sample_rate(sample_rate);
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum VCOWaveform {
+    Sine,
+    Triangle,
+    Saw,
+    Pulse,
+}
+
+impl VCOWaveform {
+    #[inline]
+    fn render(self, arg: f32, increment: f32, width: f32) -> f32 {
+        match self {
+            VCOWaveform::Sine => waveforms::sine(arg),
+            VCOWaveform::Triangle => waveforms::triangle(arg, increment),
+            VCOWaveform::Saw => waveforms::saw(arg, increment),
+            VCOWaveform::Pulse => waveforms::pulse(arg, increment, width),
+        }
+    }
+
+    /// Value at `arg` without any band-limiting.
+    #[inline]
+    fn naive(self, arg: f32, width: f32) -> f32 {
+        self.render(arg, 0.0, width)
+    }
+}
+
+impl From<u8> for VCOWaveform {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => VCOWaveform::Triangle,
+            2 => VCOWaveform::Saw,
+            3 => VCOWaveform::Pulse,
+            _ => VCOWaveform::Sine,
+        }
+    }
+}
+
+impl Processor for VCO {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "pulse_width", "pitch", "fm", "pwm", "sync"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.pulse_width_buffer,
+            2 => &mut self.pitch_buffer,
+            3 => &mut self.fm_buffer,
+            4 => &mut self.pwm_buffer,
+            5 => &mut self.sync_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, params: &[f32]) {
+        VCO::process(
+            self,
+            frames,
+            block_param!(params, 0, "fmDepth"),
+            block_param!(params, 1, "waveform") as u8,
+        );
+    }
+
+    fn reset(&mut self) {
+        VCO::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        VCO::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_sync_restarts_cycle() {
+        let mut vco = VCO::new(128, 48000.0);
+        vco.frequency_buffer.fill(100.0);
+        vco.sync_buffer.fill(-1.0);
+        vco.sync_buffer[64] = 1.0;
+        vco.process(128, 0.0, VCOWaveform::Saw as u8);
+
+        // the saw starts its cycle at 0 and rises by 2 * increment per sample
+        let increment = 100.0 / 48000.0;
+        // half way between the samples, the step from the saw's value then
+        // is spread over both
+        let step = -2.0 * 63.5 * increment;
+        let blep = 0.5 * step * 0.25;
+        assert!((vco.output_buffer[62] - 2.0 * 62.0 * increment).abs() < 1e-4);
+        assert!((vco.output_buffer[63] - (2.0 * 63.0 * increment + blep)).abs() < 1e-4);
+        assert!((vco.output_buffer[64] - (increment - blep)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_hard_sync_pulse_step_is_band_limited() {
+        let mut vco = VCO::new(128, 48000.0);
+        vco.frequency_buffer.fill(100.0);
+        vco.sync_buffer.fill(-1.0);
+        vco.process(128, 0.0, VCOWaveform::Pulse as u8);
+        vco.process(128, 0.0, VCOWaveform::Pulse as u8);
+
+        // a reset in the low half of the cycle steps from -1 up to 1, three
+        // quarters of the way from sample 19 to 20
+        vco.sync_buffer[19] = -3.0;
+        vco.sync_buffer[20] = 1.0;
+        vco.process(128, 0.0, VCOWaveform::Pulse as u8);
+        assert_eq!(vco.output_buffer[18], -1.0);
+        assert!((vco.output_buffer[19] - (-1.0 + 0.25 * 0.25)).abs() < 1e-4);
+        assert!((vco.output_buffer[20] - (1.0 - 0.75 * 0.75)).abs() < 1e-4);
+        assert_eq!(vco.output_buffer[21], 1.0);
+    }
+}
@@ -0,0 +1,175 @@
+use wasm_bindgen::prelude::*;
+use wasm_utils::{IOBufferPtrs, ParameterDescriptors};
+
+use crate::{
+    dsp::{phase_counter::PhaseCounter, wavetable::Wavetable},
+    invalid_buffer_index,
+    processor::Processor,
+    utils,
+};
+
+#[wasm_bindgen]
+#[derive(IOBufferPtrs, ParameterDescriptors)]
+#[param(name = "frequency", min = 0.0, max = 20000.0, default = 440.0)]
+#[param(name = "position", min = 0.0, max = 1.0, default = 0.0)]
+pub struct WavetableOscillator {
+    buffer_frame_length: usize,
+    sample_rate: f32,
+    sample_rate_inv: f32,
+
+    phase_counter: PhaseCounter,
+    wavetable: Wavetable,
+
+    // parameter buffers
+    #[io_buffer]
+    frequency_buffer: Vec<f32>,
+    #[io_buffer]
+    position_buffer: Vec<f32>,
+
+    // IO buffers
+    // 1V/oct: the frequency is scaled by `2^pitch`
+    #[io_buffer]
+    pitch_buffer: Vec<f32>,
+    #[io_buffer]
+    output_buffer: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl WavetableOscillator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(buffer_frame_length: usize, sample_rate: f32) -> WavetableOscillator {
+        utils::set_panic_hook();
+
+        WavetableOscillator {
+            buffer_frame_length,
+            sample_rate,
+            sample_rate_inv: 1.0 / sample_rate,
+
+            phase_counter: PhaseCounter::new(),
+            wavetable: Wavetable::new(),
+
+            frequency_buffer: vec![0.0; buffer_frame_length],
+            position_buffer: vec![0.0; buffer_frame_length],
+
+            pitch_buffer: vec![0.0; buffer_frame_length],
+            output_buffer: vec![0.0; buffer_frame_length],
+        }
+    }
+
+    /// Replaces the wavetable with the single-cycle frames in `table`, each
+    /// `frame_size` samples long. Their storage is allocated here, they are
+    /// built one frame per block and the current table keeps playing until
+    /// they are complete. Returns
+    /// `false` if `table` does not hold between 1 and 256 whole frames.
+    pub fn load_table(&mut self, table: Vec<f32>, frame_size: usize) -> bool {
+        self.wavetable.load(table, frame_size)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.wavetable.frame_count()
+    }
+
+    pub fn process(&mut self, frames: usize) {
+        let frames = frames.min(self.buffer_frame_length);
+
+        self.wavetable.build_step();
+
+        let nyquist = 0.5 * self.sample_rate;
+
+        for n in 0..frames {
+            let frequency = self.frequency_buffer[n] * self.pitch_buffer[n].exp2();
+            let increment = frequency.min(nyquist) * self.sample_rate_inv;
+
+            let arg = self.phase_counter.advance(increment);
+            self.output_buffer[n] = self.wavetable.read(arg, self.position_buffer[n], increment);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase_counter.reset();
+    }
+
+    pub fn reconfigure(&mut self, sample_rate: f32, _channel_count: usize) {
+        self.sample_rate = sample_rate;
+        self.sample_rate_inv = 1.0 / sample_rate;
+    }
+}
+
+impl Processor for WavetableOscillator {
+    fn buffer_frame_length(&self) -> usize {
+        self.buffer_frame_length
+    }
+
+    fn inputs(&self) -> &'static [&'static str] {
+        &["frequency", "position", "pitch"]
+    }
+
+    fn outputs(&self) -> &'static [&'static str] {
+        &["output"]
+    }
+
+    fn input_buffer(&mut self, index: usize) -> &mut [f32] {
+        match index {
+            0 => &mut self.frequency_buffer,
+            1 => &mut self.position_buffer,
+            2 => &mut self.pitch_buffer,
+            _ => invalid_buffer_index!("input", index),
+        }
+    }
+
+    fn output_buffer(&self, index: usize) -> &[f32] {
+        match index {
+            0 => &self.output_buffer,
+            _ => invalid_buffer_index!("output", index),
+        }
+    }
+
+    fn process(&mut self, frames: usize, _params: &[f32]) {
+        WavetableOscillator::process(self, frames);
+    }
+
+    fn reset(&mut self) {
+        WavetableOscillator::reset(self);
+    }
+
+    fn reconfigure(&mut self, sample_rate: f32, channel_count: usize) {
+        WavetableOscillator::reconfigure(self, sample_rate, channel_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // upward zero crossings of one second of the sine table
+    fn count_cycles(oscillator: &mut WavetableOscillator) -> usize {
+        let mut cycles = 0;
+        let mut previous = 0.0;
+        for _ in 0..375 {
+            WavetableOscillator::process(oscillator, 128);
+            for y in oscillator.output_buffer.iter() {
+                if previous < 0.0 && *y >= 0.0 {
+                    cycles += 1;
+                }
+                previous = *y;
+            }
+        }
+        cycles
+    }
+
+    #[test]
+    fn test_pitch_tracks_one_volt_per_octave() {
+        for (pitch, expected) in [(0.0, 220), (1.0, 440), (-1.0, 110), (0.5, 311)] {
+            let mut oscillator = WavetableOscillator::new(128, 48000.0);
+            oscillator.frequency_buffer.fill(220.0);
+            oscillator.pitch_buffer.fill(pitch);
+            let cycles = count_cycles(&mut oscillator);
+            assert!(
+                (cycles as i32 - expected).abs() <= 1,
+                "{}: {} cycles",
+                pitch,
+                cycles
+            );
+        }
+    }
+}
@@ -1,8 +1,14 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{Data, DeriveInput, Fields, Type, parse_macro_input};
+use syn::{
+    Attribute, Data, DeriveInput, Fields, Lit, LitStr, Token, Type, parse::ParseStream,
+    parse_macro_input,
+};
 
+/// Generates `#[wasm_bindgen]` pointer accessors for every `#[io_buffer]` field:
+/// `Vec<f32>` fields get `<field>_ptr()`, `[Vec<f32>; N]` and `Vec<Vec<f32>>`
+/// fields get a bounds-checked `<field>_ptr(index)`.
 #[proc_macro_derive(IOBufferPtrs, attributes(io_buffer))]
 pub fn derive_io_buffer_ptrs(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -11,43 +17,49 @@ pub fn derive_io_buffer_ptrs(input: TokenStream) -> TokenStream {
     let mut ptr_methods = Vec::new();
     let mut errors = Vec::new();
 
-    if let Data::Struct(data_struct) = input.data {
-        if let Fields::Named(fields_named) = data_struct.fields {
-            for field in fields_named.named.iter() {
-                let attrs = &field.attrs;
-                let is_io_buffer = attrs.iter().any(|a| a.path().is_ident("io_buffer"));
-                if is_io_buffer {
-                    let field_name = field.ident.as_ref().unwrap();
-                    let method_name = format_ident!("{}_ptr", field_name);
-                    
-                    // Check if the field is Vec<f32> and generate error if not
-                    if let Type::Path(type_path) = &field.ty {
-                        if type_path.path.segments.last().unwrap().ident == "Vec" {
-                            // Check if it's Vec<f32> specifically
-                            if let syn::PathArguments::AngleBracketed(args) = &type_path.path.segments.last().unwrap().arguments {
-                                if let syn::GenericArgument::Type(Type::Path(inner_type)) = &args.args[0] {
-                                    if inner_type.path.segments.last().unwrap().ident == "f32" {
-                                        ptr_methods.push(quote! {
-                                            pub fn #method_name(&mut self) -> *mut f32 {
-                                                self.#field_name.as_mut_ptr()
-                                            }
-                                        });
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
+    if let Data::Struct(data_struct) = input.data
+        && let Fields::Named(fields_named) = data_struct.fields
+    {
+        for field in fields_named.named.iter() {
+            let is_io_buffer = field.attrs.iter().any(|a| a.path().is_ident("io_buffer"));
+            if !is_io_buffer {
+                continue;
+            }
+
+            let field_name = field.ident.as_ref().unwrap();
+            let method_name = format_ident!("{}_ptr", field_name);
+
+            if is_f32_vec(&field.ty) {
+                ptr_methods.push(quote! {
+                    pub fn #method_name(&mut self) -> *mut f32 {
+                        self.#field_name.as_mut_ptr()
+                    }
+                });
+            } else if is_f32_vec_array(&field.ty) {
+                ptr_methods.push(quote! {
+                    pub fn #method_name(&mut self, index: usize) -> *mut f32 {
+                        let len = self.#field_name.len();
+                        assert!(
+                            index < len,
+                            "{} index {} out of range (len {})",
+                            stringify!(#field_name),
+                            index,
+                            len
+                        );
+                        self.#field_name[index].as_mut_ptr()
                     }
-                    
-                    // If we reach here, the field has io_buffer attribute but is not Vec<f32>
-                    let field_type = quote! { #field.ty };
-                    errors.push(quote! {
-                        compile_error!(concat!(
-                            "Field '", stringify!(#field_name), "' has io_buffer attribute but is not of type Vec<f32>. ",
-                            "Found type: ", stringify!(#field_type)
-                        ));
-                    });
-                }
+                });
+            } else {
+                errors.push(
+                    syn::Error::new_spanned(
+                        &field.ty,
+                        format!(
+                            "`#[io_buffer]` field `{}` must be of type `Vec<f32>`, `[Vec<f32>; N]` or `Vec<Vec<f32>>`",
+                            field_name
+                        ),
+                    )
+                    .to_compile_error(),
+                );
             }
         }
     }
@@ -57,10 +69,206 @@ pub fn derive_io_buffer_ptrs(input: TokenStream) -> TokenStream {
         impl #struct_name {
             #(#ptr_methods)*
         }
-        
-        // Generate compilation errors for invalid io_buffer attributes
+
         #(#errors)*
     };
 
     TokenStream::from(expanded)
 }
+
+/// Inner type of `Vec<T>`, if `ty` is a `Vec`.
+fn vec_element(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Vec" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+fn is_f32_vec(ty: &Type) -> bool {
+    matches!(vec_element(ty), Some(Type::Path(inner)) if inner.path.is_ident("f32"))
+}
+
+fn is_f32_vec_array(ty: &Type) -> bool {
+    match ty {
+        Type::Array(array) => is_f32_vec(&array.elem),
+        _ => vec_element(ty).is_some_and(is_f32_vec),
+    }
+}
+
+struct ParamAttr {
+    name: String,
+    min: Option<f64>,
+    max: Option<f64>,
+    default: f64,
+    rate: Option<String>,
+}
+
+/// Declares the AudioParams of a processor once, on the Rust struct:
+/// `#[param(name = "cutoff", min = 20.0, max = 20480.0, default = 1000.0, rate = "a-rate")]`.
+/// `min`, `max` and `rate` are optional and fall back to the Web Audio defaults.
+///
+/// Generates a `PARAMETERS` constant for Rust callers and a static
+/// `parameter_descriptors()` returning the JSON for the worklet's
+/// `parameterDescriptors` getter.
+#[proc_macro_derive(ParameterDescriptors, attributes(param))]
+pub fn derive_parameter_descriptors(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let struct_name = input.ident;
+
+    let mut params = Vec::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("param")) {
+        match parse_param_attr(attr) {
+            Ok(param) => params.push(param),
+            Err(err) => return TokenStream::from(err.to_compile_error()),
+        }
+    }
+
+    let json = format!(
+        "[{}]",
+        params
+            .iter()
+            .map(param_json)
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+
+    let number = |value: f64| {
+        let abs = value.abs() as f32;
+        if value < 0.0 {
+            quote! { -#abs }
+        } else {
+            quote! { #abs }
+        }
+    };
+
+    let descriptors = params.iter().map(|param| {
+        let name = &param.name;
+        let default_value = number(param.default);
+        let min_value = match param.min {
+            Some(min) => number(min),
+            None => quote! { f32::MIN },
+        };
+        let max_value = match param.max {
+            Some(max) => number(max),
+            None => quote! { f32::MAX },
+        };
+        let automation_rate = match param.rate.as_deref() {
+            Some("k-rate") => quote! { crate::processor::AutomationRate::KRate },
+            _ => quote! { crate::processor::AutomationRate::ARate },
+        };
+        quote! {
+            crate::processor::ParamDescriptor {
+                name: #name,
+                default_value: #default_value,
+                min_value: #min_value,
+                max_value: #max_value,
+                automation_rate: #automation_rate,
+            }
+        }
+    });
+
+    let expanded = quote! {
+        impl #struct_name {
+            pub const PARAMETERS: &'static [crate::processor::ParamDescriptor] = &[
+                #(#descriptors),*
+            ];
+        }
+
+        #[wasm_bindgen]
+        impl #struct_name {
+            pub fn parameter_descriptors() -> String {
+                String::from(#json)
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+fn parse_param_attr(attr: &Attribute) -> syn::Result<ParamAttr> {
+    let mut name = None;
+    let mut min = None;
+    let mut max = None;
+    let mut default = None;
+    let mut rate = None;
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("name") {
+            let value: LitStr = meta.value()?.parse()?;
+            name = Some(value.value());
+        } else if meta.path.is_ident("min") {
+            min = Some(parse_number(meta.value()?)?);
+        } else if meta.path.is_ident("max") {
+            max = Some(parse_number(meta.value()?)?);
+        } else if meta.path.is_ident("default") {
+            default = Some(parse_number(meta.value()?)?);
+        } else if meta.path.is_ident("rate") {
+            let value: LitStr = meta.value()?.parse()?;
+            if value.value() != "a-rate" && value.value() != "k-rate" {
+                return Err(syn::Error::new_spanned(
+                    value,
+                    "rate must be \"a-rate\" or \"k-rate\"",
+                ));
+            }
+            rate = Some(value.value());
+        } else {
+            return Err(meta.error("expected one of `name`, `min`, `max`, `default`, `rate`"));
+        }
+        Ok(())
+    })?;
+
+    let Some(name) = name else {
+        return Err(syn::Error::new_spanned(attr, "param is missing `name`"));
+    };
+    let Some(default) = default else {
+        return Err(syn::Error::new_spanned(attr, "param is missing `default`"));
+    };
+
+    Ok(ParamAttr {
+        name,
+        min,
+        max,
+        default,
+        rate,
+    })
+}
+
+fn parse_number(input: ParseStream) -> syn::Result<f64> {
+    let negative = input.peek(Token![-]);
+    if negative {
+        input.parse::<Token![-]>()?;
+    }
+    let value = match input.parse::<Lit>()? {
+        Lit::Float(lit) => lit.base10_parse::<f64>()?,
+        Lit::Int(lit) => lit.base10_parse::<f64>()?,
+        lit => return Err(syn::Error::new_spanned(lit, "expected a number")),
+    };
+    Ok(if negative { -value } else { value })
+}
+
+fn param_json(param: &ParamAttr) -> String {
+    let mut fields = vec![
+        format!("\"name\":{:?}", param.name),
+        format!("\"defaultValue\":{}", param.default),
+    ];
+    if let Some(min) = param.min {
+        fields.push(format!("\"minValue\":{}", min));
+    }
+    if let Some(max) = param.max {
+        fields.push(format!("\"maxValue\":{}", max));
+    }
+    if let Some(rate) = &param.rate {
+        fields.push(format!("\"automationRate\":{:?}", rate));
+    }
+    format!("{{{}}}", fields.join(","))
+}